@@ -0,0 +1,181 @@
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+// timed with the time stamp counter, so the numbers are reference cycles
+#define RUNS 64
+
+typedef uint64_t (*Benchmark)(size_t size);
+
+static char *haystack;
+static char *other;
+static char *needle;
+static volatile size_t sink;
+
+static uint64_t cycles(void) { return __builtin_ia32_rdtsc(); }
+
+// byte-at-a-time versions of each routine, which is what libkns used to do
+
+__attribute__((noinline)) static size_t naive_strlen(const char *s) {
+  size_t n = 0;
+
+  while (s[n] != '\0') {
+    ++n;
+  }
+
+  return n;
+}
+
+__attribute__((noinline)) static const void *
+naive_memchr(const void *s, int c, size_t n) {
+  const unsigned char *p = s;
+
+  for (size_t i = 0; i < n; ++i) {
+    if (p[i] == (unsigned char)c) {
+      return p + i;
+    }
+  }
+
+  return NULL;
+}
+
+__attribute__((noinline)) static int naive_memcmp(const void *s1,
+                                                  const void *s2, size_t n) {
+  const unsigned char *l = s1;
+  const unsigned char *r = s2;
+
+  for (size_t i = 0; i < n; ++i) {
+    if (l[i] != r[i]) {
+      return l[i] - r[i];
+    }
+  }
+
+  return 0;
+}
+
+__attribute__((noinline)) static const char *naive_strchr(const char *s,
+                                                          int c) {
+  for (;; ++s) {
+    if (*s == (char)c) {
+      return s;
+    } else if (*s == '\0') {
+      return NULL;
+    }
+  }
+}
+
+__attribute__((noinline)) static int naive_strcmp(const char *s1,
+                                                  const char *s2) {
+  while (*s1 != '\0' && *s1 == *s2) {
+    ++s1;
+    ++s2;
+  }
+
+  return (unsigned char)*s1 - (unsigned char)*s2;
+}
+
+__attribute__((noinline)) static const char *naive_strstr(const char *h,
+                                                          const char *n) {
+  for (; *h != '\0'; ++h) {
+    size_t i = 0;
+
+    while (n[i] != '\0' && h[i] == n[i]) {
+      ++i;
+    }
+
+    if (n[i] == '\0') {
+      return h;
+    }
+  }
+
+  return NULL;
+}
+
+// every input is size bytes of 'a' followed by a terminator, so nothing
+// matches until the end
+
+#define BENCHMARK(name, call)                                                  \
+  static uint64_t name(size_t size) {                                          \
+    uint64_t best = (uint64_t)-1;                                              \
+                                                                               \
+    (void)size;                                                                \
+                                                                               \
+    for (int i = 0; i < RUNS; ++i) {                                           \
+      uint64_t start = cycles();                                               \
+      sink = (size_t)(call);                                                   \
+      uint64_t elapsed = cycles() - start;                                     \
+                                                                               \
+      if (elapsed < best) {                                                    \
+        best = elapsed;                                                        \
+      }                                                                        \
+    }                                                                          \
+                                                                               \
+    return best;                                                               \
+  }
+
+BENCHMARK(bench_strlen, strlen(haystack))
+BENCHMARK(bench_naive_strlen, naive_strlen(haystack))
+BENCHMARK(bench_memchr, memchr(haystack, 'b', size))
+BENCHMARK(bench_naive_memchr, naive_memchr(haystack, 'b', size))
+BENCHMARK(bench_memcmp, memcmp(haystack, other, size))
+BENCHMARK(bench_naive_memcmp, naive_memcmp(haystack, other, size))
+BENCHMARK(bench_strchr, strchr(haystack, 'b'))
+BENCHMARK(bench_naive_strchr, naive_strchr(haystack, 'b'))
+BENCHMARK(bench_strcmp, strcmp(haystack, other))
+BENCHMARK(bench_naive_strcmp, naive_strcmp(haystack, other))
+BENCHMARK(bench_strstr, strstr(haystack, needle))
+BENCHMARK(bench_naive_strstr, naive_strstr(haystack, needle))
+
+static void compare(const char *name, Benchmark fast, Benchmark naive) {
+  static const size_t SIZES[] = {16, 256, 4096, 65536};
+
+  for (size_t i = 0; i < sizeof(SIZES) / sizeof(SIZES[0]); ++i) {
+    size_t size = SIZES[i];
+
+    memset(haystack, 'a', size);
+    haystack[size] = '\0';
+    memset(other, 'a', size);
+    other[size] = '\0';
+
+    uint64_t fast_cycles = fast(size);
+    uint64_t naive_cycles = naive(size);
+
+    printf("%-8s %6zu bytes: %8llu cycles, naive %9llu cycles, %6.1fx\n", name,
+           size, (unsigned long long)fast_cycles,
+           (unsigned long long)naive_cycles,
+           (double)naive_cycles / (double)fast_cycles);
+  }
+}
+
+int main(void) {
+  // the worst case for naive search: almost every position matches all but
+  // the last byte of the needle
+  static const size_t NEEDLE_SIZE = 64;
+
+  haystack = malloc(65536 + 1);
+  other = malloc(65536 + 1);
+  needle = malloc(NEEDLE_SIZE + 1);
+
+  if (!haystack || !other || !needle) {
+    perror("malloc");
+
+    return EXIT_FAILURE;
+  }
+
+  memset(needle, 'a', NEEDLE_SIZE - 1);
+  needle[NEEDLE_SIZE - 1] = 'b';
+  needle[NEEDLE_SIZE] = '\0';
+
+  compare("strlen", bench_strlen, bench_naive_strlen);
+  compare("memchr", bench_memchr, bench_naive_memchr);
+  compare("memcmp", bench_memcmp, bench_naive_memcmp);
+  compare("strchr", bench_strchr, bench_naive_strchr);
+  compare("strcmp", bench_strcmp, bench_naive_strcmp);
+  compare("strstr", bench_strstr, bench_naive_strstr);
+
+  free(needle);
+  free(other);
+  free(haystack);
+}
@@ -3,7 +3,13 @@ use std::{env, fs, path::Path, process::Command};
 use cc::Build;
 
 fn main() {
-    const FILES: &[&str] = &["string/memcpy", "string/memmove", "string/memset"];
+    const FILES: &[&str] = &[
+        "signal/restore",
+        "string/memcpy",
+        "string/memmove",
+        "string/memset",
+        "unistd/vfork",
+    ];
 
     let out_dir = env::var("OUT_DIR").unwrap();
 
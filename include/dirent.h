@@ -0,0 +1,67 @@
+#ifndef __KNS_DIRENT_H
+#define __KNS_DIRENT_H
+
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DT_UNKNOWN 0
+#define DT_FIFO 1
+#define DT_CHR 2
+#define DT_DIR 4
+#define DT_BLK 6
+#define DT_REG 8
+#define DT_LNK 10
+#define DT_SOCK 12
+#define DT_WHT 14
+
+typedef struct DIR DIR;
+
+struct dirent {
+  ino_t d_ino;
+  off_t d_off;
+  unsigned short d_reclen;
+  unsigned char d_type;
+  char d_name[256];
+};
+
+extern DIR *opendir(const char *name);
+extern DIR *fdopendir(int fd);
+extern int closedir(DIR *dirp);
+
+extern struct dirent *readdir(DIR *dirp);
+extern int readdir_r(DIR *dirp, struct dirent *entry, struct dirent **result);
+
+extern void rewinddir(DIR *dirp);
+extern void seekdir(DIR *dirp, long loc);
+extern long telldir(DIR *dirp);
+extern int dirfd(DIR *dirp);
+
+extern int scandir(const char *dirp, struct dirent ***namelist,
+                   int (*filter)(const struct dirent *),
+                   int (*compar)(const struct dirent **,
+                                 const struct dirent **));
+extern int alphasort(const struct dirent **a, const struct dirent **b);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif
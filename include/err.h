@@ -0,0 +1,42 @@
+#ifndef __KNS_ERR_H
+#define __KNS_ERR_H
+
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#include <stdarg.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+extern void err(int eval, const char *format, ...) __attribute__((__noreturn__));
+extern void verr(int eval, const char *format, va_list args)
+    __attribute__((__noreturn__));
+extern void errx(int eval, const char *format, ...)
+    __attribute__((__noreturn__));
+extern void verrx(int eval, const char *format, va_list args)
+    __attribute__((__noreturn__));
+
+extern void warn(const char *format, ...);
+extern void vwarn(const char *format, va_list args);
+extern void warnx(const char *format, ...);
+extern void vwarnx(const char *format, va_list args);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif
@@ -0,0 +1,56 @@
+#ifndef __KNS_FTW_H
+#define __KNS_FTW_H
+
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#include <sys/stat.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define FTW_F 0
+#define FTW_D 1
+#define FTW_DNR 2
+#define FTW_NS 3
+#define FTW_SL 4
+#define FTW_DP 5
+#define FTW_SLN 6
+
+#define FTW_PHYS 1
+#define FTW_MOUNT 2
+#define FTW_CHDIR 4
+#define FTW_DEPTH 8
+
+struct FTW {
+  int base;
+  int level;
+};
+
+extern int ftw(const char *dirpath,
+               int (*fn)(const char *fpath, const struct stat *sb,
+                         int typeflag),
+               int nopenfd);
+extern int nftw(const char *dirpath,
+                int (*fn)(const char *fpath, const struct stat *sb,
+                          int typeflag, struct FTW *ftwbuf),
+                int nopenfd, int flags);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif
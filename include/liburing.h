@@ -0,0 +1,276 @@
+#ifndef __KNS_LIBURING_H
+#define __KNS_LIBURING_H
+
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/types.h>
+#include <sys/uio.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define IORING_SETUP_IOPOLL (1U << 0)
+#define IORING_SETUP_SQPOLL (1U << 1)
+#define IORING_SETUP_SQ_AFF (1U << 2)
+#define IORING_SETUP_CQSIZE (1U << 3)
+#define IORING_SETUP_CLAMP (1U << 4)
+#define IORING_SETUP_ATTACH_WQ (1U << 5)
+
+#define IORING_FEAT_SINGLE_MMAP (1U << 0)
+#define IORING_FEAT_NODROP (1U << 1)
+#define IORING_FEAT_SUBMIT_STABLE (1U << 2)
+#define IORING_FEAT_RW_CUR_POS (1U << 3)
+#define IORING_FEAT_CUR_PERSONALITY (1U << 4)
+#define IORING_FEAT_FAST_POLL (1U << 5)
+
+#define IORING_ENTER_GETEVENTS (1U << 0)
+#define IORING_ENTER_SQ_WAKEUP (1U << 1)
+
+#define IORING_SQ_NEED_WAKEUP (1U << 0)
+
+#define IORING_TIMEOUT_ABS (1U << 0)
+
+#define IORING_OFF_SQ_RING 0ULL
+#define IORING_OFF_CQ_RING 0x8000000ULL
+#define IORING_OFF_SQES 0x10000000ULL
+
+#define IOSQE_FIXED_FILE (1U << 0)
+#define IOSQE_IO_DRAIN (1U << 1)
+#define IOSQE_IO_LINK (1U << 2)
+#define IOSQE_IO_HARDLINK (1U << 3)
+#define IOSQE_ASYNC (1U << 4)
+#define IOSQE_BUFFER_SELECT (1U << 5)
+
+enum {
+  IORING_OP_NOP,
+  IORING_OP_READV,
+  IORING_OP_WRITEV,
+  IORING_OP_FSYNC,
+  IORING_OP_READ_FIXED,
+  IORING_OP_WRITE_FIXED,
+  IORING_OP_POLL_ADD,
+  IORING_OP_POLL_REMOVE,
+  IORING_OP_SYNC_FILE_RANGE,
+  IORING_OP_SENDMSG,
+  IORING_OP_RECVMSG,
+  IORING_OP_TIMEOUT,
+  IORING_OP_TIMEOUT_REMOVE,
+  IORING_OP_ACCEPT,
+  IORING_OP_ASYNC_CANCEL,
+  IORING_OP_LINK_TIMEOUT,
+  IORING_OP_CONNECT,
+  IORING_OP_FALLOCATE,
+  IORING_OP_OPENAT,
+  IORING_OP_CLOSE,
+  IORING_OP_FILES_UPDATE,
+  IORING_OP_STATX,
+  IORING_OP_READ,
+  IORING_OP_WRITE,
+  IORING_OP_FADVISE,
+  IORING_OP_MADVISE,
+  IORING_OP_SEND,
+  IORING_OP_RECV,
+  IORING_OP_OPENAT2,
+  IORING_OP_EPOLL_CTL,
+  IORING_OP_SPLICE,
+  IORING_OP_PROVIDE_BUFFERS,
+  IORING_OP_REMOVE_BUFFERS,
+  IORING_OP_TEE,
+
+  IORING_OP_LAST,
+};
+
+struct sockaddr;
+typedef unsigned int socklen_t;
+
+struct __kernel_timespec {
+  long long tv_sec;
+  long long tv_nsec;
+};
+
+struct io_sqring_offsets {
+  uint32_t head;
+  uint32_t tail;
+  uint32_t ring_mask;
+  uint32_t ring_entries;
+  uint32_t flags;
+  uint32_t dropped;
+  uint32_t array;
+  uint32_t resv1;
+  uint64_t resv2;
+};
+
+struct io_cqring_offsets {
+  uint32_t head;
+  uint32_t tail;
+  uint32_t ring_mask;
+  uint32_t ring_entries;
+  uint32_t overflow;
+  uint32_t cqes;
+  uint32_t flags;
+  uint32_t resv1;
+  uint64_t resv2;
+};
+
+struct io_uring_params {
+  uint32_t sq_entries;
+  uint32_t cq_entries;
+  uint32_t flags;
+  uint32_t sq_thread_cpu;
+  uint32_t sq_thread_idle;
+  uint32_t features;
+  uint32_t wq_fd;
+  uint32_t resv[3];
+  struct io_sqring_offsets sq_off;
+  struct io_cqring_offsets cq_off;
+};
+
+struct io_uring_sqe {
+  uint8_t opcode;
+  uint8_t flags;
+  uint16_t ioprio;
+  int32_t fd;
+  union {
+    uint64_t off;
+    uint64_t addr2;
+  };
+  union {
+    uint64_t addr;
+    uint64_t splice_off_in;
+  };
+  uint32_t len;
+  union {
+    int rw_flags;
+    uint32_t fsync_flags;
+    uint16_t poll_events;
+    uint32_t poll32_events;
+    uint32_t sync_range_flags;
+    uint32_t msg_flags;
+    uint32_t timeout_flags;
+    uint32_t accept_flags;
+    uint32_t cancel_flags;
+    uint32_t open_flags;
+    uint32_t statx_flags;
+    uint32_t fadvise_advice;
+    uint32_t splice_flags;
+  };
+  uint64_t user_data;
+  union {
+    struct {
+      union {
+        uint16_t buf_index;
+        uint16_t buf_group;
+      };
+      uint16_t personality;
+      int32_t splice_fd_in;
+    };
+    uint64_t __pad2[3];
+  };
+};
+
+struct io_uring_cqe {
+  uint64_t user_data;
+  int32_t res;
+  uint32_t flags;
+};
+
+struct io_uring_sq {
+  unsigned *khead;
+  unsigned *ktail;
+  unsigned *kring_mask;
+  unsigned *kring_entries;
+  unsigned *kflags;
+  unsigned *kdropped;
+  unsigned *array;
+  struct io_uring_sqe *sqes;
+  unsigned sqe_head;
+  unsigned sqe_tail;
+  size_t ring_sz;
+  void *ring_ptr;
+  unsigned pad[4];
+};
+
+struct io_uring_cq {
+  unsigned *khead;
+  unsigned *ktail;
+  unsigned *kring_mask;
+  unsigned *kring_entries;
+  unsigned *kflags;
+  unsigned *koverflow;
+  struct io_uring_cqe *cqes;
+  size_t ring_sz;
+  void *ring_ptr;
+  unsigned pad[4];
+};
+
+struct io_uring {
+  struct io_uring_sq sq;
+  struct io_uring_cq cq;
+  unsigned flags;
+  int ring_fd;
+  unsigned features;
+  unsigned pad[3];
+};
+
+// like liburing, functions that can fail return -errno instead of setting errno
+
+extern int io_uring_queue_init(unsigned entries, struct io_uring *ring,
+                               unsigned flags);
+extern void io_uring_queue_exit(struct io_uring *ring);
+
+extern struct io_uring_sqe *io_uring_get_sqe(struct io_uring *ring);
+extern int io_uring_submit(struct io_uring *ring);
+extern int io_uring_submit_and_wait(struct io_uring *ring, unsigned wait_nr);
+
+extern int io_uring_peek_cqe(struct io_uring *ring,
+                             struct io_uring_cqe **cqe_ptr);
+extern int io_uring_wait_cqe(struct io_uring *ring,
+                             struct io_uring_cqe **cqe_ptr);
+extern void io_uring_cqe_seen(struct io_uring *ring, struct io_uring_cqe *cqe);
+
+extern void io_uring_sqe_set_data(struct io_uring_sqe *sqe, void *data);
+extern void *io_uring_cqe_get_data(const struct io_uring_cqe *cqe);
+
+extern void io_uring_prep_nop(struct io_uring_sqe *sqe);
+extern void io_uring_prep_read(struct io_uring_sqe *sqe, int fd, void *buf,
+                               unsigned nbytes, uint64_t offset);
+extern void io_uring_prep_write(struct io_uring_sqe *sqe, int fd,
+                                const void *buf, unsigned nbytes,
+                                uint64_t offset);
+extern void io_uring_prep_readv(struct io_uring_sqe *sqe, int fd,
+                                const struct iovec *iovecs, unsigned nr_vecs,
+                                uint64_t offset);
+extern void io_uring_prep_writev(struct io_uring_sqe *sqe, int fd,
+                                 const struct iovec *iovecs, unsigned nr_vecs,
+                                 uint64_t offset);
+extern void io_uring_prep_openat(struct io_uring_sqe *sqe, int dfd,
+                                 const char *path, int flags, mode_t mode);
+extern void io_uring_prep_close(struct io_uring_sqe *sqe, int fd);
+extern void io_uring_prep_accept(struct io_uring_sqe *sqe, int fd,
+                                 struct sockaddr *addr, socklen_t *addrlen,
+                                 int flags);
+extern void io_uring_prep_timeout(struct io_uring_sqe *sqe,
+                                  struct __kernel_timespec *ts, unsigned count,
+                                  unsigned flags);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif
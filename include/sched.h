@@ -16,10 +16,52 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+#include <stddef.h>
+#include <sys/types.h>
+
 #ifdef __cplusplus
 extern "C" {
 #endif
 
+#define CLONE_VM 0x00000100
+#define CLONE_FS 0x00000200
+#define CLONE_FILES 0x00000400
+#define CLONE_SIGHAND 0x00000800
+#define CLONE_VFORK 0x00004000
+#define CLONE_PARENT 0x00008000
+#define CLONE_THREAD 0x00010000
+#define CLONE_SYSVSEM 0x00040000
+#define CLONE_SETTLS 0x00080000
+#define CLONE_PARENT_SETTID 0x00100000
+#define CLONE_CHILD_CLEARTID 0x00200000
+#define CLONE_CHILD_SETTID 0x01000000
+
+#define CPU_SETSIZE 1024
+
+typedef struct {
+  unsigned long __bits[CPU_SETSIZE / (8 * sizeof(unsigned long))];
+} cpu_set_t;
+
+#define __CPU_WORD(cpu) ((cpu) / (8 * sizeof(unsigned long)))
+#define __CPU_MASK(cpu) (1UL << ((cpu) % (8 * sizeof(unsigned long))))
+
+#define CPU_ZERO(set)                                                          \
+  do {                                                                         \
+    for (size_t __i = 0; __i < sizeof(cpu_set_t) / sizeof(unsigned long);     \
+         ++__i) {                                                              \
+      (set)->__bits[__i] = 0;                                                  \
+    }                                                                          \
+  } while (0)
+#define CPU_SET(cpu, set) ((set)->__bits[__CPU_WORD(cpu)] |= __CPU_MASK(cpu))
+#define CPU_CLR(cpu, set) ((set)->__bits[__CPU_WORD(cpu)] &= ~__CPU_MASK(cpu))
+#define CPU_ISSET(cpu, set)                                                    \
+  (((set)->__bits[__CPU_WORD(cpu)] & __CPU_MASK(cpu)) != 0)
+#define CPU_COUNT(set) __sched_cpucount(sizeof(cpu_set_t), (set))
+
+extern int sched_getaffinity(pid_t pid, size_t cpusetsize, cpu_set_t *mask);
+
+extern int __sched_cpucount(size_t setsize, const cpu_set_t *setp);
+
 #ifdef __cplusplus
 } // extern "C"
 #endif
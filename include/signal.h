@@ -0,0 +1,186 @@
+#ifndef __KNS_SIGNAL_H
+#define __KNS_SIGNAL_H
+
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#include <stddef.h>
+#include <sys/types.h>
+#include <time.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SIGHUP 1
+#define SIGINT 2
+#define SIGQUIT 3
+#define SIGILL 4
+#define SIGTRAP 5
+#define SIGABRT 6
+#define SIGIOT SIGABRT
+#define SIGBUS 7
+#define SIGFPE 8
+#define SIGKILL 9
+#define SIGUSR1 10
+#define SIGSEGV 11
+#define SIGUSR2 12
+#define SIGPIPE 13
+#define SIGALRM 14
+#define SIGTERM 15
+#define SIGSTKFLT 16
+#define SIGCHLD 17
+#define SIGCONT 18
+#define SIGSTOP 19
+#define SIGTSTP 20
+#define SIGTTIN 21
+#define SIGTTOU 22
+#define SIGURG 23
+#define SIGXCPU 24
+#define SIGXFSZ 25
+#define SIGVTALRM 26
+#define SIGPROF 27
+#define SIGWINCH 28
+#define SIGIO 29
+#define SIGPOLL SIGIO
+#define SIGPWR 30
+#define SIGSYS 31
+#define SIGRTMIN 32
+#define SIGRTMAX 64
+
+#define NSIG 65
+
+#define SIG_DFL ((sighandler_t)0)
+#define SIG_IGN ((sighandler_t)1)
+#define SIG_ERR ((sighandler_t)-1)
+
+#define SA_NOCLDSTOP 0x00000001
+#define SA_NOCLDWAIT 0x00000002
+#define SA_SIGINFO 0x00000004
+#define SA_RESTORER 0x04000000
+#define SA_ONSTACK 0x08000000
+#define SA_RESTART 0x10000000
+#define SA_NODEFER 0x40000000
+#define SA_RESETHAND 0x80000000
+
+#define SIG_BLOCK 0
+#define SIG_UNBLOCK 1
+#define SIG_SETMASK 2
+
+#define SS_ONSTACK 1
+#define SS_DISABLE 2
+
+#define MINSIGSTKSZ 2048
+#define SIGSTKSZ 8192
+
+#define CLD_EXITED 1
+#define CLD_KILLED 2
+#define CLD_DUMPED 3
+#define CLD_TRAPPED 4
+#define CLD_STOPPED 5
+#define CLD_CONTINUED 6
+
+typedef int sig_atomic_t;
+
+typedef void (*sighandler_t)(int);
+
+typedef struct {
+  unsigned long __bits[1024 / (8 * sizeof(unsigned long))];
+} sigset_t;
+
+typedef struct {
+  int si_signo;
+  int si_errno;
+  int si_code;
+  union {
+    int __pad[28];
+    struct {
+      pid_t __pid;
+      uid_t __uid;
+      int __status;
+      long __utime;
+      long __stime;
+    } __sigchld;
+    struct {
+      pid_t __pid;
+      uid_t __uid;
+      union {
+        int sival_int;
+        void *sival_ptr;
+      } __value;
+    } __kill;
+    struct {
+      void *__addr;
+    } __fault;
+  } __fields;
+} siginfo_t;
+
+#define si_pid __fields.__sigchld.__pid
+#define si_uid __fields.__sigchld.__uid
+#define si_status __fields.__sigchld.__status
+#define si_utime __fields.__sigchld.__utime
+#define si_stime __fields.__sigchld.__stime
+#define si_value __fields.__kill.__value
+#define si_addr __fields.__fault.__addr
+
+struct sigaction {
+  union {
+    sighandler_t sa_handler;
+    void (*sa_sigaction)(int, siginfo_t *, void *);
+  } __handler;
+  sigset_t sa_mask;
+  int sa_flags;
+  void (*sa_restorer)(void);
+};
+
+#define sa_handler __handler.sa_handler
+#define sa_sigaction __handler.sa_sigaction
+
+typedef struct {
+  void *ss_sp;
+  int ss_flags;
+  size_t ss_size;
+} stack_t;
+
+extern int sigaction(int sig, const struct sigaction *act,
+                     struct sigaction *oldact);
+extern sighandler_t signal(int sig, sighandler_t handler);
+
+extern int sigprocmask(int how, const sigset_t *set, sigset_t *oldset);
+extern int pthread_sigmask(int how, const sigset_t *set, sigset_t *oldset);
+extern int sigpending(sigset_t *set);
+
+extern int sigemptyset(sigset_t *set);
+extern int sigfillset(sigset_t *set);
+extern int sigaddset(sigset_t *set, int sig);
+extern int sigdelset(sigset_t *set, int sig);
+extern int sigismember(const sigset_t *set, int sig);
+
+extern int raise(int sig);
+extern int kill(pid_t pid, int sig);
+
+extern int sigsuspend(const sigset_t *mask);
+extern int sigwait(const sigset_t *set, int *sig);
+extern int sigwaitinfo(const sigset_t *set, siginfo_t *info);
+extern int sigtimedwait(const sigset_t *set, siginfo_t *info,
+                        const struct timespec *timeout);
+
+extern int sigaltstack(const stack_t *ss, stack_t *old_ss);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif
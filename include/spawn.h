@@ -0,0 +1,89 @@
+#ifndef __KNS_SPAWN_H
+#define __KNS_SPAWN_H
+
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#include <signal.h>
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define POSIX_SPAWN_RESETIDS 0x01
+#define POSIX_SPAWN_SETPGROUP 0x02
+#define POSIX_SPAWN_SETSIGDEF 0x04
+#define POSIX_SPAWN_SETSIGMASK 0x08
+#define POSIX_SPAWN_SETSID 0x80
+
+typedef struct {
+  short __flags;
+  pid_t __pgroup;
+  sigset_t __sigdefault;
+  sigset_t __sigmask;
+} posix_spawnattr_t;
+
+typedef struct {
+  void *__head;
+  void *__tail;
+} posix_spawn_file_actions_t;
+
+extern int posix_spawn(pid_t *pid, const char *path,
+                       const posix_spawn_file_actions_t *file_actions,
+                       const posix_spawnattr_t *attrp, char *const argv[],
+                       char *const envp[]);
+extern int posix_spawnp(pid_t *pid, const char *file,
+                        const posix_spawn_file_actions_t *file_actions,
+                        const posix_spawnattr_t *attrp, char *const argv[],
+                        char *const envp[]);
+
+extern int
+posix_spawn_file_actions_init(posix_spawn_file_actions_t *file_actions);
+extern int
+posix_spawn_file_actions_destroy(posix_spawn_file_actions_t *file_actions);
+extern int
+posix_spawn_file_actions_addclose(posix_spawn_file_actions_t *file_actions,
+                                  int fd);
+extern int
+posix_spawn_file_actions_adddup2(posix_spawn_file_actions_t *file_actions,
+                                 int fd, int newfd);
+extern int
+posix_spawn_file_actions_addopen(posix_spawn_file_actions_t *file_actions,
+                                 int fd, const char *path, int oflag,
+                                 mode_t mode);
+
+extern int posix_spawnattr_init(posix_spawnattr_t *attr);
+extern int posix_spawnattr_destroy(posix_spawnattr_t *attr);
+extern int posix_spawnattr_setflags(posix_spawnattr_t *attr, short flags);
+extern int posix_spawnattr_getflags(const posix_spawnattr_t *attr,
+                                    short *flags);
+extern int posix_spawnattr_setpgroup(posix_spawnattr_t *attr, pid_t pgroup);
+extern int posix_spawnattr_getpgroup(const posix_spawnattr_t *attr,
+                                     pid_t *pgroup);
+extern int posix_spawnattr_setsigdefault(posix_spawnattr_t *attr,
+                                         const sigset_t *sigdefault);
+extern int posix_spawnattr_getsigdefault(const posix_spawnattr_t *attr,
+                                         sigset_t *sigdefault);
+extern int posix_spawnattr_setsigmask(posix_spawnattr_t *attr,
+                                      const sigset_t *sigmask);
+extern int posix_spawnattr_getsigmask(const posix_spawnattr_t *attr,
+                                      sigset_t *sigmask);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif
@@ -20,6 +20,12 @@
 extern "C" {
 #endif
 
+#ifdef __cplusplus
+#define NULL nullptr
+#else
+#define NULL ((void *)0)
+#endif
+
 typedef unsigned long size_t;
 typedef long ssize_t;
 typedef long ptrdiff_t;
@@ -16,6 +16,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+#include <stdarg.h>
+#include <stddef.h>
+#include <sys/types.h>
+
 #ifdef __cplusplus
 extern "C" {
 #endif
@@ -26,14 +30,106 @@ extern "C" {
 
 #define EOF ((int)-1)
 
+#define BUFSIZ 8192
+
+#define _IOFBF 0
+#define _IOLBF 1
+#define _IONBF 2
+
+#define RENAME_NOREPLACE (1 << 0)
+#define RENAME_EXCHANGE (1 << 1)
+#define RENAME_WHITEOUT (1 << 2)
+
+#define SEEK_SET 0
+#define SEEK_CUR 1
+#define SEEK_END 2
+
 typedef struct FILE FILE;
 
+typedef struct {
+  off_t __offset;
+} fpos_t;
+
 extern FILE *fopen(const char *pathname, const char *mode);
 extern int fclose(FILE *stream);
+extern int fcloseall(void);
+
+extern int rename(const char *oldpath, const char *newpath);
+extern int renameat(int olddirfd, const char *oldpath, int newdirfd,
+                    const char *newpath);
+extern int renameat2(int olddirfd, const char *oldpath, int newdirfd,
+                     const char *newpath, unsigned int flags);
 
 extern char *fgets(char *s, int size, FILE *stream);
 extern int fputs(const char *s, FILE *stream);
 
+extern int fgetc(FILE *stream);
+extern int getc(FILE *stream);
+extern int getchar(void);
+extern int ungetc(int c, FILE *stream);
+
+extern int fputc(int c, FILE *stream);
+extern int putc(int c, FILE *stream);
+extern int putchar(int c);
+extern int puts(const char *s);
+
+extern void perror(const char *s);
+
+extern size_t fread(void *ptr, size_t size, size_t nmemb, FILE *stream);
+extern size_t fwrite(const void *ptr, size_t size, size_t nmemb, FILE *stream);
+
+extern ssize_t getline(char **lineptr, size_t *n, FILE *stream);
+extern ssize_t getdelim(char **lineptr, size_t *n, int delim, FILE *stream);
+
+extern int feof(FILE *stream);
+extern int ferror(FILE *stream);
+extern void clearerr(FILE *stream);
+extern int fileno(FILE *stream);
+
+extern int setvbuf(FILE *stream, char *buf, int mode, size_t size);
+extern void setbuf(FILE *stream, char *buf);
+extern void setlinebuf(FILE *stream);
+extern int fflush(FILE *stream);
+
+extern int fseek(FILE *stream, long offset, int whence);
+extern int fseeko(FILE *stream, off_t offset, int whence);
+extern long ftell(FILE *stream);
+extern off_t ftello(FILE *stream);
+extern void rewind(FILE *stream);
+extern int fgetpos(FILE *stream, fpos_t *pos);
+extern int fsetpos(FILE *stream, const fpos_t *pos);
+
+extern int printf(const char *format, ...)
+    __attribute__((__format__(__printf__, 1, 2)));
+extern int fprintf(FILE *stream, const char *format, ...)
+    __attribute__((__format__(__printf__, 2, 3)));
+extern int dprintf(int fd, const char *format, ...)
+    __attribute__((__format__(__printf__, 2, 3)));
+extern int sprintf(char *str, const char *format, ...)
+    __attribute__((__format__(__printf__, 2, 3)));
+extern int snprintf(char *str, size_t size, const char *format, ...)
+    __attribute__((__format__(__printf__, 3, 4)));
+extern int asprintf(char **strp, const char *format, ...)
+    __attribute__((__format__(__printf__, 2, 3)));
+
+extern int vprintf(const char *format, va_list ap);
+extern int vfprintf(FILE *stream, const char *format, va_list ap);
+extern int vdprintf(int fd, const char *format, va_list ap);
+extern int vsprintf(char *str, const char *format, va_list ap);
+extern int vsnprintf(char *str, size_t size, const char *format, va_list ap);
+extern int vasprintf(char **strp, const char *format, va_list ap);
+
+extern int scanf(const char *format, ...)
+    __attribute__((__format__(__scanf__, 1, 2)));
+extern int fscanf(FILE *stream, const char *format, ...)
+    __attribute__((__format__(__scanf__, 2, 3)));
+extern int sscanf(const char *str, const char *format, ...)
+    __attribute__((__format__(__scanf__, 2, 3)));
+
+extern int vscanf(const char *format, va_list ap);
+extern int vfscanf(FILE *stream, const char *format, va_list ap);
+extern int vsscanf(const char *str, const char *format, va_list ap);
+
 extern FILE *__KNS_stdin(void);
 extern FILE *__KNS_stdout(void);
 extern FILE *__KNS_stderr(void);
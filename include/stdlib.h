@@ -25,8 +25,27 @@ extern "C" {
 #define EXIT_SUCCESS ((int)0)
 #define EXIT_FAILURE ((int)1)
 
+extern void exit(int status) __attribute__((__noreturn__));
+extern void quick_exit(int status) __attribute__((__noreturn__));
+extern void abort(void) __attribute__((__noreturn__));
+extern void _Exit(int status) __attribute__((__noreturn__));
+extern int atexit(void (*function)(void));
+extern int at_quick_exit(void (*function)(void));
+
+extern char *getenv(const char *name);
+extern char *secure_getenv(const char *name);
+extern int setenv(const char *name, const char *value, int overwrite);
+extern int unsetenv(const char *name);
+extern int putenv(char *string);
+extern int clearenv(void);
+
+extern char *realpath(const char *path, char *resolved_path);
+
 extern long strtol(const char *nptr, char **endptr, int base);
 
+extern int strfromd(char *str, size_t n, const char *format, double fp);
+extern int strfromf(char *str, size_t n, const char *format, float fp);
+
 extern void *malloc(size_t size);
 extern void free(void *ptr);
 extern void *calloc(size_t nmemb, size_t size);
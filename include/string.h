@@ -25,9 +25,55 @@ extern "C" {
 extern void *memset(void *s, int c, size_t n);
 extern void *memcpy(void *dest, const void *src, size_t n);
 extern void *memmove(void *dest, const void *src, size_t n);
+extern void *mempcpy(void *dest, const void *src, size_t n);
+
+extern int memcmp(const void *s1, const void *s2, size_t n);
+extern void *memchr(const void *s, int c, size_t n);
+extern void *memrchr(const void *s, int c, size_t n);
+extern void *memmem(const void *haystack, size_t haystacklen,
+                    const void *needle, size_t needlelen);
 
 extern size_t strlen(const char *s);
+extern size_t strnlen(const char *s, size_t maxlen);
+
+extern int strcmp(const char *s1, const char *s2);
+extern int strncmp(const char *s1, const char *s2, size_t n);
+
+extern char *strcpy(char *dest, const char *src);
+extern char *strncpy(char *dest, const char *src, size_t n);
+extern char *stpcpy(char *dest, const char *src);
+extern char *stpncpy(char *dest, const char *src, size_t n);
+extern char *strcat(char *dest, const char *src);
+extern char *strncat(char *dest, const char *src, size_t n);
+extern size_t strlcpy(char *dst, const char *src, size_t size);
+extern size_t strlcat(char *dst, const char *src, size_t size);
+
+extern char *strdup(const char *s);
+extern char *strndup(const char *s, size_t n);
+
+extern char *strchr(const char *s, int c);
+extern char *strrchr(const char *s, int c);
+extern char *strchrnul(const char *s, int c);
 extern char *strstr(const char *haystack, const char *needle);
+extern char *strpbrk(const char *s, const char *accept);
+extern size_t strspn(const char *s, const char *accept);
+extern size_t strcspn(const char *s, const char *reject);
+
+extern char *strtok(char *str, const char *delim);
+extern char *strtok_r(char *str, const char *delim, char **saveptr);
+extern char *strsep(char **stringp, const char *delim);
+
+extern char *strerror(int errnum);
+#ifdef _GNU_SOURCE
+extern char *strerror_r(int errnum, char *buf, size_t buflen);
+#else
+extern int strerror_r(int errnum, char *buf,
+                      size_t buflen) __asm__("__xpg_strerror_r");
+#endif
+extern const char *strerrorname_np(int errnum);
+extern const char *strerrordesc_np(int errnum);
+
+extern char *strsignal(int sig);
 
 #ifdef __cplusplus
 } // extern "C"
@@ -86,6 +86,7 @@ extern "C" {
 extern void *mmap(void *addr, size_t length, int prot, int flags, int fd,
                   off_t offset);
 extern int munmap(void *addr, size_t length);
+extern int mprotect(void *addr, size_t length, int prot);
 extern int madvise(void *addr, size_t length, int advice);
 extern int posix_madvise(void *addr, size_t length, int advice);
 
@@ -21,6 +21,18 @@ extern "C" {
 #endif
 
 typedef long off_t;
+typedef unsigned long dev_t;
+typedef unsigned long ino_t;
+typedef unsigned long nlink_t;
+typedef long blksize_t;
+typedef long blkcnt_t;
+typedef int pid_t;
+typedef unsigned int mode_t;
+typedef unsigned int uid_t;
+typedef unsigned int gid_t;
+typedef unsigned int id_t;
+typedef long time_t;
+typedef long suseconds_t;
 
 #ifdef __cplusplus
 } // extern "C"
@@ -0,0 +1,55 @@
+#ifndef __KNS_SYS_UIO_H
+#define __KNS_SYS_UIO_H
+
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#include <stddef.h>
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define IOV_MAX 1024
+#define UIO_MAXIOV IOV_MAX
+
+#define RWF_HIPRI 0x01
+#define RWF_DSYNC 0x02
+#define RWF_SYNC 0x04
+#define RWF_NOWAIT 0x08
+#define RWF_APPEND 0x10
+
+struct iovec {
+  void *iov_base;
+  size_t iov_len;
+};
+
+extern ssize_t readv(int fd, const struct iovec *iov, int iovcnt);
+extern ssize_t writev(int fd, const struct iovec *iov, int iovcnt);
+extern ssize_t preadv(int fd, const struct iovec *iov, int iovcnt,
+                      off_t offset);
+extern ssize_t pwritev(int fd, const struct iovec *iov, int iovcnt,
+                       off_t offset);
+extern ssize_t preadv2(int fd, const struct iovec *iov, int iovcnt,
+                       off_t offset, int flags);
+extern ssize_t pwritev2(int fd, const struct iovec *iov, int iovcnt,
+                        off_t offset, int flags);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif
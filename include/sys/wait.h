@@ -0,0 +1,55 @@
+#ifndef __KNS_SYS_WAIT_H
+#define __KNS_SYS_WAIT_H
+
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#include <signal.h>
+#include <sys/resource.h>
+#include <sys/types.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define WNOHANG 0x00000001
+#define WUNTRACED 0x00000002
+#define WSTOPPED WUNTRACED
+#define WEXITED 0x00000004
+#define WCONTINUED 0x00000008
+#define WNOWAIT 0x01000000
+
+#define WEXITSTATUS(status) (((status) & 0xff00) >> 8)
+#define WTERMSIG(status) ((status) & 0x7f)
+#define WSTOPSIG(status) WEXITSTATUS(status)
+#define WCOREDUMP(status) ((status) & 0x80)
+#define WIFEXITED(status) (WTERMSIG(status) == 0)
+#define WIFSIGNALED(status) (((status) & 0xffff) - 1U < 0xffU)
+#define WIFSTOPPED(status)                                                     \
+  ((short)((((status) & 0xffff) * 0x10001U) >> 8) > 0x7f00)
+#define WIFCONTINUED(status) ((status) == 0xffff)
+
+typedef enum { P_ALL = 0, P_PID = 1, P_PGID = 2, P_PIDFD = 3 } idtype_t;
+
+extern pid_t wait(int *wstatus);
+extern pid_t waitpid(pid_t pid, int *wstatus, int options);
+extern int waitid(idtype_t idtype, id_t id, siginfo_t *infop, int options);
+extern pid_t wait4(pid_t pid, int *wstatus, int options, struct rusage *rusage);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif
+
+#endif
@@ -17,6 +17,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 #include <stddef.h>
+#include <sys/types.h>
 
 #ifdef __cplusplus
 extern "C" {
@@ -26,14 +27,130 @@ extern "C" {
 #define STDOUT_FILENO 1
 #define STDERR_FILENO 2
 
+#define F_OK 0
+#define X_OK 1
+#define W_OK 2
+#define R_OK 4
+
+#define F_ULOCK 0
+#define F_LOCK 1
+#define F_TLOCK 2
+#define F_TEST 3
+
+#define SEEK_SET 0
+#define SEEK_CUR 1
+#define SEEK_END 2
+
+enum {
+  _SC_ARG_MAX = 0,
+  _SC_CHILD_MAX = 1,
+  _SC_CLK_TCK = 2,
+  _SC_NGROUPS_MAX = 3,
+  _SC_OPEN_MAX = 4,
+  _SC_STREAM_MAX = 5,
+  _SC_TZNAME_MAX = 6,
+  _SC_VERSION = 29,
+  _SC_PAGESIZE = 30,
+  _SC_RTSIG_MAX = 31,
+  _SC_SIGQUEUE_MAX = 34,
+  _SC_LINE_MAX = 43,
+  _SC_IOV_MAX = 60,
+  _SC_THREADS = 67,
+  _SC_LOGIN_NAME_MAX = 71,
+  _SC_TTY_NAME_MAX = 72,
+  _SC_THREAD_STACK_MIN = 75,
+  _SC_NPROCESSORS_CONF = 83,
+  _SC_NPROCESSORS_ONLN = 84,
+  _SC_PHYS_PAGES = 85,
+  _SC_AVPHYS_PAGES = 86,
+  _SC_ATEXIT_MAX = 87,
+  _SC_MONOTONIC_CLOCK = 149,
+  _SC_SYMLOOP_MAX = 173,
+  _SC_HOST_NAME_MAX = 180,
+};
+
+#define _SC_PAGE_SIZE _SC_PAGESIZE
+
 enum {
-  _SC_PAGESIZE,
+  _PC_LINK_MAX = 0,
+  _PC_MAX_CANON = 1,
+  _PC_MAX_INPUT = 2,
+  _PC_NAME_MAX = 3,
+  _PC_PATH_MAX = 4,
+  _PC_PIPE_BUF = 5,
+  _PC_CHOWN_RESTRICTED = 6,
+  _PC_NO_TRUNC = 7,
+  _PC_VDISABLE = 8,
+  _PC_FILESIZEBITS = 13,
+  _PC_SYMLINK_MAX = 19,
+  _PC_2_SYMLINKS = 20,
 };
 
+extern char **environ;
+
 extern ssize_t read(int fd, void *buf, size_t count);
 extern ssize_t write(int fd, const void *buf, size_t count);
+extern off_t lseek(int fd, off_t offset, int whence);
+extern ssize_t pread(int fd, void *buf, size_t count, off_t offset);
+extern ssize_t pwrite(int fd, const void *buf, size_t count, off_t offset);
+extern ssize_t copy_file_range(int fd_in, off_t *off_in, int fd_out,
+                               off_t *off_out, size_t len, unsigned int flags);
+extern int close(int fd);
+
+extern int dup(int oldfd);
+extern int dup2(int oldfd, int newfd);
+extern int dup3(int oldfd, int newfd, int flags);
+extern int pipe(int pipefd[2]);
+extern int pipe2(int pipefd[2], int flags);
+extern int lockf(int fd, int cmd, off_t len);
+
+extern int access(const char *pathname, int mode);
+extern int faccessat(int dirfd, const char *pathname, int mode, int flags);
+extern int rmdir(const char *pathname);
+extern int unlink(const char *pathname);
+extern int unlinkat(int dirfd, const char *pathname, int flags);
+extern int link(const char *oldpath, const char *newpath);
+extern int linkat(int olddirfd, const char *oldpath, int newdirfd,
+                  const char *newpath, int flags);
+extern int symlink(const char *target, const char *linkpath);
+extern int symlinkat(const char *target, int newdirfd, const char *linkpath);
+extern ssize_t readlink(const char *pathname, char *buf, size_t bufsiz);
+extern ssize_t readlinkat(int dirfd, const char *pathname, char *buf,
+                          size_t bufsiz);
+extern int chown(const char *pathname, uid_t owner, gid_t group);
+extern int lchown(const char *pathname, uid_t owner, gid_t group);
+extern int fchown(int fd, uid_t owner, gid_t group);
+extern int fchownat(int dirfd, const char *pathname, uid_t owner, gid_t group,
+                    int flags);
+extern int truncate(const char *path, off_t length);
+extern int ftruncate(int fd, off_t length);
+
+extern char *getcwd(char *buf, size_t size);
+extern int chdir(const char *path);
+extern int fchdir(int fd);
+
+extern pid_t fork(void);
+extern pid_t vfork(void);
+extern int execve(const char *pathname, char *const argv[], char *const envp[]);
+extern int execv(const char *pathname, char *const argv[]);
+extern int execvp(const char *file, char *const argv[]);
+extern int execvpe(const char *file, char *const argv[], char *const envp[]);
+extern void _exit(int status) __attribute__((__noreturn__));
+
+extern pid_t getpid(void);
+extern pid_t getppid(void);
+extern uid_t getuid(void);
+extern uid_t geteuid(void);
+extern gid_t getgid(void);
+extern gid_t getegid(void);
+extern int setpgid(pid_t pid, pid_t pgid);
+extern pid_t getpgid(pid_t pid);
+extern pid_t setsid(void);
 
 extern long sysconf(int name);
+extern int getpagesize(void);
+extern long pathconf(const char *path, int name);
+extern long fpathconf(int fd, int name);
 
 #ifdef __cplusplus
 } // extern "C"
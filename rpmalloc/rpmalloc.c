@@ -2989,6 +2989,27 @@ void rpmalloc_thread_finalize(void) {
 #endif
 }
 
+//! Take every global lock so that a fork cannot copy one that is held
+void rpmalloc_fork_prepare(void) {
+  while (!atomic_cas32_acquire(&_memory_global_lock, 1, 0))
+    /* Spin */;
+#if ENABLE_GLOBAL_CACHE
+  for (size_t iclass = 0; iclass < LARGE_CLASS_COUNT; ++iclass) {
+    while (!atomic_cas32_acquire(&_memory_span_cache[iclass].lock, 1, 0))
+      /* Spin */;
+  }
+#endif
+}
+
+//! Release the locks taken by rpmalloc_fork_prepare, in parent and child alike
+void rpmalloc_fork_release(void) {
+#if ENABLE_GLOBAL_CACHE
+  for (size_t iclass = LARGE_CLASS_COUNT; iclass > 0; --iclass)
+    atomic_store32_release(&_memory_span_cache[iclass - 1].lock, 0);
+#endif
+  atomic_store32_release(&_memory_global_lock, 0);
+}
+
 int rpmalloc_is_thread_initialized(void) {
   return (get_thread_heap_raw() != 0) ? 1 : 0;
 }
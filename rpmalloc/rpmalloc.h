@@ -225,6 +225,12 @@ RPMALLOC_EXPORT void rpmalloc_thread_initialize(void);
 //! Finalize allocator for calling thread
 RPMALLOC_EXPORT void rpmalloc_thread_finalize(void);
 
+//! Take the allocator's global locks before forking
+RPMALLOC_EXPORT void rpmalloc_fork_prepare(void);
+
+//! Release the allocator's global locks after forking
+RPMALLOC_EXPORT void rpmalloc_fork_release(void);
+
 //! Perform deferred deallocations pending for the calling thread heap
 RPMALLOC_EXPORT void rpmalloc_thread_collect(void);
 
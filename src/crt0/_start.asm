@@ -21,4 +21,12 @@ _start:
     mov rdi, [rsp]
     lea rsi, [rsp + 8]
     lea rdx, [rsp + rdi*8 + 16]
+
+    ; the auxiliary vector starts after the null that ends envp
+    mov rcx, rdx
+.find_auxv:
+    add rcx, 8
+    cmp qword [rcx - 8], 0
+    jne .find_auxv
+
     call __KNS_start
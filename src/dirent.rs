@@ -0,0 +1,368 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_char, c_int, c_long, c_unsignedchar, c_unsignedshort, c_void, errno,
+    fcntl::{self, O_CLOEXEC, O_DIRECTORY, O_RDONLY},
+    internal::{self, alloc::Box, sync::Mutex},
+    stddef::size_t,
+    stdlib,
+    sys::{
+        stat::{self, S_IFDIR, S_IFMT},
+        types::{ino_t, off_t, stat as Stat},
+    },
+    unistd::{self, SEEK_SET},
+};
+
+use core::{
+    cmp::Ordering,
+    mem::{self, MaybeUninit},
+    ptr, slice,
+};
+
+pub const DT_UNKNOWN: c_unsignedchar = 0;
+pub const DT_FIFO: c_unsignedchar = 1;
+pub const DT_CHR: c_unsignedchar = 2;
+pub const DT_DIR: c_unsignedchar = 4;
+pub const DT_BLK: c_unsignedchar = 6;
+pub const DT_REG: c_unsignedchar = 8;
+pub const DT_LNK: c_unsignedchar = 10;
+pub const DT_SOCK: c_unsignedchar = 12;
+pub const DT_WHT: c_unsignedchar = 14;
+
+// same as glibc. getdents64 fills as much of it as fits whole entries
+const BUF_LEN: usize = 32 * 1024;
+
+/// Laid out like the kernel's linux_dirent64, so readdir can hand out
+/// pointers straight into the buffer. d_name is only as long as d_reclen.
+#[repr(C)]
+pub struct dirent {
+    pub d_ino: ino_t,
+    pub d_off: off_t,
+    pub d_reclen: c_unsignedshort,
+    pub d_type: c_unsignedchar,
+    pub d_name: [c_char; 256],
+}
+
+pub struct DIR {
+    fd: c_int,
+    stream: Mutex<Stream>,
+}
+
+struct Stream {
+    // u64s so that entries, which the kernel pads to 8 bytes, are aligned
+    buf: Box<[MaybeUninit<u64>]>,
+    pos: usize,
+    len: usize,
+    // d_off of the last entry returned, which is where the next one starts
+    offset: off_t,
+}
+
+impl Stream {
+    /// Returns the next entry, which is valid until the next call, or
+    /// None at the end of the directory.
+    unsafe fn next(&mut self, fd: c_int) -> Result<Option<*mut dirent>, c_int> {
+        if self.pos >= self.len {
+            let bytes = self.buf.len() * 8;
+
+            match sys::getdents64(fd, self.buf.as_mut_ptr() as *mut c_void, bytes) {
+                // the directory was removed out from under us, which is the same as empty
+                n if n == -(errno::ENOENT as isize) => return Ok(None),
+                n if n < 0 => return Err(-n as c_int),
+                0 => return Ok(None),
+                n => {
+                    self.pos = 0;
+                    self.len = n as usize;
+                }
+            }
+        }
+
+        let entry = (self.buf.as_mut_ptr() as *mut u8).add(self.pos) as *mut dirent;
+        self.pos += (*entry).d_reclen as usize;
+        self.offset = (*entry).d_off;
+
+        Ok(Some(entry))
+    }
+
+    unsafe fn seek(&mut self, fd: c_int, offset: off_t) {
+        if unistd::sys::lseek(fd, offset, SEEK_SET) >= 0 {
+            self.offset = offset;
+        }
+
+        self.pos = 0;
+        self.len = 0;
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn opendir(name: *const c_char) -> *mut DIR {
+    let fd = fcntl::sys::open(name, O_RDONLY | O_DIRECTORY | O_CLOEXEC, 0);
+
+    if fd < 0 {
+        *internal::errno() = -fd as c_int;
+
+        return ptr::null_mut();
+    }
+
+    let dirp = fdopendir(fd as c_int);
+
+    if dirp.is_null() {
+        unistd::sys::close(fd as c_int);
+    }
+
+    dirp
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fdopendir(fd: c_int) -> *mut DIR {
+    let mut statbuf = Stat::default();
+    let result = stat::sys::fstat(fd, &mut statbuf);
+
+    if result < 0 {
+        *internal::errno() = -result as c_int;
+
+        return ptr::null_mut();
+    }
+
+    if statbuf.st_mode & S_IFMT != S_IFDIR {
+        *internal::errno() = errno::ENOTDIR;
+
+        return ptr::null_mut();
+    }
+
+    let buf = match Box::new_uninit_slice(BUF_LEN / 8) {
+        Ok(b) => b,
+        Err(_) => {
+            *internal::errno() = errno::ENOMEM;
+
+            return ptr::null_mut();
+        }
+    };
+
+    let dirp = DIR {
+        fd,
+        stream: Mutex::new(Stream {
+            buf,
+            pos: 0,
+            len: 0,
+            offset: 0,
+        }),
+    };
+
+    match Box::new(dirp) {
+        Ok(d) => Box::into_raw(d),
+        Err(_) => {
+            *internal::errno() = errno::ENOMEM;
+
+            ptr::null_mut()
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn closedir(dirp: *mut DIR) -> c_int {
+    let dirp = Box::from_raw(dirp);
+    let result = unistd::sys::close(dirp.fd);
+
+    if result < 0 {
+        *internal::errno() = -result as c_int;
+
+        -1
+    } else {
+        0
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn readdir(dirp: *mut DIR) -> *mut dirent {
+    match (*dirp).stream.lock().next((*dirp).fd) {
+        Ok(Some(entry)) => entry,
+        Ok(None) => ptr::null_mut(),
+        Err(e) => {
+            *internal::errno() = e;
+
+            ptr::null_mut()
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn readdir_r(
+    dirp: *mut DIR,
+    entry: *mut dirent,
+    result: *mut *mut dirent,
+) -> c_int {
+    match (*dirp).stream.lock().next((*dirp).fd) {
+        Ok(Some(next)) => {
+            ptr::copy_nonoverlapping(
+                next as *const u8,
+                entry as *mut u8,
+                (*next).d_reclen as usize,
+            );
+            *result = entry;
+
+            0
+        }
+        Ok(None) => {
+            *result = ptr::null_mut();
+
+            0
+        }
+        Err(e) => {
+            *result = ptr::null_mut();
+
+            e
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rewinddir(dirp: *mut DIR) {
+    (*dirp).stream.lock().seek((*dirp).fd, 0);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn seekdir(dirp: *mut DIR, loc: c_long) {
+    (*dirp).stream.lock().seek((*dirp).fd, loc);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn telldir(dirp: *mut DIR) -> c_long {
+    (*dirp).stream.lock().offset
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dirfd(dirp: *mut DIR) -> c_int {
+    (*dirp).fd
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn scandir(
+    dirp: *const c_char,
+    namelist: *mut *mut *mut dirent,
+    filter: Option<unsafe extern "C" fn(*const dirent) -> c_int>,
+    compar: Option<unsafe extern "C" fn(*mut *const dirent, *mut *const dirent) -> c_int>,
+) -> c_int {
+    let dir = opendir(dirp);
+
+    if dir.is_null() {
+        return -1;
+    }
+
+    let mut list: *mut *mut dirent = ptr::null_mut();
+    let mut len = 0;
+    let mut capacity = 0;
+    let saved_errno = *internal::errno();
+
+    let failed = loop {
+        // a null readdir is only an error if it sets errno
+        *internal::errno() = 0;
+        let entry = readdir(dir);
+
+        if entry.is_null() {
+            break *internal::errno() != 0;
+        }
+
+        if let Some(filter) = filter {
+            if filter(entry) == 0 {
+                continue;
+            }
+        }
+
+        if len == capacity {
+            capacity = if capacity == 0 { 16 } else { capacity * 2 };
+            let grown = stdlib::realloc(
+                list as *mut c_void,
+                (capacity * mem::size_of::<*mut dirent>()) as size_t,
+            ) as *mut *mut dirent;
+
+            if grown.is_null() {
+                break true;
+            }
+
+            list = grown;
+        }
+
+        let reclen = (*entry).d_reclen as usize;
+        let copy = stdlib::malloc(reclen as size_t) as *mut dirent;
+
+        if copy.is_null() {
+            break true;
+        }
+
+        ptr::copy_nonoverlapping(entry as *const u8, copy as *mut u8, reclen);
+        *list.add(len) = copy;
+        len += 1;
+    };
+
+    let errno = *internal::errno();
+    closedir(dir);
+
+    if failed {
+        for i in 0..len {
+            stdlib::free(*list.add(i) as *mut c_void);
+        }
+
+        stdlib::free(list as *mut c_void);
+        *internal::errno() = errno;
+
+        return -1;
+    }
+
+    *internal::errno() = saved_errno;
+
+    if let Some(compar) = compar {
+        if len > 1 {
+            let entries = slice::from_raw_parts_mut(list as *mut *const dirent, len);
+
+            entries.sort_unstable_by(|a, b| {
+                let mut a = *a;
+                let mut b = *b;
+
+                compar(&mut a, &mut b).cmp(&0)
+            });
+        }
+    }
+
+    *namelist = list;
+
+    len as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn alphasort(a: *mut *const dirent, b: *mut *const dirent) -> c_int {
+    let a = (**a).d_name.as_ptr() as *const u8;
+    let b = (**b).d_name.as_ptr() as *const u8;
+    let mut i = 0;
+
+    loop {
+        match (*a.add(i)).cmp(&*b.add(i)) {
+            Ordering::Less => return -1,
+            Ordering::Greater => return 1,
+            Ordering::Equal if *a.add(i) == 0 => return 0,
+            Ordering::Equal => i += 1,
+        }
+    }
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    use crate::syscall;
+
+    pub(crate) unsafe fn getdents64(fd: c_int, dirp: *mut c_void, count: usize) -> isize {
+        syscall!(217, fd as isize, dirp as isize, count as size_t as isize)
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_char, c_int, errno, internal,
+    stdio::{self, FILE},
+    stdlib, string,
+};
+
+use core::ffi::VaList;
+
+#[no_mangle]
+pub unsafe extern "C" fn err(eval: c_int, format: *const c_char, mut args: ...) -> ! {
+    verr(eval, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn verr(eval: c_int, format: *const c_char, args: VaList) -> ! {
+    vwarn(format, args);
+
+    stdlib::exit(eval)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn errx(eval: c_int, format: *const c_char, mut args: ...) -> ! {
+    verrx(eval, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn verrx(eval: c_int, format: *const c_char, args: VaList) -> ! {
+    vwarnx(format, args);
+
+    stdlib::exit(eval)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn warn(format: *const c_char, mut args: ...) {
+    vwarn(format, args.as_va_list())
+}
+
+/// Writes the program name, the formatted message, and the message for
+/// errno to stderr, separated by colons.
+#[no_mangle]
+pub unsafe extern "C" fn vwarn(format: *const c_char, args: VaList) {
+    let errnum = *internal::errno();
+
+    report(format, args, Some(errnum));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn warnx(format: *const c_char, mut args: ...) {
+    vwarnx(format, args.as_va_list())
+}
+
+/// Like `vwarn`, but leaves out errno.
+#[no_mangle]
+pub unsafe extern "C" fn vwarnx(format: *const c_char, args: VaList) {
+    report(format, args, None);
+}
+
+unsafe fn report(format: *const c_char, args: VaList, errnum: Option<c_int>) {
+    let stream: *mut FILE = stdio::__KNS_stderr();
+
+    if !errno::program_invocation_short_name.is_null() {
+        stdio::fputs(errno::program_invocation_short_name, stream);
+        stdio::fputs(b": \0".as_ptr() as *const c_char, stream);
+    }
+
+    if !format.is_null() {
+        stdio::vfprintf(stream, format, args);
+
+        if errnum.is_some() {
+            stdio::fputs(b": \0".as_ptr() as *const c_char, stream);
+        }
+    }
+
+    if let Some(errnum) = errnum {
+        stdio::fputs(string::strerror(errnum), stream);
+    }
+
+    stdio::fputc(b'\n' as c_int, stream);
+}
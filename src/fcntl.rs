@@ -12,3 +12,372 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_char, c_int, c_long, c_short, c_unsignedint, c_unsignedlong, errno,
+    stddef::{size_t, ssize_t},
+    sys::{
+        types::{mode_t, off_t, pid_t},
+        uio::iovec,
+    },
+    wrap_syscall,
+};
+
+pub const O_RDONLY: c_int = 0o0000000;
+pub const O_WRONLY: c_int = 0o0000001;
+pub const O_RDWR: c_int = 0o0000002;
+pub const O_ACCMODE: c_int = 0o0000003;
+pub const O_CREAT: c_int = 0o0000100;
+pub const O_EXCL: c_int = 0o0000200;
+pub const O_NOCTTY: c_int = 0o0000400;
+pub const O_TRUNC: c_int = 0o0001000;
+pub const O_APPEND: c_int = 0o0002000;
+pub const O_NONBLOCK: c_int = 0o0004000;
+pub const O_NDELAY: c_int = O_NONBLOCK;
+pub const O_DSYNC: c_int = 0o0010000;
+pub const FASYNC: c_int = 0o0020000;
+pub const O_ASYNC: c_int = FASYNC;
+pub const O_DIRECT: c_int = 0o0040000;
+pub const O_LARGEFILE: c_int = 0o0100000;
+pub const O_DIRECTORY: c_int = 0o0200000;
+pub const O_NOFOLLOW: c_int = 0o0400000;
+pub const O_NOATIME: c_int = 0o1000000;
+pub const O_CLOEXEC: c_int = 0o2000000;
+pub const O_SYNC: c_int = 0o4010000;
+pub const O_RSYNC: c_int = O_SYNC;
+pub const O_PATH: c_int = 0o10000000;
+pub const O_TMPFILE: c_int = 0o20000000 | O_DIRECTORY;
+
+pub const F_DUPFD: c_int = 0;
+pub const F_GETFD: c_int = 1;
+pub const F_SETFD: c_int = 2;
+pub const F_GETFL: c_int = 3;
+pub const F_SETFL: c_int = 4;
+pub const F_GETLK: c_int = 5;
+pub const F_SETLK: c_int = 6;
+pub const F_SETLKW: c_int = 7;
+pub const F_SETOWN: c_int = 8;
+pub const F_GETOWN: c_int = 9;
+pub const F_SETSIG: c_int = 10;
+pub const F_GETSIG: c_int = 11;
+pub const F_SETOWN_EX: c_int = 15;
+pub const F_GETOWN_EX: c_int = 16;
+pub const F_OFD_GETLK: c_int = 36;
+pub const F_OFD_SETLK: c_int = 37;
+pub const F_OFD_SETLKW: c_int = 38;
+pub const F_SETLEASE: c_int = 1024;
+pub const F_GETLEASE: c_int = 1025;
+pub const F_NOTIFY: c_int = 1026;
+pub const F_DUPFD_CLOEXEC: c_int = 1030;
+pub const F_SETPIPE_SZ: c_int = 1031;
+pub const F_GETPIPE_SZ: c_int = 1032;
+pub const F_ADD_SEALS: c_int = 1033;
+pub const F_GET_SEALS: c_int = 1034;
+
+pub const FD_CLOEXEC: c_long = 1;
+
+pub const F_RDLCK: c_short = 0;
+pub const F_WRLCK: c_short = 1;
+pub const F_UNLCK: c_short = 2;
+
+pub const F_OWNER_TID: c_int = 0;
+pub const F_OWNER_PID: c_int = 1;
+pub const F_OWNER_PGRP: c_int = 2;
+
+pub const AT_FDCWD: c_int = -100;
+pub const AT_SYMLINK_NOFOLLOW: c_int = 0x100;
+pub const AT_REMOVEDIR: c_int = 0x200;
+pub const AT_EACCESS: c_int = 0x200;
+pub const AT_SYMLINK_FOLLOW: c_int = 0x400;
+pub const AT_NO_AUTOMOUNT: c_int = 0x800;
+pub const AT_EMPTY_PATH: c_int = 0x1000;
+pub const AT_STATX_SYNC_AS_STAT: c_int = 0x0000;
+pub const AT_STATX_FORCE_SYNC: c_int = 0x2000;
+pub const AT_STATX_DONT_SYNC: c_int = 0x4000;
+
+pub const SPLICE_F_MOVE: c_unsignedint = 1;
+pub const SPLICE_F_NONBLOCK: c_unsignedint = 2;
+pub const SPLICE_F_MORE: c_unsignedint = 4;
+pub const SPLICE_F_GIFT: c_unsignedint = 8;
+
+pub const POSIX_FADV_NORMAL: c_int = 0;
+pub const POSIX_FADV_RANDOM: c_int = 1;
+pub const POSIX_FADV_SEQUENTIAL: c_int = 2;
+pub const POSIX_FADV_WILLNEED: c_int = 3;
+pub const POSIX_FADV_DONTNEED: c_int = 4;
+pub const POSIX_FADV_NOREUSE: c_int = 5;
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct flock {
+    pub l_type: c_short,
+    pub l_whence: c_short,
+    pub l_start: off_t,
+    pub l_len: off_t,
+    pub l_pid: pid_t,
+}
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct f_owner_ex {
+    pub type_: c_int,
+    pub pid: pid_t,
+}
+
+// mode is only passed when the file might be created
+fn takes_mode(flags: c_int) -> bool {
+    flags & O_CREAT != 0 || flags & O_TMPFILE == O_TMPFILE
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn open(pathname: *const c_char, flags: c_int, mut args: ...) -> c_int {
+    let mode = if takes_mode(flags) {
+        args.arg::<mode_t>()
+    } else {
+        0
+    };
+
+    wrap_syscall!(sys::open(pathname, flags, mode)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn openat(
+    dirfd: c_int,
+    pathname: *const c_char,
+    flags: c_int,
+    mut args: ...
+) -> c_int {
+    let mode = if takes_mode(flags) {
+        args.arg::<mode_t>()
+    } else {
+        0
+    };
+
+    wrap_syscall!(sys::openat(dirfd, pathname, flags, mode)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn creat(pathname: *const c_char, mode: mode_t) -> c_int {
+    wrap_syscall!(sys::open(pathname, O_CREAT | O_WRONLY | O_TRUNC, mode)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fcntl(fd: c_int, cmd: c_int, mut args: ...) -> c_int {
+    // every command that takes an argument takes an int or a pointer, and the kernel only looks
+    // at the low half of an int
+    let arg = match cmd {
+        F_GETFD | F_GETFL | F_GETOWN | F_GETSIG | F_GETLEASE | F_GETPIPE_SZ | F_GET_SEALS => 0,
+        _ => args.arg::<c_unsignedlong>() as c_long,
+    };
+
+    if cmd != F_GETOWN {
+        return wrap_syscall!(sys::fcntl(fd, cmd, arg)) as c_int;
+    }
+
+    // a process group comes back from F_GETOWN negated, which looks like an error if it's small
+    let mut owner = f_owner_ex::default();
+    let result = sys::fcntl(fd, F_GETOWN_EX, &mut owner as *mut f_owner_ex as c_long);
+
+    if result == -(errno::EINVAL as isize) {
+        return wrap_syscall!(sys::fcntl(fd, F_GETOWN, 0)) as c_int;
+    } else if result < 0 {
+        return wrap_syscall!(result) as c_int;
+    }
+
+    if owner.type_ == F_OWNER_PGRP {
+        -owner.pid
+    } else {
+        owner.pid
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dup(oldfd: c_int) -> c_int {
+    wrap_syscall!(sys::dup(oldfd)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dup2(oldfd: c_int, newfd: c_int) -> c_int {
+    wrap_syscall!(sys::dup2(oldfd, newfd)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dup3(oldfd: c_int, newfd: c_int, flags: c_int) -> c_int {
+    wrap_syscall!(sys::dup3(oldfd, newfd, flags)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pipe(pipefd: *mut c_int) -> c_int {
+    pipe2(pipefd, 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pipe2(pipefd: *mut c_int, flags: c_int) -> c_int {
+    wrap_syscall!(sys::pipe2(pipefd, flags)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_fallocate(fd: c_int, offset: off_t, len: off_t) -> c_int {
+    if offset < 0 || len <= 0 {
+        return errno::EINVAL;
+    }
+
+    // like the rest of posix_*, this returns the error instead of setting errno
+    -sys::fallocate(fd, 0, offset, len) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_fadvise(
+    fd: c_int,
+    offset: off_t,
+    len: off_t,
+    advice: c_int,
+) -> c_int {
+    -sys::fadvise64(fd, offset, len, advice) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn splice(
+    fd_in: c_int,
+    off_in: *mut off_t,
+    fd_out: c_int,
+    off_out: *mut off_t,
+    len: size_t,
+    flags: c_unsignedint,
+) -> ssize_t {
+    wrap_syscall!(sys::splice(fd_in, off_in, fd_out, off_out, len, flags)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn tee(
+    fd_in: c_int,
+    fd_out: c_int,
+    len: size_t,
+    flags: c_unsignedint,
+) -> ssize_t {
+    wrap_syscall!(sys::tee(fd_in, fd_out, len, flags)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vmsplice(
+    fd: c_int,
+    iov: *const iovec,
+    nr_segs: size_t,
+    flags: c_unsignedint,
+) -> ssize_t {
+    wrap_syscall!(sys::vmsplice(fd, iov, nr_segs, flags)) as ssize_t
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    use crate::syscall;
+
+    pub(crate) unsafe fn open(pathname: *const c_char, flags: c_int, mode: mode_t) -> isize {
+        syscall!(2, pathname as isize, flags as isize, mode as isize)
+    }
+
+    pub(crate) unsafe fn openat(
+        dirfd: c_int,
+        pathname: *const c_char,
+        flags: c_int,
+        mode: mode_t,
+    ) -> isize {
+        syscall!(
+            257,
+            dirfd as isize,
+            pathname as isize,
+            flags as isize,
+            mode as isize
+        )
+    }
+
+    pub(crate) unsafe fn fcntl(fd: c_int, cmd: c_int, arg: c_long) -> isize {
+        syscall!(72, fd as isize, cmd as isize, arg as isize)
+    }
+
+    pub(crate) unsafe fn dup(oldfd: c_int) -> isize {
+        syscall!(32, oldfd as isize)
+    }
+
+    pub(crate) unsafe fn dup2(oldfd: c_int, newfd: c_int) -> isize {
+        syscall!(33, oldfd as isize, newfd as isize)
+    }
+
+    pub(crate) unsafe fn dup3(oldfd: c_int, newfd: c_int, flags: c_int) -> isize {
+        syscall!(292, oldfd as isize, newfd as isize, flags as isize)
+    }
+
+    pub(crate) unsafe fn pipe2(pipefd: *mut c_int, flags: c_int) -> isize {
+        syscall!(293, pipefd as isize, flags as isize)
+    }
+
+    pub(crate) unsafe fn fallocate(fd: c_int, mode: c_int, offset: off_t, len: off_t) -> isize {
+        syscall!(
+            285,
+            fd as isize,
+            mode as isize,
+            offset as isize,
+            len as isize
+        )
+    }
+
+    pub(crate) unsafe fn fadvise64(fd: c_int, offset: off_t, len: off_t, advice: c_int) -> isize {
+        syscall!(
+            221,
+            fd as isize,
+            offset as isize,
+            len as isize,
+            advice as isize
+        )
+    }
+
+    pub(crate) unsafe fn splice(
+        fd_in: c_int,
+        off_in: *mut off_t,
+        fd_out: c_int,
+        off_out: *mut off_t,
+        len: size_t,
+        flags: c_unsignedint,
+    ) -> isize {
+        syscall!(
+            275,
+            fd_in as isize,
+            off_in as isize,
+            fd_out as isize,
+            off_out as isize,
+            len as isize,
+            flags as isize
+        )
+    }
+
+    pub(crate) unsafe fn tee(
+        fd_in: c_int,
+        fd_out: c_int,
+        len: size_t,
+        flags: c_unsignedint,
+    ) -> isize {
+        syscall!(
+            276,
+            fd_in as isize,
+            fd_out as isize,
+            len as isize,
+            flags as isize
+        )
+    }
+
+    pub(crate) unsafe fn vmsplice(
+        fd: c_int,
+        iov: *const iovec,
+        nr_segs: size_t,
+        flags: c_unsignedint,
+    ) -> isize {
+        syscall!(
+            278,
+            fd as isize,
+            iov as isize,
+            nr_segs as isize,
+            flags as isize
+        )
+    }
+}
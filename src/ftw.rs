@@ -0,0 +1,361 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_char, c_int,
+    dirent::{self, DIR},
+    errno,
+    fcntl::{self, AT_FDCWD, AT_SYMLINK_NOFOLLOW, O_CLOEXEC, O_DIRECTORY, O_RDONLY},
+    internal,
+    string::strlen,
+    sys::{
+        stat::{self, S_IFDIR, S_IFLNK, S_IFMT},
+        types::{dev_t, ino_t, stat as Stat},
+    },
+    unistd::{self, PATH_MAX},
+};
+
+pub const FTW_F: c_int = 0;
+pub const FTW_D: c_int = 1;
+pub const FTW_DNR: c_int = 2;
+pub const FTW_NS: c_int = 3;
+pub const FTW_SL: c_int = 4;
+pub const FTW_DP: c_int = 5;
+pub const FTW_SLN: c_int = 6;
+
+pub const FTW_PHYS: c_int = 1;
+pub const FTW_MOUNT: c_int = 2;
+pub const FTW_CHDIR: c_int = 4;
+pub const FTW_DEPTH: c_int = 8;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct FTW {
+    pub base: c_int,
+    pub level: c_int,
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ftw(
+    dirpath: *const c_char,
+    func: unsafe extern "C" fn(*const c_char, *const Stat, c_int) -> c_int,
+    _nopenfd: c_int,
+) -> c_int {
+    walk(dirpath, 0, &mut |path, statbuf, typeflag, _| {
+        // ftw has no way to say a symlink is dangling
+        let typeflag = if typeflag == FTW_SLN {
+            FTW_NS
+        } else {
+            typeflag
+        };
+
+        func(path, statbuf, typeflag)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nftw(
+    dirpath: *const c_char,
+    func: unsafe extern "C" fn(*const c_char, *const Stat, c_int, *mut FTW) -> c_int,
+    _nopenfd: c_int,
+    flags: c_int,
+) -> c_int {
+    walk(dirpath, flags, &mut |path, statbuf, typeflag, ftwbuf| {
+        func(path, statbuf, typeflag, ftwbuf)
+    })
+}
+
+type Callback<'a> = dyn FnMut(*const c_char, *const Stat, c_int, *mut FTW) -> c_int + 'a;
+
+// one directory per level is held open, so nopenfd is only a suggestion
+struct Walk<'a> {
+    path: [u8; PATH_MAX],
+    flags: c_int,
+    dev: dev_t,
+    func: &'a mut Callback<'a>,
+}
+
+/// The directories above the one being walked, to catch symlink loops.
+struct Ancestor<'a> {
+    dev: dev_t,
+    ino: ino_t,
+    parent: Option<&'a Ancestor<'a>>,
+}
+
+unsafe fn walk(dirpath: *const c_char, flags: c_int, func: &mut Callback) -> c_int {
+    let len = strlen(dirpath) as usize;
+
+    if len == 0 {
+        *internal::errno() = errno::ENOENT;
+
+        return -1;
+    } else if len >= PATH_MAX {
+        *internal::errno() = errno::ENAMETOOLONG;
+
+        return -1;
+    }
+
+    let mut walk = Walk {
+        path: [0; PATH_MAX],
+        flags,
+        dev: 0,
+        func,
+    };
+
+    walk.path[..len].copy_from_slice(core::slice::from_raw_parts(dirpath as *const u8, len));
+
+    // the basename starts after the last slash that isn't trailing
+    let trimmed = walk.path[..len]
+        .iter()
+        .rposition(|&c| c != b'/')
+        .map_or(len, |i| i + 1);
+    let base = walk.path[..trimmed]
+        .iter()
+        .rposition(|&c| c == b'/')
+        .map_or(0, |i| i + 1);
+
+    if flags & FTW_CHDIR == 0 {
+        return walk.visit(AT_FDCWD, AT_FDCWD, 0, len, base, 0, None);
+    }
+
+    // so that we can put the working directory back afterward
+    let cwd = fcntl::sys::open(
+        b".\0".as_ptr() as *const c_char,
+        O_RDONLY | O_DIRECTORY | O_CLOEXEC,
+        0,
+    );
+
+    if cwd < 0 {
+        *internal::errno() = -cwd as c_int;
+
+        return -1;
+    }
+
+    let result = walk.visit(AT_FDCWD, cwd as c_int, 0, len, base, 0, None);
+    unistd::sys::close(cwd as c_int);
+
+    result
+}
+
+impl Walk<'_> {
+    /// Reports path[..len] and, if it's a directory, everything under it.
+    /// The path is opened relative to dirfd starting from name, and the
+    /// working directory is returned to cwd before every callback.
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn visit(
+        &mut self,
+        dirfd: c_int,
+        cwd: c_int,
+        name: usize,
+        len: usize,
+        base: usize,
+        level: c_int,
+        ancestors: Option<&Ancestor>,
+    ) -> c_int {
+        let pathname = self.path[name..].as_ptr() as *const c_char;
+        let follow = if self.flags & FTW_PHYS != 0 {
+            AT_SYMLINK_NOFOLLOW
+        } else {
+            0
+        };
+
+        let mut statbuf = Stat::default();
+        let result = stat::sys::fstatat(dirfd, pathname, &mut statbuf, follow);
+
+        let mut typeflag = if result < 0 {
+            if follow == 0
+                && stat::sys::fstatat(dirfd, pathname, &mut statbuf, AT_SYMLINK_NOFOLLOW) >= 0
+                && statbuf.st_mode & S_IFMT == S_IFLNK
+            {
+                FTW_SLN
+            } else if -result as c_int == errno::EACCES {
+                FTW_NS
+            } else {
+                *internal::errno() = -result as c_int;
+
+                return -1;
+            }
+        } else {
+            match statbuf.st_mode & S_IFMT {
+                S_IFDIR if self.flags & FTW_DEPTH != 0 => FTW_DP,
+                S_IFDIR => FTW_D,
+                S_IFLNK => FTW_SL,
+                _ => FTW_F,
+            }
+        };
+
+        if level == 0 {
+            self.dev = statbuf.st_dev;
+        } else if self.flags & FTW_MOUNT != 0 && statbuf.st_dev != self.dev {
+            return 0;
+        }
+
+        let mut dir = core::ptr::null_mut::<DIR>();
+
+        if typeflag == FTW_D || typeflag == FTW_DP {
+            let mut ancestor = ancestors;
+
+            while let Some(a) = ancestor {
+                if a.dev == statbuf.st_dev && a.ino == statbuf.st_ino {
+                    return 0;
+                }
+
+                ancestor = a.parent;
+            }
+
+            let fd = fcntl::sys::openat(dirfd, pathname, O_RDONLY | O_DIRECTORY | O_CLOEXEC, 0);
+
+            if fd == -(errno::EACCES as isize) {
+                typeflag = FTW_DNR;
+            } else if fd < 0 {
+                *internal::errno() = -fd as c_int;
+
+                return -1;
+            } else {
+                dir = dirent::fdopendir(fd as c_int);
+
+                if dir.is_null() {
+                    unistd::sys::close(fd as c_int);
+
+                    return -1;
+                }
+            }
+        }
+
+        let mut ftwbuf = FTW {
+            base: base as c_int,
+            level,
+        };
+
+        if typeflag != FTW_DP {
+            let result = (self.func)(
+                self.path.as_ptr() as *const c_char,
+                &statbuf,
+                typeflag,
+                &mut ftwbuf,
+            );
+
+            if result != 0 {
+                if !dir.is_null() {
+                    dirent::closedir(dir);
+                }
+
+                return result;
+            }
+        }
+
+        if dir.is_null() {
+            return 0;
+        }
+
+        let this = Ancestor {
+            dev: statbuf.st_dev,
+            ino: statbuf.st_ino,
+            parent: ancestors,
+        };
+
+        let result = self.visit_children(dir, len, level, &this);
+
+        self.path[len] = 0;
+
+        if self.flags & FTW_CHDIR != 0 {
+            unistd::sys::fchdir(cwd);
+        }
+
+        dirent::closedir(dir);
+
+        if result != 0 {
+            return result;
+        }
+
+        if typeflag == FTW_DP {
+            return (self.func)(
+                self.path.as_ptr() as *const c_char,
+                &statbuf,
+                typeflag,
+                &mut ftwbuf,
+            );
+        }
+
+        0
+    }
+
+    unsafe fn visit_children(
+        &mut self,
+        dir: *mut DIR,
+        len: usize,
+        level: c_int,
+        this: &Ancestor,
+    ) -> c_int {
+        let fd = dirent::dirfd(dir);
+
+        if self.flags & FTW_CHDIR != 0 {
+            let result = unistd::sys::fchdir(fd);
+
+            if result < 0 {
+                *internal::errno() = -result as c_int;
+
+                return -1;
+            }
+        }
+
+        // "/" already ends in a slash
+        let child = if self.path[len - 1] == b'/' {
+            len
+        } else {
+            len + 1
+        };
+
+        loop {
+            let entry = dirent::readdir(dir);
+
+            if entry.is_null() {
+                return 0;
+            }
+
+            let name = (*entry).d_name.as_ptr();
+            let name_len = strlen(name) as usize;
+            let name = core::slice::from_raw_parts(name as *const u8, name_len);
+
+            if name == b"." || name == b".." {
+                continue;
+            }
+
+            if child + name_len >= PATH_MAX {
+                *internal::errno() = errno::ENAMETOOLONG;
+
+                return -1;
+            }
+
+            self.path[len] = b'/';
+            self.path[child..child + name_len].copy_from_slice(name);
+            self.path[child + name_len] = 0;
+
+            let result = self.visit(
+                fd,
+                fd,
+                child,
+                child + name_len,
+                child,
+                level + 1,
+                Some(this),
+            );
+
+            if result != 0 {
+                return result;
+            }
+        }
+    }
+}
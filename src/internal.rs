@@ -14,23 +14,31 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 pub(crate) mod alloc;
+pub(crate) mod auxv;
 pub(crate) mod elf;
 pub(crate) mod errno;
+#[cfg(feature = "io_uring")]
+pub(crate) mod io_uring;
 pub(crate) mod sync;
 pub(crate) mod tcb;
 
-use elf::Header as ELFHeader;
+use elf::ProgramHeader;
 
 use crate::{
-    c_char, c_int, c_unsignedint, c_void,
+    c_char, c_int, c_unsignedint, c_unsignedlong, c_void,
     internal::errno::ErrorNumber,
     stddef::size_t,
-    stdlib,
-    sys::{mman, stat, types},
+    stdlib, string,
+    sys::{
+        auxv::{AT_PAGESZ, AT_PHDR, AT_PHENT, AT_PHNUM},
+        mman,
+        uio::{self, iovec},
+    },
     syscall, unistd,
 };
 
 use core::{
+    cmp,
     convert::{TryFrom, TryInto},
     fmt::{self, Display, Formatter},
     mem,
@@ -38,6 +46,7 @@ use core::{
     ops::{Deref, DerefMut},
     ptr::{self, NonNull},
     slice,
+    sync::atomic::AtomicI32,
 };
 
 #[macro_export]
@@ -122,10 +131,47 @@ impl Display for InvalidFileDescriptor {
     }
 }
 
+/// read(2) for stdio, which goes through this thread's io_uring when libkns is
+/// built with the `io_uring` feature and the kernel lets it have one.
+pub(crate) unsafe fn read(fd: c_int, buf: *mut c_void, count: size_t) -> isize {
+    #[cfg(feature = "io_uring")]
+    {
+        if let Some(result) = io_uring::read(fd, buf, count) {
+            return result;
+        }
+    }
+
+    unistd::sys::read(fd, buf, count)
+}
+
+/// write(2) for stdio; see `read`.
+pub(crate) unsafe fn write(fd: c_int, buf: *const c_void, count: size_t) -> isize {
+    #[cfg(feature = "io_uring")]
+    {
+        if let Some(result) = io_uring::write(fd, buf, count) {
+            return result;
+        }
+    }
+
+    unistd::sys::write(fd, buf, count)
+}
+
+/// writev(2) for stdio; see `read`.
+pub(crate) unsafe fn writev(fd: c_int, iov: *const iovec, iovcnt: c_int) -> isize {
+    #[cfg(feature = "io_uring")]
+    {
+        if let Some(result) = io_uring::writev(fd, iov, iovcnt) {
+            return result;
+        }
+    }
+
+    uio::sys::writev(fd, iov, iovcnt)
+}
+
 pub(crate) fn write_all(fd: &FileDescriptor, mut data: &[u8]) -> Result<(), ErrorNumber> {
     while !data.is_empty() {
         let this_written = ErrorNumber::from_syscall(unsafe {
-            unistd::sys::write(
+            write(
                 fd.as_raw(),
                 data.as_ptr() as *mut c_void,
                 data.len() as size_t,
@@ -138,10 +184,112 @@ pub(crate) fn write_all(fd: &FileDescriptor, mut data: &[u8]) -> Result<(), Erro
     Ok(())
 }
 
+/// Writes every byte of every buffer in `bufs`, in order, with as few
+/// writev calls as it takes. `bufs` is left pointing past whatever was
+/// written, so on error it holds what wasn't.
+pub(crate) fn write_all_vectored(
+    fd: &FileDescriptor,
+    bufs: &mut [iovec],
+) -> Result<(), ErrorNumber> {
+    let mut first = 0;
+
+    while first < bufs.len() {
+        if bufs[first].iov_len == 0 {
+            first += 1;
+
+            continue;
+        }
+
+        let remaining = &bufs[first..];
+        let mut written = ErrorNumber::from_syscall::<usize>(unsafe {
+            writev(
+                fd.as_raw(),
+                remaining.as_ptr(),
+                cmp::min(remaining.len(), uio::IOV_MAX as usize) as c_int,
+            )
+        })?;
+
+        while written > 0 {
+            let buf = &mut bufs[first];
+            let this_written = cmp::min(written, buf.iov_len as usize);
+
+            buf.iov_base = unsafe { (buf.iov_base as *mut u8).add(this_written) } as *mut c_void;
+            buf.iov_len -= this_written as size_t;
+            written -= this_written;
+
+            if buf.iov_len == 0 {
+                first += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 extern "C" {
     fn main(argc: c_int, argv: *mut *mut c_char, envp: *mut *mut c_char) -> c_int;
 }
 
+type InitFn = unsafe extern "C" fn(c_int, *mut *mut c_char, *mut *mut c_char);
+type FiniFn = unsafe extern "C" fn();
+
+// provided by the linker; each pair bounds an array of function pointers, which is empty if the
+// program has no such section
+extern "C" {
+    static __preinit_array_start: [InitFn; 0];
+    static __preinit_array_end: [InitFn; 0];
+    static __init_array_start: [InitFn; 0];
+    static __init_array_end: [InitFn; 0];
+    static __fini_array_start: [FiniFn; 0];
+    static __fini_array_end: [FiniFn; 0];
+}
+
+// only present if the program was linked with crti.o
+extern "C" {
+    #[linkage = "extern_weak"]
+    static _init: *const c_void;
+    #[linkage = "extern_weak"]
+    static _fini: *const c_void;
+}
+
+unsafe fn array_between<T>(start: &[T; 0], end: &[T; 0]) -> &'static [T] {
+    let start = start.as_ptr();
+    let len = (end.as_ptr() as usize - start as usize) / mem::size_of::<T>();
+
+    slice::from_raw_parts(start, len)
+}
+
+// .preinit_array, then _init, then .init_array, each in order
+unsafe fn run_initializers(argc: c_int, argv: *mut *mut c_char, envp: *mut *mut c_char) {
+    for init in array_between(&__preinit_array_start, &__preinit_array_end) {
+        init(argc, argv, envp);
+    }
+
+    if !_init.is_null() {
+        mem::transmute::<*const c_void, FiniFn>(_init)();
+    }
+
+    for init in array_between(&__init_array_start, &__init_array_end) {
+        init(argc, argv, envp);
+    }
+}
+
+// .fini_array in reverse order, then _fini
+extern "C" fn run_finalizers() {
+    unsafe {
+        for fini in array_between(&__fini_array_start, &__fini_array_end)
+            .iter()
+            .rev()
+        {
+            fini();
+        }
+
+        if !_fini.is_null() {
+            mem::transmute::<*const c_void, FiniFn>(_fini)();
+        }
+    }
+}
+
 static mut MAIN_TCB: Option<TCBBox> = None;
 
 pub(crate) unsafe fn finalize() {
@@ -149,72 +297,44 @@ pub(crate) unsafe fn finalize() {
     mem::drop(MAIN_TCB.take().unwrap());
 }
 
+// the kernel maps the program headers along with the program itself
+fn self_program_headers() -> impl Iterator<Item = ProgramHeader> {
+    let table = auxv::get(AT_PHDR).unwrap_or(0) as *const u8;
+    let entry_len = auxv::get(AT_PHENT).unwrap_or(0) as usize;
+    let num_entries = auxv::get(AT_PHNUM).unwrap_or(0) as usize;
+
+    let table = if table.is_null() || entry_len < elf::PROGRAM_HEADER_SIZE {
+        &[]
+    } else {
+        unsafe { slice::from_raw_parts(table, entry_len * num_entries) }
+    };
+
+    elf::program_headers(table, cmp::max(entry_len, 1))
+}
+
+// how far a position-independent executable was moved from the addresses in its program headers
+fn load_bias() -> usize {
+    match self_program_headers().find(|h| h.p_type == elf::PT_PHDR) {
+        Some(h) => auxv::get(AT_PHDR).unwrap() as usize - h.p_vaddr as usize,
+        None => 0,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn __KNS_start(
     argc: isize,
     argv: *mut *mut c_char,
     envp: *mut *mut c_char,
+    auxv: *const c_unsignedlong,
 ) -> ! {
     let main_result = {
-        const PATH_MAX: usize = 4096;
-        let mut self_path_buf = [0u8; PATH_MAX + 1];
-        let self_path_len = match ErrorNumber::from_syscall(unistd::sys::readlink(
-            (&b"/proc/self/exe\0").as_ptr() as *const c_char,
-            (&mut self_path_buf).as_mut_ptr() as *mut c_char,
-            PATH_MAX as size_t,
-        )) {
-            Ok(l) => l,
-            Err(e) => panic!("couldn't read path to self: {}", e),
-        };
-        let self_path = &self_path_buf[..self_path_len];
-
-        let self_fd: FileDescriptor = match ErrorNumber::from_syscall(unistd::sys::open(
-            self_path.as_ptr() as *const c_char,
-            unistd::O_RDONLY,
-            0,
-        )) {
-            Ok(fd) => fd,
-            Err(e) => panic!("couldn't open self: {}", e),
-        };
-
-        let mut self_stat = types::stat::default();
-        if let Err(e) =
-            ErrorNumber::from_syscall::<isize>(stat::sys::fstat(self_fd.as_raw(), &mut self_stat))
-        {
-            panic!("couldn't fstat self: {}", e);
-        };
-
-        let self_len = self_stat.st_size as usize;
-
-        let self_ptr = match ErrorNumber::from_syscall::<isize>(mman::sys::mmap(
-            ptr::null_mut(),
-            self_len as size_t,
-            mman::PROT_READ,
-            mman::MAP_SHARED,
-            self_fd.as_raw(),
-            0,
-        )) {
-            Ok(p) => p as *mut c_void,
-            Err(e) => panic!("couldn't mmap self: {}", e),
-        };
-
-        if let Err(e) = self_fd.try_drop() {
-            panic!("couldn't close self: {}", e);
-        }
-
-        let self_slice = slice::from_raw_parts(self_ptr as *const u8, self_len);
-        let self_header = match ELFHeader::new(self_slice) {
-            Ok(h) => h,
-            Err(e) => panic!("couldn't parse ELF header of self: {}", e),
-        };
+        auxv::set(auxv);
+        string::initialize();
 
-        if let Some(tls_header) = self_header
-            .program_headers(self_slice)
-            .find(|h| h.p_type == 7)
-        {
+        if let Some(tls_header) = self_program_headers().find(|h| h.p_type == elf::PT_TLS) {
             tls::set_template(TLSTemplate {
                 initialized: slice::from_raw_parts(
-                    tls_header.p_vaddr as *const u8,
+                    (load_bias() + tls_header.p_vaddr as usize) as *const u8,
                     tls_header.p_filesz as usize,
                 ),
                 uninitialized_len: (tls_header.p_memsz - tls_header.p_filesz) as usize,
@@ -222,12 +342,6 @@ pub unsafe extern "C" fn __KNS_start(
             });
         }
 
-        if let Err(e) =
-            ErrorNumber::from_syscall::<isize>(mman::sys::munmap(self_ptr, self_len as size_t))
-        {
-            panic!("couldn't unmap self: {}", e);
-        }
-
         let mut main_tcb = match TCBBox::new() {
             Ok(b) => b,
             Err(e) => panic!("couldn't map thread control block for main thread: {}", e),
@@ -240,7 +354,34 @@ pub unsafe extern "C" fn __KNS_start(
         MAIN_TCB = Some(main_tcb);
 
         alloc::initialize();
-        main(argc.try_into().unwrap(), argv, envp)
+
+        // registered first so that it runs after every handler the program registers, like
+        // destructors of objects constructed during initialization
+        stdlib::atexit(run_finalizers);
+
+        stdlib::environ = envp;
+
+        if argc > 0 && !(*argv).is_null() {
+            let name = *argv;
+            let mut short_name = name;
+            let mut next = name;
+
+            while *next != 0 {
+                if *next == b'/' as c_char {
+                    short_name = next.add(1);
+                }
+
+                next = next.add(1);
+            }
+
+            crate::errno::program_invocation_name = name;
+            crate::errno::program_invocation_short_name = short_name;
+        }
+
+        let argc = argc.try_into().unwrap();
+        run_initializers(argc, argv, envp);
+
+        main(argc, argv, envp)
     };
 
     stdlib::exit(main_result)
@@ -257,6 +398,10 @@ pub(crate) unsafe fn errno<'a>() -> &'a mut i32 {
 
 use tls::Template as TLSTemplate;
 
+pub(crate) fn page_size() -> usize {
+    auxv::get(AT_PAGESZ).map_or(4096, |p| p as usize)
+}
+
 pub(crate) const fn round_up_to_nearest_multiple(x: usize, multiple: usize) -> usize {
     if x % multiple != 0 {
         x + (multiple - x % multiple)
@@ -299,7 +444,7 @@ mod tls {
     }
 
     pub(crate) fn mmap_len() -> usize {
-        super::round_up_to_nearest_multiple(len(), 4096)
+        super::round_up_to_nearest_multiple(len(), super::page_size())
     }
 
     pub(crate) fn initialize(tls: &mut [u8]) {
@@ -313,6 +458,49 @@ mod tls {
 pub(crate) struct ThreadControlBlock {
     _self: NonNull<ThreadControlBlock>,
     pub(crate) errno: c_int,
+    // written by the kernel: set by CLONE_PARENT_SETTID, cleared and woken by CLONE_CHILD_CLEARTID
+    pub(crate) tid: AtomicI32,
+    pub(crate) detach_state: AtomicI32,
+    pub(crate) start_routine: Option<unsafe extern "C" fn(*mut c_void) -> *mut c_void>,
+    pub(crate) arg: *mut c_void,
+    pub(crate) result: *mut c_void,
+    // where strerror puts messages for error numbers it doesn't know
+    pub(crate) strerror_buf: [c_char; 32],
+    // and where strsignal puts them for signals
+    pub(crate) strsignal_buf: [c_char; 32],
+    #[cfg(feature = "io_uring")]
+    pub(crate) ring: io_uring::ThreadRing,
+    // set when a handler installed without SA_RESTART runs on this thread
+    #[cfg(feature = "io_uring")]
+    pub(crate) interrupted_without_restart: bool,
+    stack_len: usize,
+}
+
+impl ThreadControlBlock {
+    /// Returns the start and length of the mapping that holds this thread's
+    /// stack, TLS block and control block.
+    pub(crate) fn mapping(&self) -> (*mut c_void, size_t) {
+        let start = unsafe {
+            (self as *const ThreadControlBlock as *mut u8).sub(tls::mmap_len() + self.stack_len)
+        };
+
+        (
+            start as *mut c_void,
+            TCBBox::mmap_len(self.stack_len) as size_t,
+        )
+    }
+
+    /// Returns the (exclusive) top of this thread's stack, or null for the
+    /// main thread, whose stack was set up by the kernel.
+    pub(crate) fn stack_top(&self) -> *mut c_void {
+        if self.stack_len == 0 {
+            ptr::null_mut()
+        } else {
+            unsafe {
+                (self as *const ThreadControlBlock as *mut u8).sub(tls::mmap_len()) as *mut c_void
+            }
+        }
+    }
 }
 
 pub(crate) struct TCBBox {
@@ -321,10 +509,16 @@ pub(crate) struct TCBBox {
 
 impl TCBBox {
     pub(crate) fn new() -> Result<Self, ErrorNumber> {
+        Self::with_stack(0)
+    }
+
+    /// Maps a control block with `stack_len` bytes of stack below the TLS
+    /// block. `stack_len` must be a multiple of the page size.
+    pub(crate) fn with_stack(stack_len: usize) -> Result<Self, ErrorNumber> {
         let ptr = ErrorNumber::from_syscall::<isize>(unsafe {
             mman::sys::mmap(
                 ptr::null_mut(),
-                Self::mmap_len() as size_t,
+                Self::mmap_len(stack_len) as size_t,
                 mman::PROT_READ | mman::PROT_WRITE,
                 mman::MAP_PRIVATE | mman::MAP_ANONYMOUS,
                 -1,
@@ -333,7 +527,7 @@ impl TCBBox {
         })?;
 
         let tcb_ptr = NonNull::new(unsafe {
-            (ptr as *mut u8).add(tls::mmap_len()) as *mut ThreadControlBlock
+            (ptr as *mut u8).add(stack_len + tls::mmap_len()) as *mut ThreadControlBlock
         })
         .unwrap();
 
@@ -343,6 +537,18 @@ impl TCBBox {
                 ThreadControlBlock {
                     _self: tcb_ptr,
                     errno: 0,
+                    tid: AtomicI32::new(0),
+                    detach_state: AtomicI32::new(0),
+                    start_routine: None,
+                    arg: ptr::null_mut(),
+                    result: ptr::null_mut(),
+                    strerror_buf: [0; 32],
+                    strsignal_buf: [0; 32],
+                    #[cfg(feature = "io_uring")]
+                    ring: io_uring::ThreadRing::Untried,
+                    #[cfg(feature = "io_uring")]
+                    interrupted_without_restart: false,
+                    stack_len,
                 },
             )
         };
@@ -355,19 +561,33 @@ impl TCBBox {
         Ok(Self { tcb_ptr })
     }
 
-    fn mmap_len() -> usize {
-        tls::mmap_len() + mem::size_of::<ThreadControlBlock>()
+    pub(crate) fn into_raw(b: TCBBox) -> *mut ThreadControlBlock {
+        let tcb_ptr = b.tcb_ptr.as_ptr();
+        mem::forget(b);
+
+        tcb_ptr
+    }
+
+    /// # Safety
+    ///
+    /// `tcb_ptr` must have been returned by `TCBBox::into_raw` and the thread
+    /// that it belongs to must no longer be running.
+    pub(crate) unsafe fn from_raw(tcb_ptr: *mut ThreadControlBlock) -> Self {
+        Self {
+            tcb_ptr: NonNull::new_unchecked(tcb_ptr),
+        }
+    }
+
+    fn mmap_len(stack_len: usize) -> usize {
+        stack_len + tls::mmap_len() + mem::size_of::<ThreadControlBlock>()
     }
 }
 
 impl Drop for TCBBox {
     fn drop(&mut self) {
-        unsafe {
-            mman::sys::munmap(
-                (self.tcb_ptr.as_ptr() as *mut u8).sub(tls::mmap_len()) as *mut c_void,
-                Self::mmap_len() as size_t,
-            )
-        };
+        let (start, len) = self.mapping();
+
+        unsafe { mman::sys::munmap(start, len) };
     }
 }
 
@@ -403,8 +623,11 @@ mod handler {
     use core::{
         fmt::{self, Write},
         panic::PanicInfo,
+        sync::atomic::{AtomicBool, Ordering},
     };
 
+    static PANICKING: AtomicBool = AtomicBool::new(false);
+
     #[panic_handler]
     fn panic(info: &PanicInfo) -> ! {
         struct StdErr;
@@ -428,10 +651,12 @@ mod handler {
             }
         }
 
-        writeln!(&mut StdErr, "{}", info).ok();
-
-        unsafe {
-            stdlib::exit(130);
+        // something is already broken, so don't run exit handlers or flush streams on top of
+        // it. a panic while reporting a panic goes straight to abort
+        if !PANICKING.swap(true, Ordering::Relaxed) {
+            writeln!(&mut StdErr, "{}", info).ok();
         }
+
+        unsafe { stdlib::abort() }
     }
 }
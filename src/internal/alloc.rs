@@ -30,6 +30,8 @@ extern "C" {
     fn rpmalloc_finalize();
     fn rpmalloc_thread_initialize() -> c_int;
     fn rpmalloc_thread_finalize();
+    fn rpmalloc_fork_prepare();
+    fn rpmalloc_fork_release();
     fn rpmalloc(size: size_t) -> *mut c_void;
     fn rpfree(ptr: *mut c_void);
     fn rpcalloc(num: size_t, size: size_t) -> *mut c_void;
@@ -61,6 +63,15 @@ pub(crate) unsafe fn thread_finalize() {
     rpmalloc_thread_finalize();
 }
 
+/// Holds the allocator's locks across a fork until `unlock_after_fork`.
+pub(crate) unsafe fn lock_for_fork() {
+    rpmalloc_fork_prepare();
+}
+
+pub(crate) unsafe fn unlock_after_fork() {
+    rpmalloc_fork_release();
+}
+
 pub(crate) unsafe fn alloc(layout: Layout) -> *mut u8 {
     if layout.align() < mem::size_of::<*mut c_void>() {
         rpmalloc(layout.size() as size_t) as *mut u8
@@ -94,7 +105,7 @@ pub(crate) unsafe fn alloc_zeroed(layout: Layout) -> *mut u8 {
 
 pub(crate) unsafe fn realloc(ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
     if layout.align() < mem::size_of::<*mut c_void>() {
-        rprealloc(ptr as *mut c_void, layout.size() as size_t) as *mut u8
+        rprealloc(ptr as *mut c_void, new_size as size_t) as *mut u8
     } else {
         let aligned_layout = aligned_alloc_layout(layout);
         let new_layout = Layout::from_size_align(new_size, aligned_layout.align())
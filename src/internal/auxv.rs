@@ -0,0 +1,50 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{c_unsignedlong, sys::auxv::AT_NULL};
+
+use core::ptr;
+
+// DO NOT CHANGE OUTSIDE OF __KNS_start !!!
+static mut AUXV: *const c_unsignedlong = ptr::null();
+
+/// # Safety
+///
+/// This function must be called *exactly* once, from __KNS_start, with the
+/// auxiliary vector that the kernel put on the stack.
+pub(crate) unsafe fn set(auxv: *const c_unsignedlong) {
+    AUXV = auxv;
+}
+
+/// Returns the value of the `key` entry, if the kernel supplied one.
+pub(crate) fn get(key: c_unsignedlong) -> Option<c_unsignedlong> {
+    let mut entry = unsafe { AUXV };
+
+    if entry.is_null() {
+        return None;
+    }
+
+    loop {
+        let (this_key, value) = unsafe { (*entry, *entry.add(1)) };
+
+        if this_key == AT_NULL {
+            return None;
+        } else if this_key == key {
+            return Some(value);
+        }
+
+        entry = unsafe { entry.add(2) };
+    }
+}
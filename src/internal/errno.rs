@@ -22,14 +22,182 @@ use core::{
 
 use kns_syscall::SyscallResult;
 
-#[repr(i32)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub(crate) enum ErrorNumber {
-    Badf,
-    Nomem,
-    Inval,
-    Range,
-    Nosys,
+macro_rules! error_numbers {
+    ($($variant:ident => $constant:ident, $description:literal;)*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub(crate) enum ErrorNumber {
+            $($variant,)*
+            /// Anything the kernel comes up with that isn't in the table.
+            Unknown(c_int),
+        }
+
+        impl ErrorNumber {
+            pub(crate) fn from_int(e: c_int) -> ErrorNumber {
+                match e {
+                    $(errno::$constant => ErrorNumber::$variant,)*
+                    _ => ErrorNumber::Unknown(e),
+                }
+            }
+
+            pub(crate) fn into_int(self) -> c_int {
+                match self {
+                    $(ErrorNumber::$variant => errno::$constant,)*
+                    ErrorNumber::Unknown(e) => e,
+                }
+            }
+
+            /// Returns the name of the constant, like `"ENOENT"`, as a
+            /// nul-terminated string.
+            pub(crate) fn name(self) -> Option<&'static str> {
+                match self {
+                    $(ErrorNumber::$variant => Some(concat!(stringify!($constant), "\0")),)*
+                    ErrorNumber::Unknown(_) => None,
+                }
+            }
+
+            /// Returns the message strerror gives, as a nul-terminated string.
+            pub(crate) fn message(self) -> Option<&'static str> {
+                match self {
+                    $(ErrorNumber::$variant => Some(concat!($description, "\0")),)*
+                    ErrorNumber::Unknown(_) => None,
+                }
+            }
+        }
+    };
+}
+
+error_numbers! {
+    Perm => EPERM, "Operation not permitted";
+    Noent => ENOENT, "No such file or directory";
+    Srch => ESRCH, "No such process";
+    Intr => EINTR, "Interrupted system call";
+    Io => EIO, "Input/output error";
+    Nxio => ENXIO, "No such device or address";
+    TooBig => E2BIG, "Argument list too long";
+    Noexec => ENOEXEC, "Exec format error";
+    Badf => EBADF, "Bad file descriptor";
+    Child => ECHILD, "No child processes";
+    Again => EAGAIN, "Resource temporarily unavailable";
+    Nomem => ENOMEM, "Cannot allocate memory";
+    Acces => EACCES, "Permission denied";
+    Fault => EFAULT, "Bad address";
+    Notblk => ENOTBLK, "Block device required";
+    Busy => EBUSY, "Device or resource busy";
+    Exist => EEXIST, "File exists";
+    Xdev => EXDEV, "Invalid cross-device link";
+    Nodev => ENODEV, "No such device";
+    Notdir => ENOTDIR, "Not a directory";
+    Isdir => EISDIR, "Is a directory";
+    Inval => EINVAL, "Invalid argument";
+    Nfile => ENFILE, "Too many open files in system";
+    Mfile => EMFILE, "Too many open files";
+    Notty => ENOTTY, "Inappropriate ioctl for device";
+    Txtbsy => ETXTBSY, "Text file busy";
+    Fbig => EFBIG, "File too large";
+    Nospc => ENOSPC, "No space left on device";
+    Spipe => ESPIPE, "Illegal seek";
+    Rofs => EROFS, "Read-only file system";
+    Mlink => EMLINK, "Too many links";
+    Pipe => EPIPE, "Broken pipe";
+    Dom => EDOM, "Numerical argument out of domain";
+    Range => ERANGE, "Numerical result out of range";
+    Deadlk => EDEADLK, "Resource deadlock avoided";
+    Nametoolong => ENAMETOOLONG, "File name too long";
+    Nolck => ENOLCK, "No locks available";
+    Nosys => ENOSYS, "Function not implemented";
+    Notempty => ENOTEMPTY, "Directory not empty";
+    Loop => ELOOP, "Too many levels of symbolic links";
+    Nomsg => ENOMSG, "No message of desired type";
+    Idrm => EIDRM, "Identifier removed";
+    Chrng => ECHRNG, "Channel number out of range";
+    L2nsync => EL2NSYNC, "Level 2 not synchronized";
+    L3hlt => EL3HLT, "Level 3 halted";
+    L3rst => EL3RST, "Level 3 reset";
+    Lnrng => ELNRNG, "Link number out of range";
+    Unatch => EUNATCH, "Protocol driver not attached";
+    Nocsi => ENOCSI, "No CSI structure available";
+    L2hlt => EL2HLT, "Level 2 halted";
+    Bade => EBADE, "Invalid exchange";
+    Badr => EBADR, "Invalid request descriptor";
+    Xfull => EXFULL, "Exchange full";
+    Noano => ENOANO, "No anode";
+    Badrqc => EBADRQC, "Invalid request code";
+    Badslt => EBADSLT, "Invalid slot";
+    Bfont => EBFONT, "Bad font file format";
+    Nostr => ENOSTR, "Device not a stream";
+    Nodata => ENODATA, "No data available";
+    Time => ETIME, "Timer expired";
+    Nosr => ENOSR, "Out of streams resources";
+    Nonet => ENONET, "Machine is not on the network";
+    Nopkg => ENOPKG, "Package not installed";
+    Remote => EREMOTE, "Object is remote";
+    Nolink => ENOLINK, "Link has been severed";
+    Adv => EADV, "Advertise error";
+    Srmnt => ESRMNT, "Srmount error";
+    Comm => ECOMM, "Communication error on send";
+    Proto => EPROTO, "Protocol error";
+    Multihop => EMULTIHOP, "Multihop attempted";
+    Dotdot => EDOTDOT, "RFS specific error";
+    Badmsg => EBADMSG, "Bad message";
+    Overflow => EOVERFLOW, "Value too large for defined data type";
+    Notuniq => ENOTUNIQ, "Name not unique on network";
+    Badfd => EBADFD, "File descriptor in bad state";
+    Remchg => EREMCHG, "Remote address changed";
+    Libacc => ELIBACC, "Can not access a needed shared library";
+    Libbad => ELIBBAD, "Accessing a corrupted shared library";
+    Libscn => ELIBSCN, ".lib section in a.out corrupted";
+    Libmax => ELIBMAX, "Attempting to link in too many shared libraries";
+    Libexec => ELIBEXEC, "Cannot exec a shared library directly";
+    Ilseq => EILSEQ, "Invalid or incomplete multibyte or wide character";
+    Restart => ERESTART, "Interrupted system call should be restarted";
+    Strpipe => ESTRPIPE, "Streams pipe error";
+    Users => EUSERS, "Too many users";
+    Notsock => ENOTSOCK, "Socket operation on non-socket";
+    Destaddrreq => EDESTADDRREQ, "Destination address required";
+    Msgsize => EMSGSIZE, "Message too long";
+    Prototype => EPROTOTYPE, "Protocol wrong type for socket";
+    Noprotoopt => ENOPROTOOPT, "Protocol not available";
+    Protonosupport => EPROTONOSUPPORT, "Protocol not supported";
+    Socktnosupport => ESOCKTNOSUPPORT, "Socket type not supported";
+    Opnotsupp => EOPNOTSUPP, "Operation not supported";
+    Pfnosupport => EPFNOSUPPORT, "Protocol family not supported";
+    Afnosupport => EAFNOSUPPORT, "Address family not supported by protocol";
+    Addrinuse => EADDRINUSE, "Address already in use";
+    Addrnotavail => EADDRNOTAVAIL, "Cannot assign requested address";
+    Netdown => ENETDOWN, "Network is down";
+    Netunreach => ENETUNREACH, "Network is unreachable";
+    Netreset => ENETRESET, "Network dropped connection on reset";
+    Connaborted => ECONNABORTED, "Software caused connection abort";
+    Connreset => ECONNRESET, "Connection reset by peer";
+    Nobufs => ENOBUFS, "No buffer space available";
+    Isconn => EISCONN, "Transport endpoint is already connected";
+    Notconn => ENOTCONN, "Transport endpoint is not connected";
+    Shutdown => ESHUTDOWN, "Cannot send after transport endpoint shutdown";
+    Toomanyrefs => ETOOMANYREFS, "Too many references: cannot splice";
+    Timedout => ETIMEDOUT, "Connection timed out";
+    Connrefused => ECONNREFUSED, "Connection refused";
+    Hostdown => EHOSTDOWN, "Host is down";
+    Hostunreach => EHOSTUNREACH, "No route to host";
+    Already => EALREADY, "Operation already in progress";
+    Inprogress => EINPROGRESS, "Operation now in progress";
+    Stale => ESTALE, "Stale file handle";
+    Uclean => EUCLEAN, "Structure needs cleaning";
+    Notnam => ENOTNAM, "Not a XENIX named type file";
+    Navail => ENAVAIL, "No XENIX semaphores available";
+    Isnam => EISNAM, "Is a named type file";
+    Remoteio => EREMOTEIO, "Remote I/O error";
+    Dquot => EDQUOT, "Disk quota exceeded";
+    Nomedium => ENOMEDIUM, "No medium found";
+    Mediumtype => EMEDIUMTYPE, "Wrong medium type";
+    Canceled => ECANCELED, "Operation canceled";
+    Nokey => ENOKEY, "Required key not available";
+    Keyexpired => EKEYEXPIRED, "Key has expired";
+    Keyrevoked => EKEYREVOKED, "Key has been revoked";
+    Keyrejected => EKEYREJECTED, "Key was rejected by service";
+    Ownerdead => EOWNERDEAD, "Owner died";
+    Notrecoverable => ENOTRECOVERABLE, "State not recoverable";
+    Rfkill => ERFKILL, "Operation not possible due to RF-kill";
+    Hwpoison => EHWPOISON, "Memory page has hardware error";
 }
 
 impl ErrorNumber {
@@ -37,49 +205,20 @@ impl ErrorNumber {
     where
         <T as TryFrom<isize>>::Error: Debug,
     {
-        result.into_value().map_err(|e| match e {
-            errno::EBADF => ErrorNumber::Badf,
-            errno::ENOMEM => ErrorNumber::Nomem,
-            errno::EINVAL => ErrorNumber::Inval,
-            errno::ERANGE => ErrorNumber::Range,
-            errno::ENOSYS => ErrorNumber::Nosys,
-            _ => unimplemented!("unrecognized error number {}", e),
-        })
-    }
-
-    pub(crate) fn into_int(self) -> c_int {
-        match self {
-            ErrorNumber::Badf => errno::EBADF,
-            ErrorNumber::Nomem => errno::ENOMEM,
-            ErrorNumber::Inval => errno::EINVAL,
-            ErrorNumber::Range => errno::ERANGE,
-            ErrorNumber::Nosys => errno::ENOSYS,
-        }
-    }
-
-    pub(crate) fn into_str(self) -> &'static str {
-        match self {
-            ErrorNumber::Badf => "EBADF",
-            ErrorNumber::Nomem => "ENOMEM",
-            ErrorNumber::Inval => "EINVAL",
-            ErrorNumber::Range => "ERANGE",
-            ErrorNumber::Nosys => "ENOSYS",
-        }
-    }
-
-    pub(crate) fn description(self) -> &'static str {
-        match self {
-            ErrorNumber::Badf => "Bad file descriptor",
-            ErrorNumber::Nomem => "Cannot allocate memory",
-            ErrorNumber::Inval => "Invalid argument",
-            ErrorNumber::Range => "Numerical result out of range",
-            ErrorNumber::Nosys => "Function not implemented",
-        }
+        result.into_value().map_err(ErrorNumber::from_int)
     }
 }
 
 impl Display for ErrorNumber {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{} ({})", self.description(), self.into_str())
+        match (self.message(), self.name()) {
+            (Some(message), Some(name)) => write!(
+                f,
+                "{} ({})",
+                &message[..message.len() - 1],
+                &name[..name.len() - 1]
+            ),
+            _ => write!(f, "Unknown error {}", self.into_int()),
+        }
     }
 }
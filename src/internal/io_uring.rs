@@ -0,0 +1,159 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_int, c_void, errno, internal,
+    linux::io_uring::{self, io_uring as Ring, CURRENT_POSITION, IORING_FEAT_RW_CUR_POS},
+    stddef::size_t,
+    sys::uio::iovec,
+};
+
+use core::{
+    cmp, mem,
+    sync::atomic::{self, Ordering},
+};
+
+// operations run one at a time, so this only needs to be big enough for the kernel to accept
+const ENTRIES: u32 = 4;
+
+// the most read(2) and write(2) will transfer in one call
+const MAX_RW_LEN: size_t = 0x7fff_f000;
+
+/// The ring stdio reads and writes go through. Each thread sets one up the
+/// first time it needs it; if that fails (an old kernel, `ENOSYS` or `EPERM`
+/// from a seccomp filter, `ENOMEM` from a low `RLIMIT_MEMLOCK`), the thread
+/// sticks to plain syscalls from then on.
+// kept inline: it lives in the thread control block, which has the room
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum ThreadRing {
+    Untried,
+    Ready(Ring),
+    // taken by an operation in progress, so a signal handler that does stdio in the middle of
+    // it has to make do with syscalls
+    InUse,
+    Unavailable,
+}
+
+/// Runs `op` on this thread's ring, setting one up if this is the first try.
+/// Returns `None` if there is no ring to use and the caller should make the
+/// syscall itself.
+///
+/// A signal that cuts `op` short gets the treatment the kernel gives a
+/// syscall: `op` starts over if the handler was installed with `SA_RESTART`,
+/// and fails with `EINTR` if it wasn't.
+unsafe fn with_ring(mut op: impl FnMut(&mut Ring) -> Result<isize, c_int>) -> Option<isize> {
+    let tcb = internal::tcb();
+
+    let mut ring = match mem::replace(&mut tcb.ring, ThreadRing::InUse) {
+        ThreadRing::Ready(ring) => ring,
+        ThreadRing::Untried => match Ring::new(ENTRIES, 0) {
+            // reads and writes at the file position need 5.6
+            Ok(ring) if ring.features() & IORING_FEAT_RW_CUR_POS != 0 => ring,
+            _ => {
+                tcb.ring = ThreadRing::Unavailable;
+
+                return None;
+            }
+        },
+        state => {
+            tcb.ring = state;
+
+            return None;
+        }
+    };
+
+    let result = loop {
+        // set by any handler without SA_RESTART that runs in the middle of op
+        tcb.interrupted_without_restart = false;
+
+        atomic::compiler_fence(Ordering::SeqCst);
+        let result = op(&mut ring);
+        atomic::compiler_fence(Ordering::SeqCst);
+
+        // interrupted by a signal and cancelled, which leaves the ring as good as new
+        match result {
+            Err(errno::EINTR) if !tcb.interrupted_without_restart => continue,
+            Err(errno::EINTR) => break Ok(-(errno::EINTR as isize)),
+            result => break result,
+        }
+    };
+
+    match result {
+        Ok(result) => {
+            tcb.ring = ThreadRing::Ready(ring);
+
+            Some(result)
+        }
+        Err(_) => {
+            mem::drop(ring);
+            tcb.ring = ThreadRing::Unavailable;
+
+            None
+        }
+    }
+}
+
+pub(crate) unsafe fn read(fd: c_int, buf: *mut c_void, count: size_t) -> Option<isize> {
+    with_ring(|ring| {
+        ring.run(|sqe| {
+            sqe.prep_rw(
+                io_uring::IORING_OP_READ,
+                fd,
+                buf as u64,
+                cmp::min(count, MAX_RW_LEN) as u32,
+                CURRENT_POSITION,
+            )
+        })
+    })
+}
+
+pub(crate) unsafe fn write(fd: c_int, buf: *const c_void, count: size_t) -> Option<isize> {
+    with_ring(|ring| {
+        ring.run(|sqe| {
+            sqe.prep_rw(
+                io_uring::IORING_OP_WRITE,
+                fd,
+                buf as u64,
+                cmp::min(count, MAX_RW_LEN) as u32,
+                CURRENT_POSITION,
+            )
+        })
+    })
+}
+
+pub(crate) unsafe fn writev(fd: c_int, iov: *const iovec, iovcnt: c_int) -> Option<isize> {
+    with_ring(|ring| {
+        ring.run(|sqe| {
+            sqe.prep_rw(
+                io_uring::IORING_OP_WRITEV,
+                fd,
+                iov as u64,
+                iovcnt as u32,
+                CURRENT_POSITION,
+            )
+        })
+    })
+}
+
+/// Tears down the calling thread's ring on its way out.
+pub(crate) unsafe fn thread_finalize() {
+    internal::tcb().ring = ThreadRing::Unavailable;
+}
+
+/// Called in the child of a fork, which shares the parent's rings but must
+/// not submit to them.
+pub(crate) unsafe fn reset_after_fork() {
+    internal::tcb().ring = ThreadRing::Untried;
+}
@@ -90,6 +90,40 @@ impl<T: ?Sized> Mutex<T> {
             }
         }
     }
+
+    /// Locks the mutex if nobody else holds it, without waiting.
+    pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.is_locked
+            .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| MutexGuard {
+                parent: self,
+                phantom: PhantomData,
+            })
+    }
+
+    /// The data behind the lock, for use while it's held without a guard.
+    pub(crate) fn as_ptr(&self) -> *mut T {
+        self.data.get()
+    }
+
+    /// Puts the lock back the way `new` left it, whoever held it or was
+    /// waiting for it. Only for the child of a fork, where those threads no
+    /// longer exist.
+    pub(crate) unsafe fn reset_after_fork(&self) {
+        self.is_locked.store(0, Ordering::Relaxed);
+        self.sleeping_count.store(0, Ordering::Relaxed);
+    }
+
+    /// Unlocks a mutex whose guard was forgotten, like one held across a
+    /// fork. The caller must be the one that locked it.
+    pub(crate) unsafe fn force_unlock(&self) {
+        self.is_locked.store(0, Ordering::Release);
+
+        if self.sleeping_count.load(Ordering::Relaxed) > 0 {
+            futex::sys::futex_wake_private(&self.is_locked as *const _ as *mut c_int, 1);
+        }
+    }
 }
 
 unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
@@ -104,13 +138,7 @@ unsafe impl<T: ?Sized + Sync> Sync for MutexGuard<'_, T> {}
 
 impl<'p, T: ?Sized + 'p> Drop for MutexGuard<'p, T> {
     fn drop(&mut self) {
-        self.parent.is_locked.store(0, Ordering::Release);
-
-        if self.parent.sleeping_count.load(Ordering::Relaxed) > 0 {
-            unsafe {
-                futex::sys::futex_wake_private(&self.parent.is_locked as *const _ as *mut c_int, 1)
-            };
-        }
+        unsafe { self.parent.force_unlock() };
     }
 }
 
@@ -14,7 +14,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 #![no_std]
-#![feature(asm, int_error_matching, lang_items)]
+#![feature(asm, c_variadic, int_error_matching, lang_items, linkage)]
 #![allow(non_camel_case_types, non_snake_case)]
 
 use kns_syscall::syscall;
@@ -24,9 +24,16 @@ pub(crate) mod internal;
 
 pub use core::ffi::c_void;
 
+pub mod dirent;
+pub mod err;
 pub mod errno;
 pub mod fcntl;
+pub mod ftw;
 pub mod linux;
+pub mod pthread;
+pub mod sched;
+pub mod signal;
+pub mod spawn;
 pub mod stddef;
 pub mod stdio;
 pub mod stdlib;
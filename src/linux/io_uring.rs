@@ -0,0 +1,766 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_char, c_int, c_unsignedint, c_void, errno,
+    stddef::size_t,
+    sys::{mman, types::mode_t, uio::iovec},
+    syscall,
+    time::timespec,
+    unistd,
+};
+
+use core::{
+    mem, ptr,
+    sync::atomic::{self, AtomicU32, Ordering},
+};
+
+pub const IORING_SETUP_IOPOLL: c_unsignedint = 1 << 0;
+pub const IORING_SETUP_SQPOLL: c_unsignedint = 1 << 1;
+pub const IORING_SETUP_SQ_AFF: c_unsignedint = 1 << 2;
+pub const IORING_SETUP_CQSIZE: c_unsignedint = 1 << 3;
+pub const IORING_SETUP_CLAMP: c_unsignedint = 1 << 4;
+pub const IORING_SETUP_ATTACH_WQ: c_unsignedint = 1 << 5;
+
+pub const IORING_FEAT_SINGLE_MMAP: c_unsignedint = 1 << 0;
+pub const IORING_FEAT_NODROP: c_unsignedint = 1 << 1;
+pub const IORING_FEAT_SUBMIT_STABLE: c_unsignedint = 1 << 2;
+pub const IORING_FEAT_RW_CUR_POS: c_unsignedint = 1 << 3;
+pub const IORING_FEAT_CUR_PERSONALITY: c_unsignedint = 1 << 4;
+pub const IORING_FEAT_FAST_POLL: c_unsignedint = 1 << 5;
+
+pub const IORING_ENTER_GETEVENTS: c_unsignedint = 1 << 0;
+pub const IORING_ENTER_SQ_WAKEUP: c_unsignedint = 1 << 1;
+
+pub const IORING_SQ_NEED_WAKEUP: c_unsignedint = 1 << 0;
+
+pub const IORING_TIMEOUT_ABS: c_unsignedint = 1 << 0;
+
+pub const IORING_OFF_SQ_RING: u64 = 0;
+pub const IORING_OFF_CQ_RING: u64 = 0x8000000;
+pub const IORING_OFF_SQES: u64 = 0x10000000;
+
+pub const IOSQE_FIXED_FILE: u8 = 1 << 0;
+pub const IOSQE_IO_DRAIN: u8 = 1 << 1;
+pub const IOSQE_IO_LINK: u8 = 1 << 2;
+pub const IOSQE_IO_HARDLINK: u8 = 1 << 3;
+pub const IOSQE_ASYNC: u8 = 1 << 4;
+pub const IOSQE_BUFFER_SELECT: u8 = 1 << 5;
+
+pub const IORING_OP_NOP: u8 = 0;
+pub const IORING_OP_READV: u8 = 1;
+pub const IORING_OP_WRITEV: u8 = 2;
+pub const IORING_OP_FSYNC: u8 = 3;
+pub const IORING_OP_READ_FIXED: u8 = 4;
+pub const IORING_OP_WRITE_FIXED: u8 = 5;
+pub const IORING_OP_POLL_ADD: u8 = 6;
+pub const IORING_OP_POLL_REMOVE: u8 = 7;
+pub const IORING_OP_SYNC_FILE_RANGE: u8 = 8;
+pub const IORING_OP_SENDMSG: u8 = 9;
+pub const IORING_OP_RECVMSG: u8 = 10;
+pub const IORING_OP_TIMEOUT: u8 = 11;
+pub const IORING_OP_TIMEOUT_REMOVE: u8 = 12;
+pub const IORING_OP_ACCEPT: u8 = 13;
+pub const IORING_OP_ASYNC_CANCEL: u8 = 14;
+pub const IORING_OP_LINK_TIMEOUT: u8 = 15;
+pub const IORING_OP_CONNECT: u8 = 16;
+pub const IORING_OP_FALLOCATE: u8 = 17;
+pub const IORING_OP_OPENAT: u8 = 18;
+pub const IORING_OP_CLOSE: u8 = 19;
+pub const IORING_OP_FILES_UPDATE: u8 = 20;
+pub const IORING_OP_STATX: u8 = 21;
+pub const IORING_OP_READ: u8 = 22;
+pub const IORING_OP_WRITE: u8 = 23;
+pub const IORING_OP_FADVISE: u8 = 24;
+pub const IORING_OP_MADVISE: u8 = 25;
+pub const IORING_OP_SEND: u8 = 26;
+pub const IORING_OP_RECV: u8 = 27;
+pub const IORING_OP_OPENAT2: u8 = 28;
+pub const IORING_OP_EPOLL_CTL: u8 = 29;
+pub const IORING_OP_SPLICE: u8 = 30;
+pub const IORING_OP_PROVIDE_BUFFERS: u8 = 31;
+pub const IORING_OP_REMOVE_BUFFERS: u8 = 32;
+pub const IORING_OP_TEE: u8 = 33;
+
+/// `off` for reads and writes that should use and advance the file position.
+#[cfg(feature = "io_uring")]
+pub(crate) const CURRENT_POSITION: u64 = u64::MAX;
+
+// user_data for the operation run() is waiting on and for the request that cancels it
+#[cfg(feature = "io_uring")]
+const RUN_OPERATION: u64 = 1;
+#[cfg(feature = "io_uring")]
+const RUN_CANCEL: u64 = 2;
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct io_sqring_offsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub flags: u32,
+    pub dropped: u32,
+    pub array: u32,
+    pub resv1: u32,
+    pub resv2: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct io_cqring_offsets {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub overflow: u32,
+    pub cqes: u32,
+    pub flags: u32,
+    pub resv1: u32,
+    pub resv2: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct io_uring_params {
+    pub sq_entries: u32,
+    pub cq_entries: u32,
+    pub flags: u32,
+    pub sq_thread_cpu: u32,
+    pub sq_thread_idle: u32,
+    pub features: u32,
+    pub wq_fd: u32,
+    pub resv: [u32; 3],
+    pub sq_off: io_sqring_offsets,
+    pub cq_off: io_cqring_offsets,
+}
+
+/// A submission queue entry. The kernel's unions are flattened to the
+/// member every opcode shares: `off` doubles as `addr2`, `op_flags` as
+/// `rw_flags`, `timeout_flags`, `accept_flags` and the rest.
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct io_uring_sqe {
+    pub opcode: u8,
+    pub flags: u8,
+    pub ioprio: u16,
+    pub fd: i32,
+    pub off: u64,
+    pub addr: u64,
+    pub len: u32,
+    pub op_flags: u32,
+    pub user_data: u64,
+    pub buf_index: u16,
+    pub personality: u16,
+    pub splice_fd_in: i32,
+    pad: [u64; 2],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct io_uring_cqe {
+    pub user_data: u64,
+    pub res: i32,
+    pub flags: u32,
+}
+
+impl io_uring_sqe {
+    pub(crate) fn prep_rw(&mut self, opcode: u8, fd: c_int, addr: u64, len: u32, off: u64) {
+        *self = io_uring_sqe {
+            opcode,
+            fd,
+            off,
+            addr,
+            len,
+            ..Default::default()
+        };
+    }
+}
+
+#[repr(C)]
+pub struct io_uring_sq {
+    khead: *const AtomicU32,
+    ktail: *const AtomicU32,
+    kring_mask: *const c_unsignedint,
+    kring_entries: *const c_unsignedint,
+    kflags: *const AtomicU32,
+    kdropped: *const c_unsignedint,
+    array: *mut c_unsignedint,
+    sqes: *mut io_uring_sqe,
+    // entries handed out by get_sqe that the kernel hasn't been told about yet
+    sqe_head: c_unsignedint,
+    sqe_tail: c_unsignedint,
+    ring_sz: size_t,
+    ring_ptr: *mut c_void,
+    pad: [c_unsignedint; 4],
+}
+
+#[repr(C)]
+pub struct io_uring_cq {
+    khead: *const AtomicU32,
+    ktail: *const AtomicU32,
+    kring_mask: *const c_unsignedint,
+    kring_entries: *const c_unsignedint,
+    kflags: *const c_unsignedint,
+    koverflow: *const c_unsignedint,
+    cqes: *mut io_uring_cqe,
+    ring_sz: size_t,
+    ring_ptr: *mut c_void,
+    pad: [c_unsignedint; 4],
+}
+
+/// An io_uring instance and the three mappings it is driven through: the
+/// submission ring, the completion ring (which is the same mapping on
+/// kernels with `IORING_FEAT_SINGLE_MMAP`), and the array of entries. Laid
+/// out like liburing's, so C code can keep one wherever it likes.
+#[repr(C)]
+pub struct io_uring {
+    sq: io_uring_sq,
+    cq: io_uring_cq,
+    flags: c_unsignedint,
+    ring_fd: c_int,
+    features: c_unsignedint,
+    pad: [c_unsignedint; 3],
+}
+
+unsafe fn map_ring(fd: c_int, len: size_t, offset: u64) -> Result<*mut c_void, c_int> {
+    let ptr = mman::sys::mmap(
+        ptr::null_mut(),
+        len,
+        mman::PROT_READ | mman::PROT_WRITE,
+        mman::MAP_SHARED | mman::MAP_POPULATE,
+        fd,
+        offset as i64,
+    );
+
+    if ptr < 0 {
+        Err(-ptr as c_int)
+    } else {
+        Ok(ptr as *mut c_void)
+    }
+}
+
+unsafe fn field<T>(ring_ptr: *mut c_void, offset: u32) -> *mut T {
+    (ring_ptr as *mut u8).add(offset as usize) as *mut T
+}
+
+impl io_uring {
+    /// Sets up a ring with room for at least `entries` submissions. On
+    /// failure, returns the error number the kernel gave.
+    pub(crate) unsafe fn new(entries: u32, flags: u32) -> Result<io_uring, c_int> {
+        let mut params = io_uring_params {
+            flags,
+            ..Default::default()
+        };
+
+        let fd = sys::io_uring_setup(entries, &mut params);
+
+        if fd < 0 {
+            return Err(-fd as c_int);
+        }
+
+        // every pointer starts out null, so dropping this on the way out of a failed mmap only
+        // undoes what was done
+        let mut ring: io_uring = mem::zeroed();
+        ring.ring_fd = fd as c_int;
+        ring.flags = params.flags;
+        ring.features = params.features;
+
+        let sq_off = &params.sq_off;
+        let cq_off = &params.cq_off;
+
+        ring.sq.ring_sz = (sq_off.array + params.sq_entries * 4) as size_t;
+        ring.cq.ring_sz = (cq_off.cqes as usize
+            + params.cq_entries as usize * mem::size_of::<io_uring_cqe>())
+            as size_t;
+
+        if params.features & IORING_FEAT_SINGLE_MMAP != 0 {
+            ring.sq.ring_sz = ring.sq.ring_sz.max(ring.cq.ring_sz);
+            ring.cq.ring_sz = ring.sq.ring_sz;
+        }
+
+        ring.sq.ring_ptr = map_ring(ring.ring_fd, ring.sq.ring_sz, IORING_OFF_SQ_RING)?;
+
+        ring.cq.ring_ptr = if params.features & IORING_FEAT_SINGLE_MMAP != 0 {
+            ring.sq.ring_ptr
+        } else {
+            map_ring(ring.ring_fd, ring.cq.ring_sz, IORING_OFF_CQ_RING)?
+        };
+
+        let sq = &mut ring.sq;
+        sq.khead = field(sq.ring_ptr, sq_off.head);
+        sq.ktail = field(sq.ring_ptr, sq_off.tail);
+        sq.kring_mask = field(sq.ring_ptr, sq_off.ring_mask);
+        sq.kring_entries = field(sq.ring_ptr, sq_off.ring_entries);
+        sq.kflags = field(sq.ring_ptr, sq_off.flags);
+        sq.kdropped = field(sq.ring_ptr, sq_off.dropped);
+        sq.array = field(sq.ring_ptr, sq_off.array);
+
+        let cq = &mut ring.cq;
+        cq.khead = field(cq.ring_ptr, cq_off.head);
+        cq.ktail = field(cq.ring_ptr, cq_off.tail);
+        cq.kring_mask = field(cq.ring_ptr, cq_off.ring_mask);
+        cq.kring_entries = field(cq.ring_ptr, cq_off.ring_entries);
+        cq.kflags = field(cq.ring_ptr, cq_off.flags);
+        cq.koverflow = field(cq.ring_ptr, cq_off.overflow);
+        cq.cqes = field(cq.ring_ptr, cq_off.cqes);
+
+        ring.sq.sqes =
+            map_ring(ring.ring_fd, ring.sqes_len(), IORING_OFF_SQES)? as *mut io_uring_sqe;
+
+        Ok(ring)
+    }
+
+    fn sqes_len(&self) -> size_t {
+        (unsafe { *self.sq.kring_entries } as usize * mem::size_of::<io_uring_sqe>()) as size_t
+    }
+
+    #[cfg(feature = "io_uring")]
+    pub(crate) fn features(&self) -> u32 {
+        self.features
+    }
+
+    /// Returns the next free submission entry, or `None` if every entry is
+    /// waiting on the kernel.
+    pub(crate) fn get_sqe(&mut self) -> Option<&mut io_uring_sqe> {
+        let sq = &mut self.sq;
+
+        unsafe {
+            let head = (*sq.khead).load(Ordering::Acquire);
+
+            if sq.sqe_tail.wrapping_sub(head) >= *sq.kring_entries {
+                return None;
+            }
+
+            let sqe = &mut *sq.sqes.add((sq.sqe_tail & *sq.kring_mask) as usize);
+            sq.sqe_tail = sq.sqe_tail.wrapping_add(1);
+
+            Some(sqe)
+        }
+    }
+
+    /// Publishes every entry handed out by `get_sqe` and returns how many the
+    /// kernel has yet to consume.
+    fn flush(&mut self) -> u32 {
+        let sq = &mut self.sq;
+
+        unsafe {
+            let mask = *sq.kring_mask;
+            let mut tail = (*sq.ktail).load(Ordering::Relaxed);
+
+            while sq.sqe_head != sq.sqe_tail {
+                *sq.array.add((tail & mask) as usize) = sq.sqe_head & mask;
+                tail = tail.wrapping_add(1);
+                sq.sqe_head = sq.sqe_head.wrapping_add(1);
+            }
+
+            (*sq.ktail).store(tail, Ordering::Release);
+
+            tail.wrapping_sub((*sq.khead).load(Ordering::Acquire))
+        }
+    }
+
+    /// Submits everything queued and waits for at least `wait_nr`
+    /// completions. Returns the number submitted, or -errno.
+    pub(crate) fn submit_and_wait(&mut self, wait_nr: u32) -> isize {
+        let submitted = self.flush();
+        let mut flags = 0;
+
+        let need_enter = if self.flags & IORING_SETUP_SQPOLL != 0 {
+            // the kernel thread picks up new entries on its own unless it has gone to sleep. it
+            // has to see our tail before we look at its flags
+            atomic::fence(Ordering::SeqCst);
+
+            if unsafe { (*self.sq.kflags).load(Ordering::Relaxed) } & IORING_SQ_NEED_WAKEUP != 0 {
+                flags |= IORING_ENTER_SQ_WAKEUP;
+            }
+
+            flags != 0 || wait_nr > 0
+        } else {
+            submitted > 0 || wait_nr > 0
+        };
+
+        if wait_nr > 0 {
+            flags |= IORING_ENTER_GETEVENTS;
+        }
+
+        if !need_enter {
+            return submitted as isize;
+        }
+
+        let result = unsafe { sys::io_uring_enter(self.ring_fd, submitted, wait_nr, flags) };
+
+        if result >= 0 && self.flags & IORING_SETUP_SQPOLL != 0 {
+            submitted as isize
+        } else {
+            result
+        }
+    }
+
+    /// Returns the oldest completion that hasn't been marked seen.
+    pub(crate) fn peek_cqe(&self) -> Option<&io_uring_cqe> {
+        let cq = &self.cq;
+
+        unsafe {
+            let head = (*cq.khead).load(Ordering::Relaxed);
+            let tail = (*cq.ktail).load(Ordering::Acquire);
+
+            if head == tail {
+                None
+            } else {
+                Some(&*cq.cqes.add((head & *cq.kring_mask) as usize))
+            }
+        }
+    }
+
+    /// Blocks until there is a completion to return. Returns -errno if the
+    /// wait fails, including -`EINTR` if a signal interrupts it.
+    pub(crate) fn wait_cqe(&mut self) -> Result<&io_uring_cqe, isize> {
+        while self.peek_cqe().is_none() {
+            let result = self.submit_and_wait(1);
+
+            if result < 0 {
+                return Err(result);
+            }
+        }
+
+        Ok(self.peek_cqe().unwrap())
+    }
+
+    /// Hands the completion returned by `peek_cqe` back to the kernel.
+    pub(crate) fn cqe_seen(&mut self) {
+        unsafe {
+            let head = (*self.cq.khead).load(Ordering::Relaxed);
+            (*self.cq.khead).store(head.wrapping_add(1), Ordering::Release);
+        }
+    }
+
+    /// Runs a single operation to completion on an otherwise idle ring and
+    /// returns its result the way a syscall would: the value, or -errno.
+    ///
+    /// Returns `Err` if the operation never happened, so the caller is free
+    /// to do the same thing another way. A signal that interrupts the wait
+    /// cancels the operation and returns `Err(EINTR)`, leaving the ring idle
+    /// for the caller to start it over or give up, as the signal's handler
+    /// calls for. After any other `Err` the ring may still have work in
+    /// flight.
+    #[cfg(feature = "io_uring")]
+    pub(crate) fn run(&mut self, prep: impl FnOnce(&mut io_uring_sqe)) -> Result<isize, c_int> {
+        let sqe = match self.get_sqe() {
+            Some(sqe) => sqe,
+            None => return Err(errno::EAGAIN),
+        };
+
+        prep(sqe);
+        sqe.user_data = RUN_OPERATION;
+
+        let result = self.submit_and_wait(1);
+
+        if result <= 0 {
+            // the entry is still sitting in the ring; take it back so a later submission doesn't
+            // run it after all
+            unsafe {
+                let tail = (*self.sq.ktail).load(Ordering::Relaxed);
+                (*self.sq.ktail).store(tail.wrapping_sub(1), Ordering::Release);
+            }
+
+            return Err(if result < 0 {
+                -result as c_int
+            } else {
+                errno::EAGAIN
+            });
+        }
+
+        if let Some(cqe) = self.peek_cqe() {
+            let res = cqe.res as isize;
+            self.cqe_seen();
+
+            return Ok(res);
+        }
+
+        // it was submitted, but the wait ended early, which means a signal. take the operation
+        // back unless it has already finished, and collect both completions so none are left
+        // over for the next caller
+        let sqe = self.get_sqe().ok_or(errno::EAGAIN)?;
+        sqe.prep_rw(IORING_OP_ASYNC_CANCEL, -1, RUN_OPERATION, 0, 0);
+        sqe.user_data = RUN_CANCEL;
+
+        let mut operation_result = None;
+        let mut cancelled = false;
+
+        let res = loop {
+            let result = self.submit_and_wait(1);
+
+            if result < 0 && result != -errno::EINTR as isize {
+                return Err(-result as c_int);
+            }
+
+            while let Some(cqe) = self.peek_cqe() {
+                if cqe.user_data == RUN_OPERATION {
+                    operation_result = Some(cqe.res as isize);
+                } else {
+                    cancelled = true;
+                }
+
+                self.cqe_seen();
+            }
+
+            if let (Some(res), true) = (operation_result, cancelled) {
+                break res;
+            }
+        };
+
+        if res == -errno::ECANCELED as isize || res == -errno::EINTR as isize {
+            Err(errno::EINTR)
+        } else {
+            Ok(res)
+        }
+    }
+}
+
+impl Drop for io_uring {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.sq.sqes.is_null() {
+                mman::sys::munmap(self.sq.sqes as *mut c_void, self.sqes_len());
+            }
+
+            if !self.cq.ring_ptr.is_null() && self.cq.ring_ptr != self.sq.ring_ptr {
+                mman::sys::munmap(self.cq.ring_ptr, self.cq.ring_sz);
+            }
+
+            if !self.sq.ring_ptr.is_null() {
+                mman::sys::munmap(self.sq.ring_ptr, self.sq.ring_sz);
+            }
+
+            unistd::sys::close(self.ring_fd);
+        }
+    }
+}
+
+// like liburing, these return -errno rather than setting errno
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_queue_init(
+    entries: c_unsignedint,
+    ring: *mut io_uring,
+    flags: c_unsignedint,
+) -> c_int {
+    match io_uring::new(entries, flags) {
+        Ok(new_ring) => {
+            ptr::write(ring, new_ring);
+
+            0
+        }
+        Err(e) => -e,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_queue_exit(ring: *mut io_uring) {
+    ptr::drop_in_place(ring);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_get_sqe(ring: *mut io_uring) -> *mut io_uring_sqe {
+    match (*ring).get_sqe() {
+        Some(sqe) => sqe,
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_submit(ring: *mut io_uring) -> c_int {
+    (*ring).submit_and_wait(0) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_submit_and_wait(
+    ring: *mut io_uring,
+    wait_nr: c_unsignedint,
+) -> c_int {
+    (*ring).submit_and_wait(wait_nr) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_peek_cqe(
+    ring: *mut io_uring,
+    cqe_ptr: *mut *mut io_uring_cqe,
+) -> c_int {
+    match (*ring).peek_cqe() {
+        Some(cqe) => {
+            *cqe_ptr = cqe as *const io_uring_cqe as *mut io_uring_cqe;
+
+            0
+        }
+        None => {
+            *cqe_ptr = ptr::null_mut();
+
+            -errno::EAGAIN
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_wait_cqe(
+    ring: *mut io_uring,
+    cqe_ptr: *mut *mut io_uring_cqe,
+) -> c_int {
+    match (*ring).wait_cqe() {
+        Ok(cqe) => {
+            *cqe_ptr = cqe as *const io_uring_cqe as *mut io_uring_cqe;
+
+            0
+        }
+        Err(e) => {
+            *cqe_ptr = ptr::null_mut();
+
+            e as c_int
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_cqe_seen(ring: *mut io_uring, cqe: *mut io_uring_cqe) {
+    if !cqe.is_null() {
+        (*ring).cqe_seen();
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_sqe_set_data(sqe: *mut io_uring_sqe, data: *mut c_void) {
+    (*sqe).user_data = data as u64;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_cqe_get_data(cqe: *const io_uring_cqe) -> *mut c_void {
+    (*cqe).user_data as *mut c_void
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_nop(sqe: *mut io_uring_sqe) {
+    (*sqe).prep_rw(IORING_OP_NOP, -1, 0, 0, 0);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_read(
+    sqe: *mut io_uring_sqe,
+    fd: c_int,
+    buf: *mut c_void,
+    nbytes: c_unsignedint,
+    offset: u64,
+) {
+    (*sqe).prep_rw(IORING_OP_READ, fd, buf as u64, nbytes, offset);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_write(
+    sqe: *mut io_uring_sqe,
+    fd: c_int,
+    buf: *const c_void,
+    nbytes: c_unsignedint,
+    offset: u64,
+) {
+    (*sqe).prep_rw(IORING_OP_WRITE, fd, buf as u64, nbytes, offset);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_readv(
+    sqe: *mut io_uring_sqe,
+    fd: c_int,
+    iovecs: *const iovec,
+    nr_vecs: c_unsignedint,
+    offset: u64,
+) {
+    (*sqe).prep_rw(IORING_OP_READV, fd, iovecs as u64, nr_vecs, offset);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_writev(
+    sqe: *mut io_uring_sqe,
+    fd: c_int,
+    iovecs: *const iovec,
+    nr_vecs: c_unsignedint,
+    offset: u64,
+) {
+    (*sqe).prep_rw(IORING_OP_WRITEV, fd, iovecs as u64, nr_vecs, offset);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_openat(
+    sqe: *mut io_uring_sqe,
+    dfd: c_int,
+    path: *const c_char,
+    flags: c_int,
+    mode: mode_t,
+) {
+    (*sqe).prep_rw(IORING_OP_OPENAT, dfd, path as u64, mode, 0);
+    (*sqe).op_flags = flags as u32;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_close(sqe: *mut io_uring_sqe, fd: c_int) {
+    (*sqe).prep_rw(IORING_OP_CLOSE, fd, 0, 0, 0);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_accept(
+    sqe: *mut io_uring_sqe,
+    fd: c_int,
+    addr: *mut c_void,
+    addrlen: *mut c_unsignedint,
+    flags: c_int,
+) {
+    (*sqe).prep_rw(IORING_OP_ACCEPT, fd, addr as u64, 0, addrlen as u64);
+    (*sqe).op_flags = flags as u32;
+}
+
+/// Completes after `count` other completions or once `ts` has passed,
+/// whichever is first; in the second case with `-ETIME`.
+#[no_mangle]
+pub unsafe extern "C" fn io_uring_prep_timeout(
+    sqe: *mut io_uring_sqe,
+    ts: *mut timespec,
+    count: c_unsignedint,
+    flags: c_unsignedint,
+) {
+    (*sqe).prep_rw(IORING_OP_TIMEOUT, -1, ts as u64, 1, count as u64);
+    (*sqe).op_flags = flags;
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    pub(crate) unsafe fn io_uring_setup(entries: u32, p: *mut io_uring_params) -> isize {
+        syscall!(425, entries as isize, p as isize)
+    }
+
+    pub(crate) unsafe fn io_uring_enter(
+        fd: c_int,
+        to_submit: u32,
+        min_complete: u32,
+        flags: u32,
+    ) -> isize {
+        syscall!(
+            426,
+            fd as isize,
+            to_submit as isize,
+            min_complete as isize,
+            flags as isize,
+            0,
+            0
+        )
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_int, c_unsignedlong, c_void, errno,
+    internal::{self, alloc, TCBBox, ThreadControlBlock},
+    linux::futex,
+    sched::{
+        CLONE_CHILD_CLEARTID, CLONE_FILES, CLONE_FS, CLONE_PARENT_SETTID, CLONE_SETTLS,
+        CLONE_SIGHAND, CLONE_SYSVSEM, CLONE_THREAD, CLONE_VM,
+    },
+    stddef::size_t,
+    stdlib,
+    sys::mman,
+    syscall,
+};
+
+use core::{
+    hint, mem, ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+pub type pthread_t = c_unsignedlong;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct pthread_attr_t {
+    stacksize: size_t,
+    detachstate: c_int,
+}
+
+pub const PTHREAD_CREATE_JOINABLE: c_int = 0;
+pub const PTHREAD_CREATE_DETACHED: c_int = 1;
+
+pub const PTHREAD_STACK_MIN: size_t = 16384;
+
+const DEFAULT_STACK_LEN: usize = 8 * (1 << 20); // 8 MiB, same as the usual RLIMIT_STACK
+
+// values of ThreadControlBlock::detach_state
+const JOINABLE: c_int = 0;
+const DETACHED: c_int = 1;
+const EXITED: c_int = 2;
+
+// the main thread is always running until it calls pthread_exit
+static NUM_THREADS: AtomicUsize = AtomicUsize::new(1);
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_init(attr: *mut pthread_attr_t) -> c_int {
+    if attr.is_null() {
+        return errno::EINVAL;
+    }
+
+    *attr = pthread_attr_t {
+        stacksize: DEFAULT_STACK_LEN as size_t,
+        detachstate: PTHREAD_CREATE_JOINABLE,
+    };
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_destroy(attr: *mut pthread_attr_t) -> c_int {
+    if attr.is_null() {
+        return errno::EINVAL;
+    }
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_setdetachstate(
+    attr: *mut pthread_attr_t,
+    detachstate: c_int,
+) -> c_int {
+    if attr.is_null()
+        || (detachstate != PTHREAD_CREATE_JOINABLE && detachstate != PTHREAD_CREATE_DETACHED)
+    {
+        return errno::EINVAL;
+    }
+
+    (*attr).detachstate = detachstate;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_getdetachstate(
+    attr: *const pthread_attr_t,
+    detachstate: *mut c_int,
+) -> c_int {
+    if attr.is_null() || detachstate.is_null() {
+        return errno::EINVAL;
+    }
+
+    *detachstate = (*attr).detachstate;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_setstacksize(
+    attr: *mut pthread_attr_t,
+    stacksize: size_t,
+) -> c_int {
+    if attr.is_null() || stacksize < PTHREAD_STACK_MIN {
+        return errno::EINVAL;
+    }
+
+    (*attr).stacksize = stacksize;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_attr_getstacksize(
+    attr: *const pthread_attr_t,
+    stacksize: *mut size_t,
+) -> c_int {
+    if attr.is_null() || stacksize.is_null() {
+        return errno::EINVAL;
+    }
+
+    *stacksize = (*attr).stacksize;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_create(
+    thread: *mut pthread_t,
+    attr: *const pthread_attr_t,
+    start_routine: Option<unsafe extern "C" fn(*mut c_void) -> *mut c_void>,
+    arg: *mut c_void,
+) -> c_int {
+    if thread.is_null() || start_routine.is_none() {
+        return errno::EINVAL;
+    }
+
+    let (stack_len, detach_state) = if let Some(attr) = attr.as_ref() {
+        let detach_state = if attr.detachstate == PTHREAD_CREATE_DETACHED {
+            DETACHED
+        } else {
+            JOINABLE
+        };
+
+        (attr.stacksize as usize, detach_state)
+    } else {
+        (DEFAULT_STACK_LEN, JOINABLE)
+    };
+
+    let guard_len = internal::page_size();
+    let stack_len = internal::round_up_to_nearest_multiple(stack_len, guard_len) + guard_len;
+
+    let mut tcb = match TCBBox::with_stack(stack_len) {
+        Ok(b) => b,
+        Err(_) => return errno::EAGAIN,
+    };
+
+    // the lowest page of the stack traps overflows instead of scribbling over whatever is below
+    let (mapping_start, _) = tcb.mapping();
+    let rax = mman::sys::mprotect(mapping_start, guard_len as size_t, mman::PROT_NONE);
+
+    if rax < 0 {
+        return creation_error(rax);
+    }
+
+    tcb.start_routine = start_routine;
+    tcb.arg = arg;
+    tcb.detach_state.store(detach_state, Ordering::Relaxed);
+
+    let stack_top = tcb.stack_top();
+    let tcb_ptr = TCBBox::into_raw(tcb);
+    let tid_ptr = (*tcb_ptr).tid.as_ptr();
+
+    NUM_THREADS.fetch_add(1, Ordering::Relaxed);
+
+    // after this point the new thread may have already exited and (if detached) freed its TCB
+    *thread = tcb_ptr as pthread_t;
+
+    let rax = sys::clone(
+        CLONE_VM
+            | CLONE_FS
+            | CLONE_FILES
+            | CLONE_SIGHAND
+            | CLONE_THREAD
+            | CLONE_SYSVSEM
+            | CLONE_SETTLS
+            | CLONE_PARENT_SETTID
+            | CLONE_CHILD_CLEARTID,
+        stack_top,
+        tid_ptr,
+        tid_ptr,
+        tcb_ptr as *mut c_void,
+    );
+
+    if rax < 0 {
+        NUM_THREADS.fetch_sub(1, Ordering::Relaxed);
+        mem::drop(TCBBox::from_raw(tcb_ptr));
+
+        return creation_error(rax);
+    }
+
+    0
+}
+
+// pthread_create reports running out of memory the same way as running out of threads
+fn creation_error(rax: isize) -> c_int {
+    match -rax as c_int {
+        errno::ENOMEM => errno::EAGAIN,
+        e => e,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_join(thread: pthread_t, retval: *mut *mut c_void) -> c_int {
+    let tcb_ptr = thread as *mut ThreadControlBlock;
+
+    if tcb_ptr.is_null() {
+        return errno::ESRCH;
+    }
+
+    if ptr::eq(tcb_ptr, internal::tcb()) {
+        return errno::EDEADLK;
+    }
+
+    let tcb = &*tcb_ptr;
+
+    if tcb.stack_top().is_null() || tcb.detach_state.load(Ordering::Relaxed) == DETACHED {
+        return errno::EINVAL;
+    }
+
+    // the kernel zeroes tid and wakes us once the thread is off its stack for good
+    loop {
+        let tid = tcb.tid.load(Ordering::Acquire);
+
+        if tid == 0 {
+            break;
+        }
+
+        futex::sys::futex_wait(tcb.tid.as_ptr(), tid, ptr::null());
+    }
+
+    if !retval.is_null() {
+        *retval = tcb.result;
+    }
+
+    mem::drop(TCBBox::from_raw(tcb_ptr));
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_detach(thread: pthread_t) -> c_int {
+    let tcb_ptr = thread as *mut ThreadControlBlock;
+
+    if tcb_ptr.is_null() {
+        return errno::ESRCH;
+    }
+
+    let tcb = &*tcb_ptr;
+
+    if tcb.stack_top().is_null() {
+        return errno::EINVAL;
+    }
+
+    match tcb
+        .detach_state
+        .compare_exchange(JOINABLE, DETACHED, Ordering::AcqRel, Ordering::Acquire)
+    {
+        Ok(_) => 0,
+        // already on its way out; nobody else will reap it
+        Err(EXITED) => pthread_join(thread, ptr::null_mut()),
+        Err(_) => errno::EINVAL,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_exit(retval: *mut c_void) -> ! {
+    let tcb = internal::tcb();
+    tcb.result = retval;
+
+    if NUM_THREADS.fetch_sub(1, Ordering::AcqRel) == 1 {
+        stdlib::exit(0);
+    }
+
+    if tcb.stack_top().is_null() {
+        // main thread: its stack and TCB stay around for the rest of the process
+        sys::exit(0);
+    }
+
+    #[cfg(feature = "io_uring")]
+    internal::io_uring::thread_finalize();
+    alloc::thread_finalize();
+
+    if tcb
+        .detach_state
+        .compare_exchange(JOINABLE, EXITED, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+    {
+        sys::exit(0);
+    }
+
+    // detached: nobody will join us, so free our own stack. the kernel must not clear tid after
+    // the mapping is gone, since someone else may have mapped that page by then
+    let (start, len) = tcb.mapping();
+    sys::set_tid_address(ptr::null_mut());
+    sys::unmap_self_and_exit(start, len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_self() -> pthread_t {
+    internal::tcb() as *mut ThreadControlBlock as pthread_t
+}
+
+#[no_mangle]
+pub extern "C" fn pthread_equal(t1: pthread_t, t2: pthread_t) -> c_int {
+    (t1 == t2) as c_int
+}
+
+/// Called in the child of a fork, where only the thread that forked is left.
+pub(crate) unsafe fn reset_after_fork() {
+    NUM_THREADS.store(1, Ordering::Relaxed);
+    internal::tcb()
+        .tid
+        .store(sys::gettid() as c_int, Ordering::Relaxed);
+
+    #[cfg(feature = "io_uring")]
+    internal::io_uring::reset_after_fork();
+}
+
+unsafe extern "C" fn start_thread() -> ! {
+    alloc::thread_initialize();
+
+    let tcb = internal::tcb();
+    let result = (tcb.start_routine.unwrap())(tcb.arg);
+
+    pthread_exit(result)
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    /// Starts a new thread running `start_thread` on `stack_top`. Returns the
+    /// child's thread ID in the calling thread.
+    pub(crate) unsafe fn clone(
+        flags: c_int,
+        stack_top: *mut c_void,
+        parent_tid: *mut c_int,
+        child_tid: *mut c_int,
+        tls: *mut c_void,
+    ) -> isize {
+        let mut rax: isize = 56;
+
+        // the child returns from the syscall on its new, empty stack; it can't touch anything
+        // the compiler put on the parent's stack, so call straight into start_thread
+        asm!(
+            "syscall",
+            "test rax, rax",
+            "jnz 2f",
+            "xor ebp, ebp",
+            "call {start_thread}",
+            "ud2",
+            "2:",
+            start_thread = sym start_thread,
+            inlateout("rax") rax,
+            in("rdi") flags as isize,
+            in("rsi") stack_top as isize,
+            in("rdx") parent_tid as isize,
+            in("r10") child_tid as isize,
+            in("r8") tls as isize,
+            lateout("rcx") _,
+            lateout("r11") _,
+        );
+
+        rax
+    }
+
+    pub(crate) unsafe fn exit(status: c_int) -> ! {
+        syscall!(60, status as isize);
+
+        hint::unreachable_unchecked();
+    }
+
+    pub(crate) unsafe fn gettid() -> isize {
+        syscall!(186)
+    }
+
+    pub(crate) unsafe fn set_tid_address(tidptr: *mut c_int) -> isize {
+        syscall!(218, tidptr as isize)
+    }
+
+    /// Unmaps the calling thread's own stack and exits without touching it again.
+    pub(crate) unsafe fn unmap_self_and_exit(start: *mut c_void, len: size_t) -> ! {
+        asm!(
+            "syscall",
+            "mov eax, 60",
+            "xor edi, edi",
+            "syscall",
+            "ud2",
+            in("rax") 11isize,
+            in("rdi") start as isize,
+            in("rsi") len as isize,
+            options(noreturn),
+        );
+    }
+}
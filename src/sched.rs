@@ -0,0 +1,94 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_int, c_unsignedlong,
+    internal::{self, errno::ErrorNumber},
+    stddef::size_t,
+    sys::types::pid_t,
+    syscall,
+};
+
+use core::{mem, ptr, slice};
+
+pub const CLONE_VM: c_int = 0x00000100;
+pub const CLONE_FS: c_int = 0x00000200;
+pub const CLONE_FILES: c_int = 0x00000400;
+pub const CLONE_SIGHAND: c_int = 0x00000800;
+pub const CLONE_VFORK: c_int = 0x00004000;
+pub const CLONE_PARENT: c_int = 0x00008000;
+pub const CLONE_THREAD: c_int = 0x00010000;
+pub const CLONE_SYSVSEM: c_int = 0x00040000;
+pub const CLONE_SETTLS: c_int = 0x00080000;
+pub const CLONE_PARENT_SETTID: c_int = 0x00100000;
+pub const CLONE_CHILD_CLEARTID: c_int = 0x00200000;
+pub const CLONE_CHILD_SETTID: c_int = 0x01000000;
+
+pub const CPU_SETSIZE: usize = 1024;
+
+const BITS_PER_WORD: usize = mem::size_of::<c_unsignedlong>() * 8;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct cpu_set_t {
+    bits: [c_unsignedlong; CPU_SETSIZE / BITS_PER_WORD],
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sched_getaffinity(
+    pid: pid_t,
+    cpusetsize: size_t,
+    mask: *mut cpu_set_t,
+) -> c_int {
+    match ErrorNumber::from_syscall::<usize>(sys::sched_getaffinity(pid, cpusetsize, mask)) {
+        Ok(written) => {
+            // the kernel only writes as many bytes as it has CPUs for
+            ptr::write_bytes(
+                (mask as *mut u8).add(written),
+                0,
+                cpusetsize as usize - written,
+            );
+
+            0
+        }
+        Err(e) => {
+            *internal::errno() = e.into_int();
+
+            -1
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn __sched_cpucount(setsize: size_t, setp: *const cpu_set_t) -> c_int {
+    let words = slice::from_raw_parts(
+        setp as *const c_unsignedlong,
+        setsize as usize / mem::size_of::<c_unsignedlong>(),
+    );
+
+    words.iter().map(|w| w.count_ones() as c_int).sum()
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    pub(crate) unsafe fn sched_getaffinity(
+        pid: pid_t,
+        cpusetsize: size_t,
+        mask: *mut cpu_set_t,
+    ) -> isize {
+        syscall!(204, pid as isize, cpusetsize as isize, mask as isize)
+    }
+}
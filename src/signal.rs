@@ -0,0 +1,531 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_int, c_long, c_unsignedlong, c_void, errno, internal, pthread, stddef::size_t,
+    sys::types::pid_t, syscall, time::timespec, unistd, wrap_syscall,
+};
+
+use core::{
+    mem, ptr,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+pub const SIGHUP: c_int = 1;
+pub const SIGINT: c_int = 2;
+pub const SIGQUIT: c_int = 3;
+pub const SIGILL: c_int = 4;
+pub const SIGTRAP: c_int = 5;
+pub const SIGABRT: c_int = 6;
+pub const SIGIOT: c_int = SIGABRT;
+pub const SIGBUS: c_int = 7;
+pub const SIGFPE: c_int = 8;
+pub const SIGKILL: c_int = 9;
+pub const SIGUSR1: c_int = 10;
+pub const SIGSEGV: c_int = 11;
+pub const SIGUSR2: c_int = 12;
+pub const SIGPIPE: c_int = 13;
+pub const SIGALRM: c_int = 14;
+pub const SIGTERM: c_int = 15;
+pub const SIGSTKFLT: c_int = 16;
+pub const SIGCHLD: c_int = 17;
+pub const SIGCONT: c_int = 18;
+pub const SIGSTOP: c_int = 19;
+pub const SIGTSTP: c_int = 20;
+pub const SIGTTIN: c_int = 21;
+pub const SIGTTOU: c_int = 22;
+pub const SIGURG: c_int = 23;
+pub const SIGXCPU: c_int = 24;
+pub const SIGXFSZ: c_int = 25;
+pub const SIGVTALRM: c_int = 26;
+pub const SIGPROF: c_int = 27;
+pub const SIGWINCH: c_int = 28;
+pub const SIGIO: c_int = 29;
+pub const SIGPOLL: c_int = SIGIO;
+pub const SIGPWR: c_int = 30;
+pub const SIGSYS: c_int = 31;
+pub const SIGRTMIN: c_int = 32;
+pub const SIGRTMAX: c_int = 64;
+
+// one more than the highest signal number
+pub const NSIG: c_int = 65;
+
+pub type sig_atomic_t = c_int;
+
+pub type sighandler_t = usize;
+
+pub const SIG_DFL: sighandler_t = 0;
+pub const SIG_IGN: sighandler_t = 1;
+pub const SIG_ERR: sighandler_t = !0;
+
+pub const SA_NOCLDSTOP: c_int = 0x00000001;
+pub const SA_NOCLDWAIT: c_int = 0x00000002;
+pub const SA_SIGINFO: c_int = 0x00000004;
+pub const SA_RESTORER: c_int = 0x04000000;
+pub const SA_ONSTACK: c_int = 0x08000000;
+pub const SA_RESTART: c_int = 0x10000000;
+pub const SA_NODEFER: c_int = 0x40000000;
+pub const SA_RESETHAND: c_int = 0x80000000u32 as c_int;
+
+pub const SIG_BLOCK: c_int = 0;
+pub const SIG_UNBLOCK: c_int = 1;
+pub const SIG_SETMASK: c_int = 2;
+
+pub const SS_ONSTACK: c_int = 1;
+pub const SS_DISABLE: c_int = 2;
+
+pub const MINSIGSTKSZ: size_t = 2048;
+pub const SIGSTKSZ: size_t = 8192;
+
+// values of siginfo_t::si_code for SIGCHLD
+pub const CLD_EXITED: c_int = 1;
+pub const CLD_KILLED: c_int = 2;
+pub const CLD_DUMPED: c_int = 3;
+pub const CLD_TRAPPED: c_int = 4;
+pub const CLD_STOPPED: c_int = 5;
+pub const CLD_CONTINUED: c_int = 6;
+
+const BITS_PER_WORD: usize = mem::size_of::<c_unsignedlong>() * 8;
+
+// the kernel only looks at the first word of a sigset_t
+const KERNEL_SIGSET_LEN: usize = mem::size_of::<c_unsignedlong>();
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct siginfo_t {
+    pub si_signo: c_int,
+    pub si_errno: c_int,
+    pub si_code: c_int,
+    // what's in here depends on si_signo and si_code; only C looks inside
+    fields: [c_long; 14],
+}
+
+/// Room for 1024 signals, like glibc, though Linux only has 64.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct sigset_t {
+    bits: [c_unsignedlong; 1024 / BITS_PER_WORD],
+}
+
+impl sigset_t {
+    pub(crate) const fn empty() -> Self {
+        Self {
+            bits: [0; 1024 / BITS_PER_WORD],
+        }
+    }
+
+    pub(crate) fn full() -> Self {
+        let mut set = Self::empty();
+        set.bits[0] = !0;
+
+        set
+    }
+
+    pub(crate) fn contains(&self, sig: c_int) -> bool {
+        let (word, mask) = bit(sig).unwrap();
+
+        self.bits[word] & mask != 0
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct sigaction {
+    // sa_handler in C, or sa_sigaction if SA_SIGINFO is set
+    pub sa_sigaction: sighandler_t,
+    pub sa_mask: sigset_t,
+    pub sa_flags: c_int,
+    pub sa_restorer: Option<unsafe extern "C" fn()>,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct stack_t {
+    pub ss_sp: *mut c_void,
+    pub ss_flags: c_int,
+    pub ss_size: size_t,
+}
+
+/// The `struct sigaction` that the kernel takes.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct KernelSigaction {
+    pub(crate) handler: sighandler_t,
+    pub(crate) flags: c_unsignedlong,
+    pub(crate) restorer: usize,
+    pub(crate) mask: c_unsignedlong,
+}
+
+extern "C" {
+    // calls rt_sigreturn; see restore.asm
+    fn __KNS_restore_rt();
+}
+
+// only ever used to initialize the arrays below
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_HANDLER: AtomicUsize = AtomicUsize::new(SIG_DFL);
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_SIGINFO: AtomicBool = AtomicBool::new(false);
+#[cfg(feature = "io_uring")]
+#[allow(clippy::declare_interior_mutable_const)]
+const NO_RESTART: AtomicBool = AtomicBool::new(false);
+
+// the kernel only ever sees `dispatch` as a handler. these are what it calls, and whether the
+// program asked for SA_SIGINFO, which only matters to anyone asking for the old action
+static HANDLERS: [AtomicUsize; NSIG as usize] = [NO_HANDLER; NSIG as usize];
+static WANTS_SIGINFO: [AtomicBool; NSIG as usize] = [NO_SIGINFO; NSIG as usize];
+// and whether it asked for SA_RESTART, which the kernel honors for syscalls but stdio has to
+// honor itself for operations on its io_uring
+#[cfg(feature = "io_uring")]
+static RESTARTS: [AtomicBool; NSIG as usize] = [NO_RESTART; NSIG as usize];
+
+/// Stands in for every handler so that errno, which lives in the
+/// interrupted thread's control block, is the same after a handler as before.
+unsafe extern "C" fn dispatch(sig: c_int, info: *mut siginfo_t, context: *mut c_void) {
+    let saved_errno = *internal::errno();
+
+    // calling a one-argument handler with three arguments is fine in the x86-64 ABI
+    let handler: unsafe extern "C" fn(c_int, *mut siginfo_t, *mut c_void) =
+        mem::transmute(HANDLERS[sig as usize].load(Ordering::Acquire));
+    handler(sig, info, context);
+
+    #[cfg(feature = "io_uring")]
+    {
+        if !RESTARTS[sig as usize].load(Ordering::Relaxed) {
+            internal::tcb().interrupted_without_restart = true;
+        }
+    }
+
+    *internal::errno() = saved_errno;
+}
+
+pub(crate) fn is_dispatched(action: &KernelSigaction) -> bool {
+    action.handler == dispatch as *const () as sighandler_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigaction(
+    sig: c_int,
+    act: *const sigaction,
+    oldact: *mut sigaction,
+) -> c_int {
+    if bit(sig).is_none() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    let index = sig as usize;
+    let mut new = KernelSigaction::default();
+    let mut old = KernelSigaction::default();
+    let mut previous = None;
+
+    if let Some(act) = act.as_ref() {
+        let mut flags = act.sa_flags | SA_RESTORER;
+        let handler = if act.sa_sigaction == SIG_DFL || act.sa_sigaction == SIG_IGN {
+            act.sa_sigaction
+        } else {
+            previous = Some((
+                HANDLERS[index].swap(act.sa_sigaction, Ordering::AcqRel),
+                WANTS_SIGINFO[index].swap(act.sa_flags & SA_SIGINFO != 0, Ordering::Relaxed),
+            ));
+            flags |= SA_SIGINFO;
+
+            dispatch as *const () as sighandler_t
+        };
+
+        new = KernelSigaction {
+            handler,
+            flags: flags as c_unsignedlong,
+            restorer: __KNS_restore_rt as *const () as usize,
+            mask: act.sa_mask.bits[0],
+        };
+    }
+
+    let new_ptr = if act.is_null() { ptr::null() } else { &new };
+
+    if wrap_syscall!(sys::rt_sigaction(sig, new_ptr, &mut old)) == -1 {
+        if let Some((handler, wants_siginfo)) = previous {
+            HANDLERS[index].store(handler, Ordering::Release);
+            WANTS_SIGINFO[index].store(wants_siginfo, Ordering::Relaxed);
+        }
+
+        return -1;
+    }
+
+    #[cfg(feature = "io_uring")]
+    {
+        if let (Some(_), Some(act)) = (previous, act.as_ref()) {
+            RESTARTS[index].store(act.sa_flags & SA_RESTART != 0, Ordering::Relaxed);
+        }
+    }
+
+    if let Some(oldact) = oldact.as_mut() {
+        let mut flags = old.flags as c_int & !SA_RESTORER;
+        let mut handler = old.handler;
+
+        if is_dispatched(&old) {
+            let (previous_handler, wanted_siginfo) = previous.unwrap_or_else(|| {
+                (
+                    HANDLERS[index].load(Ordering::Acquire),
+                    WANTS_SIGINFO[index].load(Ordering::Relaxed),
+                )
+            });
+
+            handler = previous_handler;
+
+            if !wanted_siginfo {
+                flags &= !SA_SIGINFO;
+            }
+        }
+
+        let mut mask = sigset_t::empty();
+        mask.bits[0] = old.mask;
+
+        *oldact = sigaction {
+            sa_sigaction: handler,
+            sa_mask: mask,
+            sa_flags: flags,
+            sa_restorer: None,
+        };
+    }
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn signal(sig: c_int, handler: sighandler_t) -> sighandler_t {
+    let act = sigaction {
+        sa_sigaction: handler,
+        sa_mask: sigset_t::empty(),
+        sa_flags: SA_RESTART,
+        sa_restorer: None,
+    };
+    let mut old = act;
+
+    if sigaction(sig, &act, &mut old) == -1 {
+        return SIG_ERR;
+    }
+
+    old.sa_sigaction
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigprocmask(
+    how: c_int,
+    set: *const sigset_t,
+    oldset: *mut sigset_t,
+) -> c_int {
+    wrap_syscall!(sys::rt_sigprocmask(how, set, oldset)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pthread_sigmask(
+    how: c_int,
+    set: *const sigset_t,
+    oldset: *mut sigset_t,
+) -> c_int {
+    -sys::rt_sigprocmask(how, set, oldset) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigemptyset(set: *mut sigset_t) -> c_int {
+    *set = sigset_t::empty();
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigfillset(set: *mut sigset_t) -> c_int {
+    *set = sigset_t::full();
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigaddset(set: *mut sigset_t, sig: c_int) -> c_int {
+    update_set(sig, |word, mask| (*set).bits[word] |= mask)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigdelset(set: *mut sigset_t, sig: c_int) -> c_int {
+    update_set(sig, |word, mask| (*set).bits[word] &= !mask)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigismember(set: *const sigset_t, sig: c_int) -> c_int {
+    match bit(sig) {
+        Some((word, mask)) => ((*set).bits[word] & mask != 0) as c_int,
+        None => {
+            *internal::errno() = errno::EINVAL;
+
+            -1
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigpending(set: *mut sigset_t) -> c_int {
+    wrap_syscall!(sys::rt_sigpending(set)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn raise(sig: c_int) -> c_int {
+    wrap_syscall!(sys::tgkill(
+        unistd::sys::getpid() as pid_t,
+        pthread::sys::gettid() as pid_t,
+        sig
+    )) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn kill(pid: pid_t, sig: c_int) -> c_int {
+    wrap_syscall!(sys::kill(pid, sig)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigsuspend(mask: *const sigset_t) -> c_int {
+    wrap_syscall!(sys::rt_sigsuspend(mask)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigwait(set: *const sigset_t, sig: *mut c_int) -> c_int {
+    loop {
+        let rax = sys::rt_sigtimedwait(set, ptr::null_mut(), ptr::null());
+
+        if rax == -errno::EINTR as isize {
+            continue;
+        }
+
+        if rax < 0 {
+            return -rax as c_int;
+        }
+
+        *sig = rax as c_int;
+
+        return 0;
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigwaitinfo(set: *const sigset_t, info: *mut siginfo_t) -> c_int {
+    sigtimedwait(set, info, ptr::null())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigtimedwait(
+    set: *const sigset_t,
+    info: *mut siginfo_t,
+    timeout: *const timespec,
+) -> c_int {
+    wrap_syscall!(sys::rt_sigtimedwait(set, info, timeout)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sigaltstack(ss: *const stack_t, old_ss: *mut stack_t) -> c_int {
+    wrap_syscall!(sys::sigaltstack(ss, old_ss)) as c_int
+}
+
+// the word and bit within a sigset_t that hold `sig`, if it's a signal at all
+fn bit(sig: c_int) -> Option<(usize, c_unsignedlong)> {
+    if sig <= 0 || sig >= NSIG {
+        return None;
+    }
+
+    let index = (sig - 1) as usize;
+
+    Some((index / BITS_PER_WORD, 1 << (index % BITS_PER_WORD)))
+}
+
+fn update_set<F: FnOnce(usize, c_unsignedlong)>(sig: c_int, update: F) -> c_int {
+    match bit(sig) {
+        Some((word, mask)) => {
+            update(word, mask);
+
+            0
+        }
+        None => {
+            unsafe { *internal::errno() = errno::EINVAL };
+
+            -1
+        }
+    }
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    pub(crate) unsafe fn rt_sigaction(
+        sig: c_int,
+        act: *const KernelSigaction,
+        oldact: *mut KernelSigaction,
+    ) -> isize {
+        syscall!(
+            13,
+            sig as isize,
+            act as isize,
+            oldact as isize,
+            KERNEL_SIGSET_LEN as isize
+        )
+    }
+
+    pub(crate) unsafe fn rt_sigprocmask(
+        how: c_int,
+        set: *const sigset_t,
+        oldset: *mut sigset_t,
+    ) -> isize {
+        syscall!(
+            14,
+            how as isize,
+            set as isize,
+            oldset as isize,
+            KERNEL_SIGSET_LEN as isize
+        )
+    }
+
+    pub(crate) unsafe fn rt_sigpending(set: *mut sigset_t) -> isize {
+        syscall!(127, set as isize, KERNEL_SIGSET_LEN as isize)
+    }
+
+    pub(crate) unsafe fn rt_sigtimedwait(
+        set: *const sigset_t,
+        info: *mut siginfo_t,
+        timeout: *const timespec,
+    ) -> isize {
+        syscall!(
+            128,
+            set as isize,
+            info as isize,
+            timeout as isize,
+            KERNEL_SIGSET_LEN as isize
+        )
+    }
+
+    pub(crate) unsafe fn rt_sigsuspend(mask: *const sigset_t) -> isize {
+        syscall!(130, mask as isize, KERNEL_SIGSET_LEN as isize)
+    }
+
+    pub(crate) unsafe fn sigaltstack(ss: *const stack_t, old_ss: *mut stack_t) -> isize {
+        syscall!(131, ss as isize, old_ss as isize)
+    }
+
+    pub(crate) unsafe fn kill(pid: pid_t, sig: c_int) -> isize {
+        syscall!(62, pid as isize, sig as isize)
+    }
+
+    pub(crate) unsafe fn tgkill(tgid: pid_t, tid: pid_t, sig: c_int) -> isize {
+        syscall!(234, tgid as isize, tid as isize, sig as isize)
+    }
+}
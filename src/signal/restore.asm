@@ -0,0 +1,23 @@
+; Copyright (C) 2020 Gregory Meyer
+;
+; This program is free software: you can redistribute it and/or modify
+; it under the terms of the GNU Affero General Public License as published by
+; the Free Software Foundation, either version 3 of the License, or
+; (at your option) any later version.
+;
+; This program is distributed in the hope that it will be useful,
+; but WITHOUT ANY WARRANTY; without even the implied warranty of
+; MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+; GNU Affero General Public License for more details.
+;
+; You should have received a copy of the GNU Affero General Public License
+; along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+global __KNS_restore_rt
+
+section .text
+; the sa_restorer of every handler that sigaction installs. a handler returns here, on top of
+; the frame the kernel pushed to run it, and rt_sigreturn picks up where the signal interrupted
+__KNS_restore_rt:
+    mov eax, 15
+    syscall
@@ -0,0 +1,563 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_char, c_int, c_long, c_short, c_void, errno,
+    fcntl::{self, FD_CLOEXEC, F_GETFD, F_SETFD},
+    internal::alloc::Box,
+    sched::{CLONE_VFORK, CLONE_VM},
+    signal::{self, sigset_t, KernelSigaction, NSIG, SIGCHLD, SIG_BLOCK, SIG_DFL, SIG_SETMASK},
+    stddef::size_t,
+    stdlib, string,
+    sys::{
+        mman,
+        types::{mode_t, pid_t},
+        wait,
+    },
+    unistd,
+};
+
+use core::ptr;
+
+pub const POSIX_SPAWN_RESETIDS: c_short = 0x01;
+pub const POSIX_SPAWN_SETPGROUP: c_short = 0x02;
+pub const POSIX_SPAWN_SETSIGDEF: c_short = 0x04;
+pub const POSIX_SPAWN_SETSIGMASK: c_short = 0x08;
+pub const POSIX_SPAWN_SETSID: c_short = 0x80;
+
+const ALL_FLAGS: c_short = POSIX_SPAWN_RESETIDS
+    | POSIX_SPAWN_SETPGROUP
+    | POSIX_SPAWN_SETSIGDEF
+    | POSIX_SPAWN_SETSIGMASK
+    | POSIX_SPAWN_SETSID;
+
+// plenty for exec_search, which keeps a PATH_MAX buffer on the stack
+const CHILD_STACK_LEN: usize = 64 * 1024;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct posix_spawnattr_t {
+    flags: c_short,
+    pgroup: pid_t,
+    sigdefault: sigset_t,
+    sigmask: sigset_t,
+}
+
+/// A list of actions to run in the child, oldest first.
+#[repr(C)]
+pub struct posix_spawn_file_actions_t {
+    head: *mut FileAction,
+    tail: *mut FileAction,
+}
+
+struct FileAction {
+    kind: FileActionKind,
+    next: *mut FileAction,
+}
+
+enum FileActionKind {
+    Close(c_int),
+    Dup2(c_int, c_int),
+    Open {
+        fd: c_int,
+        path: Box<[u8]>,
+        oflag: c_int,
+        mode: mode_t,
+    },
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawn_file_actions_init(
+    file_actions: *mut posix_spawn_file_actions_t,
+) -> c_int {
+    *file_actions = posix_spawn_file_actions_t {
+        head: ptr::null_mut(),
+        tail: ptr::null_mut(),
+    };
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawn_file_actions_destroy(
+    file_actions: *mut posix_spawn_file_actions_t,
+) -> c_int {
+    let mut action = (*file_actions).head;
+
+    while !action.is_null() {
+        let next = (*action).next;
+        drop(Box::from_raw(action));
+        action = next;
+    }
+
+    posix_spawn_file_actions_init(file_actions)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawn_file_actions_addclose(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fd: c_int,
+) -> c_int {
+    if fd < 0 {
+        return errno::EBADF;
+    }
+
+    push_action(file_actions, FileActionKind::Close(fd))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawn_file_actions_adddup2(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fd: c_int,
+    newfd: c_int,
+) -> c_int {
+    if fd < 0 || newfd < 0 {
+        return errno::EBADF;
+    }
+
+    push_action(file_actions, FileActionKind::Dup2(fd, newfd))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawn_file_actions_addopen(
+    file_actions: *mut posix_spawn_file_actions_t,
+    fd: c_int,
+    path: *const c_char,
+    oflag: c_int,
+    mode: mode_t,
+) -> c_int {
+    if fd < 0 {
+        return errno::EBADF;
+    }
+
+    // the caller is free to reuse path as soon as we return
+    let len = string::strlen(path) as usize + 1;
+    let mut copy = match Box::<[u8]>::new_uninit_slice(len) {
+        Ok(b) => b,
+        Err(_) => return errno::ENOMEM,
+    };
+    ptr::copy_nonoverlapping(path as *const u8, copy.as_mut_ptr() as *mut u8, len);
+    let path = Box::from_raw(Box::into_raw(copy) as *mut [u8]);
+
+    push_action(
+        file_actions,
+        FileActionKind::Open {
+            fd,
+            path,
+            oflag,
+            mode,
+        },
+    )
+}
+
+unsafe fn push_action(
+    file_actions: *mut posix_spawn_file_actions_t,
+    kind: FileActionKind,
+) -> c_int {
+    let action = match Box::new(FileAction {
+        kind,
+        next: ptr::null_mut(),
+    }) {
+        Ok(b) => Box::into_raw(b),
+        Err(_) => return errno::ENOMEM,
+    };
+
+    let file_actions = &mut *file_actions;
+
+    if let Some(tail) = file_actions.tail.as_mut() {
+        tail.next = action;
+    } else {
+        file_actions.head = action;
+    }
+
+    file_actions.tail = action;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_init(attr: *mut posix_spawnattr_t) -> c_int {
+    *attr = posix_spawnattr_t {
+        flags: 0,
+        pgroup: 0,
+        sigdefault: sigset_t::empty(),
+        sigmask: sigset_t::empty(),
+    };
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_destroy(_attr: *mut posix_spawnattr_t) -> c_int {
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_setflags(
+    attr: *mut posix_spawnattr_t,
+    flags: c_short,
+) -> c_int {
+    if flags & !ALL_FLAGS != 0 {
+        return errno::EINVAL;
+    }
+
+    (*attr).flags = flags;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_getflags(
+    attr: *const posix_spawnattr_t,
+    flags: *mut c_short,
+) -> c_int {
+    *flags = (*attr).flags;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_setpgroup(
+    attr: *mut posix_spawnattr_t,
+    pgroup: pid_t,
+) -> c_int {
+    (*attr).pgroup = pgroup;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_getpgroup(
+    attr: *const posix_spawnattr_t,
+    pgroup: *mut pid_t,
+) -> c_int {
+    *pgroup = (*attr).pgroup;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_setsigdefault(
+    attr: *mut posix_spawnattr_t,
+    sigdefault: *const sigset_t,
+) -> c_int {
+    (*attr).sigdefault = *sigdefault;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_getsigdefault(
+    attr: *const posix_spawnattr_t,
+    sigdefault: *mut sigset_t,
+) -> c_int {
+    *sigdefault = (*attr).sigdefault;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_setsigmask(
+    attr: *mut posix_spawnattr_t,
+    sigmask: *const sigset_t,
+) -> c_int {
+    (*attr).sigmask = *sigmask;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnattr_getsigmask(
+    attr: *const posix_spawnattr_t,
+    sigmask: *mut sigset_t,
+) -> c_int {
+    *sigmask = (*attr).sigmask;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawn(
+    pid: *mut pid_t,
+    path: *const c_char,
+    file_actions: *const posix_spawn_file_actions_t,
+    attrp: *const posix_spawnattr_t,
+    argv: *const *mut c_char,
+    envp: *const *mut c_char,
+) -> c_int {
+    spawn(
+        pid,
+        Child {
+            path,
+            search_path: None,
+            file_actions,
+            attr: attrp,
+            argv: argv as *const *const c_char,
+            envp: envp as *const *const c_char,
+            mask: sigset_t::empty(),
+            error: 0,
+        },
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn posix_spawnp(
+    pid: *mut pid_t,
+    file: *const c_char,
+    file_actions: *const posix_spawn_file_actions_t,
+    attrp: *const posix_spawnattr_t,
+    argv: *const *mut c_char,
+    envp: *const *mut c_char,
+) -> c_int {
+    spawn(
+        pid,
+        Child {
+            path: file,
+            // looked up here, since the child can't safely take the environment's lock
+            search_path: Some(stdlib::getenv(b"PATH\0".as_ptr() as *const c_char)),
+            file_actions,
+            attr: attrp,
+            argv: argv as *const *const c_char,
+            envp: envp as *const *const c_char,
+            mask: sigset_t::empty(),
+            error: 0,
+        },
+    )
+}
+
+/// Everything the child needs, along with where it reports why it couldn't
+/// exec. It lives on the parent's stack, which the child shares.
+struct Child {
+    path: *const c_char,
+    // Some(PATH) for posix_spawnp
+    search_path: Option<*const c_char>,
+    file_actions: *const posix_spawn_file_actions_t,
+    attr: *const posix_spawnattr_t,
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+    // the parent's signal mask, from before it blocked everything
+    mask: sigset_t,
+    error: c_int,
+}
+
+impl Child {
+    /// Only returns if it couldn't exec. Runs in the child, which shares our
+    /// memory and our TCB, so it sticks to raw syscalls and leaves errno,
+    /// locks and the allocator alone.
+    unsafe fn run(&self) -> c_int {
+        let attr = self.attr.as_ref();
+        let sigdefault = attr
+            .filter(|a| a.flags & POSIX_SPAWN_SETSIGDEF != 0)
+            .map(|a| &a.sigdefault);
+
+        // every signal is blocked until just before the exec, so nothing gets to run one of the
+        // parent's handlers in the meantime, on our stack and with our memory. reset those first
+        for sig in 1..NSIG {
+            let mut action = KernelSigaction::default();
+            signal::sys::rt_sigaction(sig, ptr::null(), &mut action);
+
+            if signal::is_dispatched(&action) || sigdefault.map_or(false, |s| s.contains(sig)) {
+                let default = KernelSigaction {
+                    handler: SIG_DFL,
+                    ..KernelSigaction::default()
+                };
+                signal::sys::rt_sigaction(sig, &default, ptr::null_mut());
+            }
+        }
+
+        if let Some(attr) = attr {
+            if let Err(e) = apply_attributes(attr) {
+                return e;
+            }
+        }
+
+        let mut action = self
+            .file_actions
+            .as_ref()
+            .map_or(ptr::null_mut(), |actions| actions.head);
+
+        while let Some(a) = action.as_ref() {
+            if let Err(e) = a.kind.apply() {
+                return e;
+            }
+
+            action = a.next;
+        }
+
+        let mask = match attr {
+            Some(a) if a.flags & POSIX_SPAWN_SETSIGMASK != 0 => &a.sigmask,
+            _ => &self.mask,
+        };
+        signal::sys::rt_sigprocmask(SIG_SETMASK, mask, ptr::null_mut());
+
+        let exec = |pathname| unistd::sys::execve(pathname, self.argv, self.envp);
+
+        match self.search_path {
+            Some(path) => unistd::exec_search(self.path, path, exec),
+            None => -exec(self.path) as c_int,
+        }
+    }
+}
+
+impl FileActionKind {
+    unsafe fn apply(&self) -> Result<(), c_int> {
+        match *self {
+            // like close(2) itself, there's nothing useful to do if this fails
+            FileActionKind::Close(fd) => {
+                unistd::sys::close(fd);
+            }
+            // dup2 would do nothing, but the caller wants fd to survive the exec
+            FileActionKind::Dup2(fd, newfd) if fd == newfd => {
+                let flags = check(fcntl::sys::fcntl(fd, F_GETFD, 0))?;
+                check(fcntl::sys::fcntl(
+                    fd,
+                    F_SETFD,
+                    flags as c_long & !FD_CLOEXEC,
+                ))?;
+            }
+            FileActionKind::Dup2(fd, newfd) => {
+                check(fcntl::sys::dup2(fd, newfd))?;
+            }
+            FileActionKind::Open {
+                fd,
+                ref path,
+                oflag,
+                mode,
+            } => {
+                let opened = check(fcntl::sys::open(
+                    path.as_ptr() as *const c_char,
+                    oflag,
+                    mode,
+                ))? as c_int;
+
+                if opened != fd {
+                    let result = check(fcntl::sys::dup2(opened, fd));
+                    unistd::sys::close(opened);
+                    result?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+unsafe fn apply_attributes(attr: &posix_spawnattr_t) -> Result<(), c_int> {
+    if attr.flags & POSIX_SPAWN_SETSID != 0 {
+        check(unistd::sys::setsid())?;
+    }
+
+    if attr.flags & POSIX_SPAWN_SETPGROUP != 0 {
+        check(unistd::sys::setpgid(0, attr.pgroup))?;
+    }
+
+    if attr.flags & POSIX_SPAWN_RESETIDS != 0 {
+        check(unistd::sys::setgid(unistd::sys::getgid() as _))?;
+        check(unistd::sys::setuid(unistd::sys::getuid() as _))?;
+    }
+
+    Ok(())
+}
+
+fn check(rax: isize) -> Result<isize, c_int> {
+    if (-4095..0).contains(&rax) {
+        Err(-rax as c_int)
+    } else {
+        Ok(rax)
+    }
+}
+
+unsafe fn spawn(pid: *mut pid_t, mut child: Child) -> c_int {
+    let stack = match check(mman::sys::mmap(
+        ptr::null_mut(),
+        CHILD_STACK_LEN as size_t,
+        mman::PROT_READ | mman::PROT_WRITE,
+        mman::MAP_PRIVATE | mman::MAP_ANONYMOUS,
+        -1,
+        0,
+    )) {
+        Ok(stack) => stack as *mut u8,
+        Err(e) => return e,
+    };
+
+    signal::sys::rt_sigprocmask(SIG_BLOCK, &sigset_t::full(), &mut child.mask);
+
+    // CLONE_VFORK holds us here until the child has exec'd or exited, after which it's done
+    // with both its stack and `child`
+    let child_pid = sys::clone_vfork(stack.add(CHILD_STACK_LEN) as *mut c_void, &mut child);
+
+    signal::sys::rt_sigprocmask(SIG_SETMASK, &child.mask, ptr::null_mut());
+    mman::sys::munmap(stack as *mut c_void, CHILD_STACK_LEN as size_t);
+
+    let child_pid = match check(child_pid) {
+        Ok(p) => p as pid_t,
+        Err(e) => return e,
+    };
+
+    if child.error != 0 {
+        // it exited instead; don't leave a zombie behind
+        wait::sys::wait4(child_pid, ptr::null_mut(), 0, ptr::null_mut());
+
+        return child.error;
+    }
+
+    if !pid.is_null() {
+        *pid = child_pid;
+    }
+
+    0
+}
+
+unsafe extern "C" fn child_main(child: *mut Child) -> ! {
+    let child = &mut *child;
+    child.error = child.run();
+
+    stdlib::sys::exit_group(127)
+}
+
+mod sys {
+    use super::*;
+
+    /// Starts a child process that shares our memory and runs `child_main` on
+    /// `stack_top`. Returns once the child has exec'd or exited.
+    pub(super) unsafe fn clone_vfork(stack_top: *mut c_void, child: *mut Child) -> isize {
+        let mut rax: isize = 56;
+
+        // like pthread's clone, the child can't touch anything on our stack, so call straight
+        // into child_main with the argument that came through r9 untouched
+        asm!(
+            "syscall",
+            "test rax, rax",
+            "jnz 2f",
+            "xor ebp, ebp",
+            "mov rdi, r9",
+            "call {child_main}",
+            "ud2",
+            "2:",
+            child_main = sym child_main,
+            inlateout("rax") rax,
+            in("rdi") (CLONE_VM | CLONE_VFORK | SIGCHLD) as isize,
+            in("rsi") stack_top as isize,
+            in("rdx") 0isize,
+            in("r10") 0isize,
+            in("r8") 0isize,
+            in("r9") child as isize,
+            lateout("rcx") _,
+            lateout("r11") _,
+        );
+
+        rax
+    }
+}
@@ -13,8 +13,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod float;
+mod open_files;
+mod printf;
+mod scanf;
+
 use crate::{
-    c_char, c_int, c_void, errno,
+    c_char, c_int, c_long, c_unsignedint, c_void, errno,
+    fcntl::{self, AT_FDCWD},
     internal::{
         self,
         alloc::Box,
@@ -22,15 +28,34 @@ use crate::{
         sync::{Mutex, Once},
         FileDescriptor,
     },
-    stddef::size_t,
-    sys::{stat, types::stat as Stat},
-    unistd,
+    stddef::{size_t, ssize_t},
+    stdlib, string,
+    sys::{
+        stat,
+        types::{off_t, stat as Stat},
+        uio::iovec,
+    },
+    unistd, wrap_syscall,
+};
+
+use core::{
+    cell::Cell,
+    cmp,
+    convert::{TryFrom, TryInto},
+    ffi::VaList,
+    mem::{ManuallyDrop, MaybeUninit},
+    ptr, slice,
 };
 
-use core::{convert::TryInto, mem::MaybeUninit, ptr, slice};
+use printf::Sink;
+use scanf::Source;
 
 pub struct FILE {
     inner: Mutex<FileInner>,
+    // fixed when the stream is opened, so it can be checked without taking the lock
+    is_writable: bool,
+    prev_open: Cell<*mut FILE>,
+    next_open: Cell<*mut FILE>,
 }
 
 impl FILE {
@@ -41,13 +66,13 @@ impl FILE {
         buffering: BufferingKind,
     ) -> Result<Box<FILE>, ErrorNumber> {
         let read = if is_readable {
-            Some(Buffer::try_new(DEFAULT_BUFLEN).map_err(|_| ErrorNumber::Nomem)?)
+            Some(Buffer::new(BUFSIZ))
         } else {
             None
         };
 
         let write = if is_writable {
-            Some(Buffer::try_new(DEFAULT_BUFLEN).map_err(|_| ErrorNumber::Nomem)?)
+            Some(Buffer::new(BUFSIZ))
         } else {
             None
         };
@@ -61,23 +86,40 @@ impl FILE {
                 is_eof: false,
                 is_error: false,
             }),
+            is_writable,
+            prev_open: Cell::new(ptr::null_mut()),
+            next_open: Cell::new(ptr::null_mut()),
         };
 
         Box::new(file).map_err(|_| ErrorNumber::Nomem)
     }
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct fpos_t {
+    offset: off_t,
+}
+
 pub const EOF: c_int = -1;
 
-const DEFAULT_BUFLEN: usize = 4 * (1 << 20); // 4 MiB; it's big I know!
+pub const BUFSIZ: usize = 8192;
+
+pub const _IOFBF: c_int = 0;
+pub const _IOLBF: c_int = 1;
+pub const _IONBF: c_int = 2;
+
+pub const RENAME_NOREPLACE: c_unsignedint = 1 << 0;
+pub const RENAME_EXCHANGE: c_unsignedint = 1 << 1;
+pub const RENAME_WHITEOUT: c_unsignedint = 1 << 2;
 
 pub const STDIN_FILENO: c_int = 0;
 pub const STDOUT_FILENO: c_int = 1;
 pub const STDERR_FILENO: c_int = 2;
 
-pub(crate) static mut STDIN: *mut FILE = ptr::null_mut();
-pub(crate) static mut STDOUT: *mut FILE = ptr::null_mut();
-pub(crate) static mut STDERR: *mut FILE = ptr::null_mut();
+static mut STDIN: *mut FILE = ptr::null_mut();
+static mut STDOUT: *mut FILE = ptr::null_mut();
+static mut STDERR: *mut FILE = ptr::null_mut();
 
 #[no_mangle]
 pub unsafe extern "C" fn __KNS_stdin() -> *mut FILE {
@@ -92,7 +134,7 @@ pub unsafe extern "C" fn __KNS_stdin() -> *mut FILE {
         )
         .expect("couldn't create FILE * for stdin");
 
-        STDIN = Box::into_raw(stdin);
+        STDIN = open_files::insert(stdin);
     });
 
     STDIN.as_mut().unwrap()
@@ -139,7 +181,7 @@ pub unsafe extern "C" fn __KNS_stdout() -> *mut FILE {
         let stdout = FILE::from_parts(STDOUT_FILENO.try_into().unwrap(), false, true, buffering)
             .expect("couldn't create FILE * for stdout");
 
-        STDOUT = Box::into_raw(stdout);
+        STDOUT = open_files::insert(stdout);
     });
 
     STDOUT.as_mut().unwrap()
@@ -158,7 +200,7 @@ pub unsafe extern "C" fn __KNS_stderr() -> *mut FILE {
         )
         .expect("couldn't create FILE * for stderr");
 
-        STDERR = Box::into_raw(stderr);
+        STDERR = open_files::insert(stderr);
     });
 
     STDERR.as_mut().unwrap()
@@ -187,25 +229,25 @@ pub unsafe extern "C" fn fopen(pathname: *const c_char, mode: *const c_char) ->
     let mode_slice = slice::from_raw_parts(mode as *const u8, modelen);
 
     let (flags, is_readable, is_writable) = match mode_slice {
-        b"r" | b"rb" => (unistd::O_RDONLY | unistd::O_CLOEXEC, true, false),
-        b"r+" | b"r+b" | b"rb+" => (unistd::O_RDWR | unistd::O_CLOEXEC, true, true),
+        b"r" | b"rb" => (fcntl::O_RDONLY | fcntl::O_CLOEXEC, true, false),
+        b"r+" | b"r+b" | b"rb+" => (fcntl::O_RDWR | fcntl::O_CLOEXEC, true, true),
         b"w" | b"wb" => (
-            unistd::O_WRONLY | unistd::O_CREAT | unistd::O_TRUNC | unistd::O_CLOEXEC,
+            fcntl::O_WRONLY | fcntl::O_CREAT | fcntl::O_TRUNC | fcntl::O_CLOEXEC,
             false,
             true,
         ),
         b"w+" | b"w+b" | b"wb+" => (
-            unistd::O_RDWR | unistd::O_CREAT | unistd::O_TRUNC | unistd::O_CLOEXEC,
+            fcntl::O_RDWR | fcntl::O_CREAT | fcntl::O_TRUNC | fcntl::O_CLOEXEC,
             true,
             true,
         ),
         b"a" | b"ab" => (
-            unistd::O_WRONLY | unistd::O_CREAT | unistd::O_APPEND | unistd::O_CLOEXEC,
+            fcntl::O_WRONLY | fcntl::O_CREAT | fcntl::O_APPEND | fcntl::O_CLOEXEC,
             false,
             true,
         ),
         b"a+" | b"a+b" | b"ab+" => (
-            unistd::O_RDWR | unistd::O_CREAT | unistd::O_APPEND | unistd::O_CLOEXEC,
+            fcntl::O_RDWR | fcntl::O_CREAT | fcntl::O_APPEND | fcntl::O_CLOEXEC,
             true,
             true,
         ),
@@ -216,7 +258,7 @@ pub unsafe extern "C" fn fopen(pathname: *const c_char, mode: *const c_char) ->
         }
     };
 
-    let fd: FileDescriptor = match ErrorNumber::from_syscall(unistd::sys::open(
+    let fd: FileDescriptor = match ErrorNumber::from_syscall(fcntl::sys::open(
         pathname,
         flags,
         unistd::S_IRUSR
@@ -235,7 +277,7 @@ pub unsafe extern "C" fn fopen(pathname: *const c_char, mode: *const c_char) ->
     };
 
     if let Ok(file_ptr) = FILE::from_parts(fd, is_readable, is_writable, BufferingKind::Full) {
-        Box::into_raw(file_ptr)
+        open_files::insert(file_ptr)
     } else {
         *internal::errno() = errno::ENOMEM;
 
@@ -251,8 +293,43 @@ pub unsafe extern "C" fn fclose(stream: *mut FILE) -> c_int {
         return EOF;
     }
 
+    open_files::remove(stream);
+
+    close(stream)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fcloseall() -> c_int {
+    // the standard streams are only flushed, since C code can't reopen them
+    let mut file = open_files::take_if(|f| f != STDIN && f != STDOUT && f != STDERR);
+    let mut result = 0;
+
+    while !file.is_null() {
+        let next = (*file).next_open.get();
+
+        if close(file) == EOF {
+            result = EOF;
+        }
+
+        file = next;
+    }
+
+    if fflush(ptr::null_mut()) == EOF {
+        result = EOF;
+    }
+
+    result
+}
+
+// stream must already be out of the open file list
+unsafe fn close(stream: *mut FILE) -> c_int {
     // lots of inners here -- go from *mut FILE to Box<FILE> to FILE to FileInner
-    let FileInner { fd, write, .. } = Box::into_inner(Box::from_raw(stream)).inner.into_inner();
+    let mut inner = Box::into_inner(Box::from_raw(stream)).inner.into_inner();
+
+    // give back whatever was read ahead, so the offset is where the stream left off for anyone
+    // else using the file
+    let discarded = inner.discard_read_buffer();
+    let FileInner { fd, write, .. } = inner;
 
     if let Some(write) = write {
         if let Err(e) = internal::write_all(&fd, write.get_slice()) {
@@ -262,7 +339,7 @@ pub unsafe extern "C" fn fclose(stream: *mut FILE) -> c_int {
         }
     }
 
-    if let Err(e) = fd.try_drop() {
+    if let Err(e) = discarded.and(fd.try_drop()) {
         *internal::errno() = e.into_int();
 
         EOF
@@ -284,53 +361,26 @@ pub unsafe extern "C" fn fgets(s: *mut c_char, size: c_int, stream: *mut FILE) -
 
     let mut guard = stream.inner.lock();
 
-    let FileInner {
-        fd,
-        read,
-        is_eof,
-        is_error,
-        ..
-    } = &mut *guard;
-
-    let read = if let Some(r) = read {
-        r
-    } else {
+    if guard.read.is_none() {
         *internal::errno() = errno::EBADF;
 
         return ptr::null_mut();
-    };
+    }
 
-    if *is_eof {
+    if guard.is_eof {
         return ptr::null_mut();
     }
 
     while out_slice.len() > 1 {
-        let mut get_slice = read.get_slice();
-
-        if get_slice.is_empty() {
-            let put_slice = read.put_slice();
-
-            let read_len = unistd::read(
-                fd.as_raw(),
-                put_slice.as_ptr() as *mut c_void,
-                put_slice.len() as size_t,
-            );
-
-            if read_len == -1 {
-                *is_error = true;
+        let get_slice = match guard.fill_read_buffer() {
+            Ok([]) => break,
+            Ok(get_slice) => get_slice,
+            Err(e) => {
+                *internal::errno() = e.into_int();
 
                 return ptr::null_mut();
             }
-
-            if read_len == 0 {
-                *is_eof = true;
-
-                break;
-            }
-
-            read.put(read_len as usize);
-            get_slice = read.get_slice();
-        }
+        };
 
         let (copylen, will_copy_newline) =
             if let Some(newline_pos) = get_slice.iter().copied().position(|ch| ch == b'\n') {
@@ -351,7 +401,7 @@ pub unsafe extern "C" fn fgets(s: *mut c_char, size: c_int, stream: *mut FILE) -
         assert!(get_slice.len() >= copylen);
 
         out_slice[..copylen].copy_from_slice(&get_slice[..copylen]);
-        read.get(copylen);
+        guard.read.as_mut().unwrap().get(copylen);
 
         out_slice = &mut out_slice[copylen..];
 
@@ -374,153 +424,1468 @@ pub unsafe extern "C" fn fputs(s: *const c_char, stream: *mut FILE) -> c_int {
     }
 
     let stream = &*stream; // immutable ref -- we need to lock the mutex!
-    let mut as_slice = slice::from_raw_parts(s as *const u8, usize::MAX);
-    let mut has_newline = false;
-    let mut num_written = 0;
+    let as_slice = slice::from_raw_parts(s as *const u8, string::strlen(s) as usize);
 
     let mut guard = stream.inner.lock();
 
-    let FileInner {
-        fd,
-        write,
-        buffering,
-        ..
-    } = &mut *guard;
-    let buffering = *buffering;
+    if let Err(e) = guard
+        .write_buffered(as_slice)
+        .and_then(|_| guard.flush_for_buffering(as_slice.contains(&b'\n')))
+    {
+        *internal::errno() = e.into_int();
+
+        return EOF;
+    }
 
-    let write = if let Some(w) = write {
-        w
+    if let Ok(int_size_result) = as_slice.len().try_into() {
+        int_size_result
     } else {
-        *internal::errno() = errno::EBADF;
+        *internal::errno() = errno::ERANGE;
+
+        c_int::MAX
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fgetc(stream: *mut FILE) -> c_int {
+    if stream.is_null() {
+        *internal::errno() = errno::EINVAL;
 
         return EOF;
+    }
+
+    let stream = &*stream; // immutable ref -- we need to lock the mutex!
+    let mut guard = stream.inner.lock();
+
+    let ch = match guard.fill_read_buffer() {
+        Ok([]) => return EOF,
+        Ok(get_slice) => get_slice[0],
+        Err(e) => {
+            *internal::errno() = e.into_int();
+
+            return EOF;
+        }
     };
 
-    loop {
-        let put_slice = write.put_slice();
+    guard.read.as_mut().unwrap().get(1);
 
-        let (copy_len, is_done) = if let Some(null_or_line_ending_idx) = as_slice
-            .iter()
-            .take(put_slice.len())
-            .copied()
-            .position(|ch| ch == b'\0' || ch == b'\n')
-        {
-            if as_slice[null_or_line_ending_idx] == b'\0' {
-                (null_or_line_ending_idx, true)
-            } else {
-                has_newline = true;
+    ch as c_int
+}
 
-                (null_or_line_ending_idx + 1, false)
-            }
-        } else {
-            (put_slice.len(), false)
-        };
+#[no_mangle]
+pub unsafe extern "C" fn getc(stream: *mut FILE) -> c_int {
+    fgetc(stream)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getchar() -> c_int {
+    fgetc(__KNS_stdin())
+}
 
-        assert!(copy_len <= put_slice.len());
+#[no_mangle]
+pub unsafe extern "C" fn ungetc(c: c_int, stream: *mut FILE) -> c_int {
+    if stream.is_null() {
+        *internal::errno() = errno::EINVAL;
 
-        put_slice[..copy_len].copy_from_slice(&as_slice[..copy_len]);
-        let put_len = put_slice.len();
-        write.put(copy_len);
-        num_written += copy_len;
-        as_slice = &as_slice[copy_len..];
+        return EOF;
+    }
 
-        if copy_len == put_len {
-            let flush_result = write.flush(&fd);
-            has_newline = false;
+    if c == EOF {
+        return EOF;
+    }
 
-            if let Err(e) = flush_result {
-                *internal::errno() = e.into_int();
+    let stream = &*stream; // immutable ref -- we need to lock the mutex!
+    let mut guard = stream.inner.lock();
 
-                return EOF;
-            }
-        }
+    let read = if let Some(r) = guard.read.as_mut() {
+        r
+    } else {
+        *internal::errno() = errno::EBADF;
 
-        if is_done {
-            break;
-        }
+        return EOF;
+    };
+
+    if let Err(e) = read.reserve() {
+        *internal::errno() = e.into_int();
+
+        return EOF;
     }
 
-    if (has_newline && buffering == BufferingKind::Line) || buffering == BufferingKind::None {
-        if let Err(e) = write.flush(&fd) {
-            *internal::errno() = e.into_int();
+    if read.unget(c as u8).is_err() {
+        return EOF;
+    }
 
-            return EOF;
-        }
+    guard.is_eof = false;
+
+    c as u8 as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fputc(c: c_int, stream: *mut FILE) -> c_int {
+    if stream.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return EOF;
     }
 
-    if let Ok(int_size_result) = num_written.try_into() {
-        int_size_result
-    } else {
-        *internal::errno() = errno::ERANGE;
+    let stream = &*stream; // immutable ref -- we need to lock the mutex!
+    let ch = c as u8;
 
-        c_int::MAX
+    let mut guard = stream.inner.lock();
+
+    if let Err(e) = guard
+        .write_buffered(&[ch])
+        .and_then(|_| guard.flush_for_buffering(ch == b'\n'))
+    {
+        *internal::errno() = e.into_int();
+
+        return EOF;
     }
+
+    ch as c_int
 }
 
-struct Buffer {
-    data: Box<[MaybeUninit<u8>]>,
-    get_pos: usize,
-    put_pos: usize,
+#[no_mangle]
+pub unsafe extern "C" fn putc(c: c_int, stream: *mut FILE) -> c_int {
+    fputc(c, stream)
 }
 
-impl Buffer {
-    fn try_new(len: usize) -> Result<Self, ()> {
-        Ok(Self {
-            data: Box::new_uninit_slice(len)?,
-            get_pos: 0,
-            put_pos: 0,
-        })
+#[no_mangle]
+pub unsafe extern "C" fn putchar(c: c_int) -> c_int {
+    fputc(c, __KNS_stdout())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn puts(s: *const c_char) -> c_int {
+    if s.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return EOF;
     }
 
-    fn get_slice(&self) -> &[u8] {
-        let ptr = (&*self.data).as_ptr() as *const u8;
+    let stream = &*__KNS_stdout();
+    let as_slice = slice::from_raw_parts(s as *const u8, string::strlen(s) as usize);
 
-        unsafe { slice::from_raw_parts(ptr.add(self.get_pos), self.put_pos - self.get_pos) }
+    let mut guard = stream.inner.lock();
+
+    if let Err(e) = guard
+        .write_buffered(as_slice)
+        .and_then(|_| guard.write_buffered(b"\n"))
+        .and_then(|_| guard.flush_for_buffering(true))
+    {
+        *internal::errno() = e.into_int();
+
+        return EOF;
     }
 
-    fn put_slice(&mut self) -> &mut [u8] {
-        let ptr = (&mut *self.data).as_mut_ptr() as *mut u8;
+    0
+}
 
-        unsafe { slice::from_raw_parts_mut(ptr.add(self.put_pos), self.data.len() - self.put_pos) }
+#[no_mangle]
+pub unsafe extern "C" fn fread(
+    ptr: *mut c_void,
+    size: size_t,
+    nmemb: size_t,
+    stream: *mut FILE,
+) -> size_t {
+    if stream.is_null() || (ptr.is_null() && size > 0 && nmemb > 0) {
+        *internal::errno() = errno::EINVAL;
+
+        return 0;
     }
 
-    fn get(&mut self, n: usize) {
-        assert!(n <= self.put_pos - self.get_pos);
-        self.get_pos += n;
+    let total_len = match size.checked_mul(nmemb) {
+        Some(0) => return 0,
+        Some(l) => l as usize,
+        None => {
+            *internal::errno() = errno::EOVERFLOW;
 
-        if self.get_pos == self.put_pos {
-            self.get_pos = 0;
-            self.put_pos = 0;
+            return 0;
         }
+    };
+
+    let stream = &*stream; // immutable ref -- we need to lock the mutex!
+    let out_slice = slice::from_raw_parts_mut(ptr as *mut u8, total_len);
+
+    let mut guard = stream.inner.lock();
+    let mut num_read = 0;
+
+    while num_read < total_len {
+        let get_slice = match guard.fill_read_buffer() {
+            Ok([]) => break,
+            Ok(get_slice) => get_slice,
+            Err(e) => {
+                *internal::errno() = e.into_int();
+
+                break;
+            }
+        };
+
+        let copy_len = cmp::min(get_slice.len(), total_len - num_read);
+        out_slice[num_read..num_read + copy_len].copy_from_slice(&get_slice[..copy_len]);
+        guard.read.as_mut().unwrap().get(copy_len);
+
+        num_read += copy_len;
     }
 
-    fn put(&mut self, n: usize) {
-        assert!(n <= self.data.len() - self.put_pos);
+    (num_read / size as usize) as size_t
+}
 
-        self.put_pos += n;
+#[no_mangle]
+pub unsafe extern "C" fn fwrite(
+    ptr: *const c_void,
+    size: size_t,
+    nmemb: size_t,
+    stream: *mut FILE,
+) -> size_t {
+    if stream.is_null() || (ptr.is_null() && size > 0 && nmemb > 0) {
+        *internal::errno() = errno::EINVAL;
+
+        return 0;
     }
 
-    fn flush(&mut self, fd: &FileDescriptor) -> Result<(), ErrorNumber> {
-        let get_slice_len = self.get_slice().len();
-        let flush_result = internal::write_all(fd, self.get_slice());
-        self.get(get_slice_len);
+    let total_len = match size.checked_mul(nmemb) {
+        Some(0) => return 0,
+        Some(l) => l as usize,
+        None => {
+            *internal::errno() = errno::EOVERFLOW;
 
-        flush_result
+            return 0;
+        }
+    };
+
+    let stream = &*stream; // immutable ref -- we need to lock the mutex!
+    let as_slice = slice::from_raw_parts(ptr as *const u8, total_len);
+
+    let mut guard = stream.inner.lock();
+    let has_newline = guard.buffering == BufferingKind::Line && as_slice.contains(&b'\n');
+
+    if let Err(e) = guard
+        .write_buffered(as_slice)
+        .and_then(|_| guard.flush_for_buffering(has_newline))
+    {
+        *internal::errno() = e.into_int();
+
+        return 0;
     }
+
+    nmemb
 }
 
-#[derive(Eq, PartialEq, Copy, Clone)]
-pub(crate) enum BufferingKind {
-    None,
-    Line,
-    Full,
+#[no_mangle]
+pub unsafe extern "C" fn getline(
+    lineptr: *mut *mut c_char,
+    n: *mut size_t,
+    stream: *mut FILE,
+) -> ssize_t {
+    getdelim(lineptr, n, b'\n' as c_int, stream)
 }
 
-struct FileInner {
-    fd: FileDescriptor,
-    read: Option<Buffer>,
-    write: Option<Buffer>,
-    buffering: BufferingKind,
-    is_eof: bool,
-    is_error: bool,
+#[no_mangle]
+pub unsafe extern "C" fn getdelim(
+    lineptr: *mut *mut c_char,
+    n: *mut size_t,
+    delim: c_int,
+    stream: *mut FILE,
+) -> ssize_t {
+    const INITIAL_LEN: usize = 128;
+
+    if lineptr.is_null() || n.is_null() || stream.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    if (*lineptr).is_null() {
+        *n = 0;
+    }
+
+    let stream = &*stream; // immutable ref -- we need to lock the mutex!
+    let delim = delim as u8;
+
+    let mut guard = stream.inner.lock();
+    let mut len = 0;
+
+    loop {
+        let get_slice = match guard.fill_read_buffer() {
+            Ok([]) => break,
+            Ok(get_slice) => get_slice,
+            Err(e) => {
+                *internal::errno() = e.into_int();
+
+                return -1;
+            }
+        };
+
+        let (copy_len, found_delim) = match get_slice.iter().position(|&ch| ch == delim) {
+            Some(delim_pos) => (delim_pos + 1, true),
+            None => (get_slice.len(), false),
+        };
+
+        // always leave room for the terminating null byte
+        let needed = len + copy_len + 1;
+
+        if needed > *n as usize {
+            let new_len = cmp::max(needed, cmp::max(2 * *n as usize, INITIAL_LEN));
+            let new_ptr = stdlib::realloc(*lineptr as *mut c_void, new_len as size_t);
+
+            if new_ptr.is_null() {
+                return -1;
+            }
+
+            *lineptr = new_ptr as *mut c_char;
+            *n = new_len as size_t;
+        }
+
+        ptr::copy_nonoverlapping(get_slice.as_ptr(), (*lineptr as *mut u8).add(len), copy_len);
+        guard.read.as_mut().unwrap().get(copy_len);
+        len += copy_len;
+
+        if found_delim {
+            break;
+        }
+    }
+
+    if len == 0 {
+        return -1;
+    }
+
+    *(*lineptr).add(len) = 0;
+
+    len as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn feof(stream: *mut FILE) -> c_int {
+    if stream.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return 0;
+    }
+
+    (*stream).inner.lock().is_eof as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ferror(stream: *mut FILE) -> c_int {
+    if stream.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return 0;
+    }
+
+    (*stream).inner.lock().is_error as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn clearerr(stream: *mut FILE) {
+    if stream.is_null() {
+        return;
+    }
+
+    let mut guard = (*stream).inner.lock();
+    guard.is_eof = false;
+    guard.is_error = false;
+}
+
+/// Writes `s`, a colon, and the message for errno to stderr, or just the
+/// message if `s` is null or empty.
+#[no_mangle]
+pub unsafe extern "C" fn perror(s: *const c_char) {
+    let message = string::strerror(*internal::errno());
+
+    if s.is_null() || *s == 0 {
+        fprintf(__KNS_stderr(), b"%s\n\0".as_ptr() as *const c_char, message);
+    } else {
+        fprintf(
+            __KNS_stderr(),
+            b"%s: %s\n\0".as_ptr() as *const c_char,
+            s,
+            message,
+        );
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fileno(stream: *mut FILE) -> c_int {
+    if stream.is_null() {
+        *internal::errno() = errno::EBADF;
+
+        return -1;
+    }
+
+    (*stream).inner.lock().fd.as_raw()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fseek(stream: *mut FILE, offset: c_long, whence: c_int) -> c_int {
+    fseeko(stream, offset as off_t, whence)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fseeko(stream: *mut FILE, offset: off_t, whence: c_int) -> c_int {
+    if stream.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    if let Err(e) = (*stream).inner.lock().seek(offset, whence) {
+        *internal::errno() = e.into_int();
+
+        return -1;
+    }
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ftell(stream: *mut FILE) -> c_long {
+    ftello(stream) as c_long
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ftello(stream: *mut FILE) -> off_t {
+    if stream.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    match (*stream).inner.lock().tell() {
+        Ok(offset) => offset,
+        Err(e) => {
+            *internal::errno() = e.into_int();
+
+            -1
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rewind(stream: *mut FILE) {
+    if stream.is_null() {
+        return;
+    }
+
+    let mut guard = (*stream).inner.lock();
+
+    if guard.seek(0, unistd::SEEK_SET).is_ok() {
+        guard.is_error = false;
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fgetpos(stream: *mut FILE, pos: *mut fpos_t) -> c_int {
+    if pos.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    let offset = ftello(stream);
+
+    if offset == -1 {
+        return -1;
+    }
+
+    (*pos).offset = offset;
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fsetpos(stream: *mut FILE, pos: *const fpos_t) -> c_int {
+    if pos.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    fseeko(stream, (*pos).offset, unistd::SEEK_SET)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn setvbuf(
+    stream: *mut FILE,
+    buf: *mut c_char,
+    mode: c_int,
+    size: size_t,
+) -> c_int {
+    if stream.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    // like glibc, an unbuffered stream ignores buf and size
+    let (buffering, buf, size) = match mode {
+        _IOFBF => (BufferingKind::Full, buf, size),
+        _IOLBF => (BufferingKind::Line, buf, size),
+        _IONBF => (BufferingKind::None, ptr::null_mut(), 0),
+        _ => {
+            *internal::errno() = errno::EINVAL;
+
+            return -1;
+        }
+    };
+
+    // a user-supplied buffer needs a size, otherwise zero keeps the current ones
+    let size = if !buf.is_null() && size == 0 {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    } else {
+        size as usize
+    };
+
+    if let Err(e) = (*stream)
+        .inner
+        .lock()
+        .set_buffering(buf as *mut u8, buffering, size)
+    {
+        *internal::errno() = e.into_int();
+
+        return -1;
+    }
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn setbuf(stream: *mut FILE, buf: *mut c_char) {
+    let mode = if buf.is_null() { _IONBF } else { _IOFBF };
+
+    setvbuf(stream, buf, mode, BUFSIZ as size_t);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn setlinebuf(stream: *mut FILE) {
+    setvbuf(stream, ptr::null_mut(), _IOLBF, 0);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fflush(stream: *mut FILE) -> c_int {
+    if stream.is_null() {
+        if let Err(e) = open_files::flush_all() {
+            *internal::errno() = e.into_int();
+
+            return EOF;
+        }
+
+        return 0;
+    }
+
+    let mut guard = (*stream).inner.lock();
+
+    // for input streams, put the file offset back where the caller thinks it is
+    if let Err(e) = guard
+        .flush_write_buffer()
+        .and_then(|_| guard.discard_read_buffer())
+    {
+        *internal::errno() = e.into_int();
+
+        return EOF;
+    }
+
+    0
+}
+
+/// Flushes every stream that can be flushed without waiting, for `exit`.
+pub(crate) fn flush_for_exit() {
+    open_files::flush_all_for_exit();
+}
+
+/// Holds the list of open streams across a fork until `unlock_after_fork`.
+pub(crate) fn lock_for_fork() {
+    open_files::lock_for_fork();
+}
+
+/// Unlocks every stream in the child, whichever thread held it in the
+/// parent. Must come between `lock_for_fork` and `unlock_after_fork`.
+pub(crate) unsafe fn reset_after_fork() {
+    open_files::reset_after_fork();
+}
+
+/// Must follow `lock_for_fork` in the same thread, in the parent and child
+/// alike.
+pub(crate) unsafe fn unlock_after_fork() {
+    open_files::unlock_after_fork();
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rename(oldpath: *const c_char, newpath: *const c_char) -> c_int {
+    renameat(AT_FDCWD, oldpath, AT_FDCWD, newpath)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn renameat(
+    olddirfd: c_int,
+    oldpath: *const c_char,
+    newdirfd: c_int,
+    newpath: *const c_char,
+) -> c_int {
+    wrap_syscall!(sys::renameat(olddirfd, oldpath, newdirfd, newpath)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn renameat2(
+    olddirfd: c_int,
+    oldpath: *const c_char,
+    newdirfd: c_int,
+    newpath: *const c_char,
+    flags: c_unsignedint,
+) -> c_int {
+    // plain renameat works on kernels older than 3.15
+    if flags == 0 {
+        return renameat(olddirfd, oldpath, newdirfd, newpath);
+    }
+
+    wrap_syscall!(sys::renameat2(olddirfd, oldpath, newdirfd, newpath, flags)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn printf(format: *const c_char, mut args: ...) -> c_int {
+    vfprintf(__KNS_stdout(), format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fprintf(stream: *mut FILE, format: *const c_char, mut args: ...) -> c_int {
+    vfprintf(stream, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dprintf(fd: c_int, format: *const c_char, mut args: ...) -> c_int {
+    vdprintf(fd, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sprintf(s: *mut c_char, format: *const c_char, mut args: ...) -> c_int {
+    vsprintf(s, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn snprintf(
+    s: *mut c_char,
+    n: size_t,
+    format: *const c_char,
+    mut args: ...
+) -> c_int {
+    vsnprintf(s, n, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn asprintf(
+    strp: *mut *mut c_char,
+    format: *const c_char,
+    mut args: ...
+) -> c_int {
+    vasprintf(strp, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vprintf(format: *const c_char, ap: VaList) -> c_int {
+    vfprintf(__KNS_stdout(), format, ap)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vfprintf(
+    stream: *mut FILE,
+    format: *const c_char,
+    mut ap: VaList,
+) -> c_int {
+    if stream.is_null() || format.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    let stream = &*stream; // immutable ref -- we need to lock the mutex!
+    let mut guard = stream.inner.lock();
+
+    let mut sink = FileSink {
+        inner: &mut guard,
+        has_newline: false,
+    };
+
+    let result = printf::format(&mut sink, format_slice(format), &mut ap);
+    let has_newline = sink.has_newline;
+
+    // whatever made it into the buffer before an error still gets flushed as usual
+    let flush_result = guard.flush_for_buffering(has_newline);
+
+    printf_result(result.and_then(|n| flush_result.map(|_| n)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vdprintf(fd: c_int, format: *const c_char, mut ap: VaList) -> c_int {
+    if format.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    let fd = match FileDescriptor::try_from(fd) {
+        Ok(fd) => ManuallyDrop::new(fd), // we're only borrowing it
+        Err(_) => {
+            *internal::errno() = errno::EBADF;
+
+            return -1;
+        }
+    };
+
+    let mut sink = FdSink {
+        fd: &fd,
+        buf: [0; FdSink::BUFLEN],
+        len: 0,
+    };
+
+    let result = printf::format(&mut sink, format_slice(format), &mut ap);
+    let flush_result = sink.flush();
+
+    printf_result(result.and_then(|n| flush_result.map(|_| n)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vsprintf(s: *mut c_char, format: *const c_char, mut ap: VaList) -> c_int {
+    if s.is_null() || format.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    let mut sink = UnboundedSink { next: s as *mut u8 };
+    let result = printf::format(&mut sink, format_slice(format), &mut ap);
+    *sink.next = b'\0';
+
+    printf_result(result)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vsnprintf(
+    s: *mut c_char,
+    n: size_t,
+    format: *const c_char,
+    mut ap: VaList,
+) -> c_int {
+    if (s.is_null() && n > 0) || format.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    let buf = if n > 0 {
+        slice::from_raw_parts_mut(s as *mut u8, n as usize)
+    } else {
+        &mut []
+    };
+
+    let mut sink = SliceSink { buf, len: 0 };
+    let result = printf::format(&mut sink, format_slice(format), &mut ap);
+    sink.terminate();
+
+    printf_result(result)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vasprintf(
+    strp: *mut *mut c_char,
+    format: *const c_char,
+    mut ap: VaList,
+) -> c_int {
+    if strp.is_null() || format.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    let mut sink = match AllocSink::new() {
+        Ok(s) => s,
+        Err(e) => {
+            *internal::errno() = e.into_int();
+
+            return -1;
+        }
+    };
+
+    let result = printf::format(&mut sink, format_slice(format), &mut ap)
+        .and_then(|n| sink.write(b"\0").map(|_| n));
+
+    if result.is_ok() {
+        *strp = sink.into_raw();
+    }
+
+    printf_result(result)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn scanf(format: *const c_char, mut args: ...) -> c_int {
+    vscanf(format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fscanf(stream: *mut FILE, format: *const c_char, mut args: ...) -> c_int {
+    vfscanf(stream, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sscanf(s: *const c_char, format: *const c_char, mut args: ...) -> c_int {
+    vsscanf(s, format, args.as_va_list())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vscanf(format: *const c_char, ap: VaList) -> c_int {
+    vfscanf(__KNS_stdin(), format, ap)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vfscanf(
+    stream: *mut FILE,
+    format: *const c_char,
+    mut ap: VaList,
+) -> c_int {
+    if stream.is_null() || format.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return EOF;
+    }
+
+    let stream = &*stream; // immutable ref -- we need to lock the mutex!
+    let mut guard = stream.inner.lock();
+
+    if guard.read.is_none() {
+        *internal::errno() = errno::EBADF;
+
+        return EOF;
+    }
+
+    let mut source = FileSource { inner: &mut guard };
+
+    scanf_result(scanf::scan(&mut source, format_slice(format), &mut ap))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn vsscanf(s: *const c_char, format: *const c_char, mut ap: VaList) -> c_int {
+    if s.is_null() || format.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return EOF;
+    }
+
+    let mut source = SliceSource {
+        data: format_slice(s),
+    };
+
+    scanf_result(scanf::scan(&mut source, format_slice(format), &mut ap))
+}
+
+unsafe fn format_slice<'a>(format: *const c_char) -> &'a [u8] {
+    slice::from_raw_parts(format as *const u8, string::strlen(format) as usize)
+}
+
+fn printf_result(result: Result<usize, ErrorNumber>) -> c_int {
+    match result.and_then(|n| n.try_into().map_err(|_| ErrorNumber::Overflow)) {
+        Ok(n) => n,
+        Err(e) => {
+            unsafe { *internal::errno() = e.into_int() };
+
+            -1
+        }
+    }
+}
+
+fn scanf_result(result: Result<c_int, ErrorNumber>) -> c_int {
+    match result {
+        Ok(n) => n,
+        Err(e) => {
+            unsafe { *internal::errno() = e.into_int() };
+
+            EOF
+        }
+    }
+}
+
+struct FileSink<'a> {
+    inner: &'a mut FileInner,
+    has_newline: bool,
+}
+
+impl<'a> Sink for FileSink<'a> {
+    fn write(&mut self, data: &[u8]) -> Result<(), ErrorNumber> {
+        self.has_newline = self.has_newline || data.contains(&b'\n');
+
+        self.inner.write_buffered(data)
+    }
+}
+
+struct FdSink<'a> {
+    fd: &'a FileDescriptor,
+    buf: [u8; FdSink::BUFLEN],
+    len: usize,
+}
+
+impl<'a> FdSink<'a> {
+    const BUFLEN: usize = 512;
+
+    fn flush(&mut self) -> Result<(), ErrorNumber> {
+        let flush_result = internal::write_all(self.fd, &self.buf[..self.len]);
+        self.len = 0;
+
+        flush_result
+    }
+}
+
+impl<'a> Sink for FdSink<'a> {
+    fn write(&mut self, mut data: &[u8]) -> Result<(), ErrorNumber> {
+        while !data.is_empty() {
+            if self.len == self.buf.len() {
+                self.flush()?;
+            }
+
+            let copy_len = cmp::min(data.len(), self.buf.len() - self.len);
+            self.buf[self.len..self.len + copy_len].copy_from_slice(&data[..copy_len]);
+            self.len += copy_len;
+            data = &data[copy_len..];
+        }
+
+        Ok(())
+    }
+}
+
+/// Silently drops anything that doesn't fit, leaving room for a terminating
+/// null byte.
+struct SliceSink<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> SliceSink<'a> {
+    fn terminate(&mut self) {
+        if let Some(last) = self.buf.len().checked_sub(1) {
+            self.buf[cmp::min(self.len, last)] = b'\0';
+        }
+    }
+}
+
+impl<'a> Sink for SliceSink<'a> {
+    fn write(&mut self, data: &[u8]) -> Result<(), ErrorNumber> {
+        let capacity = self.buf.len().saturating_sub(1);
+        let copy_len = cmp::min(data.len(), capacity.saturating_sub(self.len));
+
+        self.buf[self.len..self.len + copy_len].copy_from_slice(&data[..copy_len]);
+        self.len += copy_len;
+
+        Ok(())
+    }
+}
+
+/// For sprintf, which trusts the caller to have made enough room.
+struct UnboundedSink {
+    next: *mut u8,
+}
+
+impl Sink for UnboundedSink {
+    fn write(&mut self, data: &[u8]) -> Result<(), ErrorNumber> {
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), self.next, data.len());
+            self.next = self.next.add(data.len());
+        }
+
+        Ok(())
+    }
+}
+
+/// For asprintf. `buf` is only ever `None` while it's being reallocated.
+struct AllocSink {
+    buf: Option<Box<[MaybeUninit<u8>]>>,
+    len: usize,
+}
+
+impl AllocSink {
+    const INITIAL_LEN: usize = 64;
+
+    fn new() -> Result<Self, ErrorNumber> {
+        let buf = Box::new_uninit_slice(Self::INITIAL_LEN).map_err(|_| ErrorNumber::Nomem)?;
+
+        Ok(Self {
+            buf: Some(buf),
+            len: 0,
+        })
+    }
+
+    fn into_raw(self) -> *mut c_char {
+        Box::into_raw(self.buf.unwrap()) as *mut c_char
+    }
+}
+
+impl Sink for AllocSink {
+    fn write(&mut self, data: &[u8]) -> Result<(), ErrorNumber> {
+        let needed = self.len.checked_add(data.len()).ok_or(ErrorNumber::Nomem)?;
+        let mut buf = self.buf.take().unwrap();
+
+        if needed > buf.len() {
+            let new_len = cmp::max(needed, buf.len() * 2);
+
+            buf = match Box::realloc(buf, new_len) {
+                Ok(b) => b,
+                Err(b) => {
+                    self.buf = Some(b);
+
+                    return Err(ErrorNumber::Nomem);
+                }
+            };
+        }
+
+        for (dst, src) in buf[self.len..needed].iter_mut().zip(data.iter()) {
+            *dst = MaybeUninit::new(*src);
+        }
+
+        self.buf = Some(buf);
+        self.len = needed;
+
+        Ok(())
+    }
+}
+
+/// Reads through the read buffer, so anything peeked at but not consumed
+/// is left there for the next read.
+struct FileSource<'a> {
+    inner: &'a mut FileInner,
+}
+
+impl<'a> Source for FileSource<'a> {
+    fn peek(&mut self) -> Option<u8> {
+        match self.inner.fill_read_buffer() {
+            Ok(get_slice) => get_slice.first().copied(),
+            Err(e) => {
+                unsafe { *internal::errno() = e.into_int() };
+
+                None
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        self.inner.read.as_mut().unwrap().get(1);
+    }
+}
+
+struct SliceSource<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Source for SliceSource<'a> {
+    fn peek(&mut self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    fn advance(&mut self) {
+        self.data = &self.data[1..];
+    }
+}
+
+/// Where a Buffer keeps its bytes.
+enum Storage {
+    /// Nothing's been read or written yet.
+    Unallocated,
+    Owned(Box<[MaybeUninit<u8>]>),
+    /// Supplied by setvbuf, and ours until the stream is closed.
+    User(*mut MaybeUninit<u8>),
+}
+
+struct Buffer {
+    storage: Storage,
+    capacity: usize,
+    get_pos: usize,
+    put_pos: usize,
+}
+
+// a buffer from setvbuf belongs to its stream as much as an owned one does
+unsafe impl Send for Buffer {}
+
+impl Buffer {
+    /// Doesn't allocate anything until `reserve` is called.
+    fn new(capacity: usize) -> Self {
+        Self {
+            storage: Storage::Unallocated,
+            capacity,
+            get_pos: 0,
+            put_pos: 0,
+        }
+    }
+
+    fn reserve(&mut self) -> Result<(), ErrorNumber> {
+        if let Storage::Unallocated = self.storage {
+            let data = Box::new_uninit_slice(self.capacity).map_err(|_| ErrorNumber::Nomem)?;
+            self.storage = Storage::Owned(data);
+        }
+
+        Ok(())
+    }
+
+    /// Switches to new storage, dropping whatever's in the buffer.
+    fn replace_storage(&mut self, storage: Storage, capacity: usize) {
+        self.storage = storage;
+        self.capacity = capacity;
+        self.get_pos = 0;
+        self.put_pos = 0;
+    }
+
+    fn data(&self) -> &[MaybeUninit<u8>] {
+        match &self.storage {
+            Storage::Unallocated => &[],
+            Storage::Owned(data) => data,
+            Storage::User(ptr) => unsafe { slice::from_raw_parts(*ptr, self.capacity) },
+        }
+    }
+
+    fn data_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        match &mut self.storage {
+            Storage::Unallocated => &mut [],
+            Storage::Owned(data) => data,
+            Storage::User(ptr) => unsafe { slice::from_raw_parts_mut(*ptr, self.capacity) },
+        }
+    }
+
+    fn get_slice(&self) -> &[u8] {
+        let ptr = self.data().as_ptr() as *const u8;
+
+        unsafe { slice::from_raw_parts(ptr.add(self.get_pos), self.put_pos - self.get_pos) }
+    }
+
+    fn put_slice(&mut self) -> &mut [u8] {
+        let put_pos = self.put_pos;
+        let data = self.data_mut();
+        let len = data.len() - put_pos;
+        let ptr = data.as_mut_ptr() as *mut u8;
+
+        unsafe { slice::from_raw_parts_mut(ptr.add(put_pos), len) }
+    }
+
+    fn get(&mut self, n: usize) {
+        assert!(n <= self.put_pos - self.get_pos);
+        self.get_pos += n;
+
+        if self.get_pos == self.put_pos {
+            self.get_pos = 0;
+            self.put_pos = 0;
+        }
+    }
+
+    /// Pushes `ch` back onto the front of the unread data, failing if
+    /// there's no room left.
+    fn unget(&mut self, ch: u8) -> Result<(), ()> {
+        let put_pos = self.put_pos;
+
+        if self.get_pos == 0 {
+            if put_pos == self.data().len() {
+                return Err(());
+            }
+
+            self.data_mut().copy_within(..put_pos, 1);
+            self.put_pos += 1;
+        } else {
+            self.get_pos -= 1;
+        }
+
+        let get_pos = self.get_pos;
+        self.data_mut()[get_pos] = MaybeUninit::new(ch);
+
+        Ok(())
+    }
+
+    fn put(&mut self, n: usize) {
+        assert!(n <= self.data().len() - self.put_pos);
+
+        self.put_pos += n;
+    }
+
+    fn flush(&mut self, fd: &FileDescriptor) -> Result<(), ErrorNumber> {
+        let get_slice_len = self.get_slice().len();
+        let flush_result = internal::write_all(fd, self.get_slice());
+        self.get(get_slice_len);
+
+        flush_result
+    }
+}
+
+#[derive(Eq, PartialEq, Copy, Clone)]
+pub(crate) enum BufferingKind {
+    None,
+    Line,
+    Full,
+}
+
+struct FileInner {
+    fd: FileDescriptor,
+    read: Option<Buffer>,
+    write: Option<Buffer>,
+    buffering: BufferingKind,
+    is_eof: bool,
+    is_error: bool,
+}
+
+impl FileInner {
+    /// Returns the unread contents of the read buffer, refilling it first if
+    /// it's empty. An empty slice means end of file.
+    fn fill_read_buffer(&mut self) -> Result<&[u8], ErrorNumber> {
+        if self.read.is_none() {
+            return Err(ErrorNumber::Badf);
+        }
+
+        // anything we wrote has to hit the file before we read past it
+        self.flush_write_buffer()?;
+
+        let read = self.read.as_mut().unwrap();
+        read.reserve()?;
+
+        if read.get_slice().is_empty() && !self.is_eof {
+            let put_slice = read.put_slice();
+
+            match ErrorNumber::from_syscall::<usize>(unsafe {
+                internal::read(
+                    self.fd.as_raw(),
+                    put_slice.as_mut_ptr() as *mut c_void,
+                    put_slice.len() as size_t,
+                )
+            }) {
+                Ok(0) => self.is_eof = true,
+                Ok(read_len) => read.put(read_len),
+                Err(e) => {
+                    self.is_error = true;
+
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(read.get_slice())
+    }
+
+    /// Copies `data` into the write buffer, or writes it out along with the
+    /// buffer's contents if it wouldn't fit.
+    fn write_buffered(&mut self, data: &[u8]) -> Result<(), ErrorNumber> {
+        if self.write.is_none() {
+            return Err(ErrorNumber::Badf);
+        }
+
+        self.discard_read_buffer()?;
+
+        let write = self.write.as_mut().unwrap();
+        let buffered = write.get_slice();
+
+        // no point in copying something that would fill the buffer anyway. send it straight out
+        // along with whatever's already buffered, in one writev
+        if buffered.len() + data.len() >= write.capacity {
+            let buffered_len = buffered.len();
+            let mut bufs = [iovec::from_slice(buffered), iovec::from_slice(data)];
+            let result = internal::write_all_vectored(&self.fd, &mut bufs);
+
+            // an error can leave some of the buffer unwritten; keep that for next time
+            write.get(buffered_len - bufs[0].iov_len as usize);
+
+            if let Err(e) = result {
+                self.is_error = true;
+
+                return Err(e);
+            }
+
+            return Ok(());
+        }
+
+        // otherwise it all fits, with room to spare
+        write.reserve()?;
+        write.put_slice()[..data.len()].copy_from_slice(data);
+        write.put(data.len());
+
+        Ok(())
+    }
+
+    /// Flushes the write buffer if this stream's buffering mode calls for it
+    /// after a write that did (or didn't) contain a newline.
+    fn flush_for_buffering(&mut self, has_newline: bool) -> Result<(), ErrorNumber> {
+        let should_flush = match self.buffering {
+            BufferingKind::None => true,
+            BufferingKind::Line => has_newline,
+            BufferingKind::Full => false,
+        };
+
+        if !should_flush {
+            return Ok(());
+        }
+
+        if self.write.is_none() {
+            return Err(ErrorNumber::Badf);
+        }
+
+        self.flush_write_buffer()
+    }
+
+    fn flush_write_buffer(&mut self) -> Result<(), ErrorNumber> {
+        let write = match self.write.as_mut() {
+            Some(w) if !w.get_slice().is_empty() => w,
+            _ => return Ok(()),
+        };
+
+        if let Err(e) = write.flush(&self.fd) {
+            self.is_error = true;
+
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Throws away read-ahead, moving the file offset back to where the
+    /// caller thinks it is, so that a write lands right after the last byte
+    /// read.
+    fn discard_read_buffer(&mut self) -> Result<(), ErrorNumber> {
+        let read = match self.read.as_mut() {
+            Some(r) if !r.get_slice().is_empty() => r,
+            _ => return Ok(()),
+        };
+
+        let unread_len = read.get_slice().len();
+
+        match ErrorNumber::from_syscall::<off_t>(unsafe {
+            unistd::sys::lseek(self.fd.as_raw(), -(unread_len as off_t), unistd::SEEK_CUR)
+        }) {
+            Ok(_) => read.get(unread_len),
+            // pipes and the like don't share one offset between reads and writes
+            Err(ErrorNumber::Spipe) => (),
+            Err(e) => {
+                self.is_error = true;
+
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Changes the buffering mode and, unless `size` is zero, the buffers
+    /// themselves. `buf` is used for the write buffer of a writable stream
+    /// and the read buffer of a read-only one.
+    fn set_buffering(
+        &mut self,
+        buf: *mut u8,
+        buffering: BufferingKind,
+        size: usize,
+    ) -> Result<(), ErrorNumber> {
+        self.flush_write_buffer()?;
+        self.discard_read_buffer()?;
+
+        // read-ahead from a pipe can't be put back
+        if self
+            .read
+            .as_ref()
+            .map_or(false, |r| !r.get_slice().is_empty())
+        {
+            return Err(ErrorNumber::Inval);
+        }
+
+        self.buffering = buffering;
+
+        if size == 0 {
+            return Ok(());
+        }
+
+        let storage = if buf.is_null() {
+            Storage::Unallocated
+        } else {
+            Storage::User(buf as *mut MaybeUninit<u8>)
+        };
+
+        match (self.read.as_mut(), self.write.as_mut()) {
+            (read, Some(write)) => {
+                write.replace_storage(storage, size);
+
+                if let Some(read) = read {
+                    read.replace_storage(Storage::Unallocated, size);
+                }
+            }
+            (Some(read), None) => read.replace_storage(storage, size),
+            (None, None) => (),
+        }
+
+        Ok(())
+    }
+
+    /// Returns where the next read or write will happen, flushing any
+    /// pending writes first.
+    fn tell(&mut self) -> Result<off_t, ErrorNumber> {
+        self.flush_write_buffer()?;
+
+        let offset = ErrorNumber::from_syscall::<off_t>(unsafe {
+            unistd::sys::lseek(self.fd.as_raw(), 0, unistd::SEEK_CUR)
+        })?;
+        let unread_len = self.read.as_ref().map_or(0, |r| r.get_slice().len());
+
+        Ok(offset - unread_len as off_t)
+    }
+
+    fn seek(&mut self, offset: off_t, whence: c_int) -> Result<off_t, ErrorNumber> {
+        self.flush_write_buffer()?;
+
+        let unread_len = self.read.as_ref().map_or(0, |r| r.get_slice().len());
+
+        // the file offset is past everything we read ahead
+        let offset = if whence == unistd::SEEK_CUR {
+            offset
+                .checked_sub(unread_len as off_t)
+                .ok_or(ErrorNumber::Overflow)?
+        } else {
+            offset
+        };
+
+        let new_offset = ErrorNumber::from_syscall::<off_t>(unsafe {
+            unistd::sys::lseek(self.fd.as_raw(), offset, whence)
+        })?;
+
+        if let Some(read) = self.read.as_mut() {
+            read.get(unread_len);
+        }
+
+        self.is_eof = false;
+
+        Ok(new_offset)
+    }
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    use crate::syscall;
+
+    pub(crate) unsafe fn renameat(
+        olddirfd: c_int,
+        oldpath: *const c_char,
+        newdirfd: c_int,
+        newpath: *const c_char,
+    ) -> isize {
+        syscall!(
+            264,
+            olddirfd as isize,
+            oldpath as isize,
+            newdirfd as isize,
+            newpath as isize
+        )
+    }
+
+    pub(crate) unsafe fn renameat2(
+        olddirfd: c_int,
+        oldpath: *const c_char,
+        newdirfd: c_int,
+        newpath: *const c_char,
+        flags: c_unsignedint,
+    ) -> isize {
+        syscall!(
+            316,
+            olddirfd as isize,
+            oldpath as isize,
+            newdirfd as isize,
+            newpath as isize,
+            flags as isize
+        )
+    }
 }
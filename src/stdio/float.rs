@@ -0,0 +1,930 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::printf::{Counter, Sink, Spec};
+
+use crate::internal::errno::ErrorNumber;
+
+use core::cmp;
+
+// 2^53 * 5^1074 (the largest significand times the smallest exponent) has 767 digits
+const MAX_DIGITS: usize = 768;
+const LIMB_BASE: u64 = 1_000_000_000;
+const LIMB_DIGITS: usize = 9;
+const MAX_LIMBS: usize = (MAX_DIGITS + LIMB_DIGITS - 1) / LIMB_DIGITS;
+
+const DEFAULT_PRECISION: usize = 6;
+
+/// Writes `value` as specified by one of the %f, %F, %e, %E, %g, %G, %a or
+/// %A conversions. Output is correctly rounded (ties to even) for any
+/// precision, the same as glibc in the default rounding mode.
+pub(crate) fn write_float<S: Sink>(
+    out: &mut Counter<'_, S>,
+    spec: &Spec,
+    value: f64,
+) -> Result<(), ErrorNumber> {
+    let is_upper = spec.conversion.is_ascii_uppercase();
+
+    let sign = if value.is_sign_negative() {
+        &b"-"[..]
+    } else if spec.plus {
+        &b"+"[..]
+    } else if spec.space {
+        &b" "[..]
+    } else {
+        &b""[..]
+    };
+
+    if !value.is_finite() {
+        let body = match (value.is_nan(), is_upper) {
+            (true, false) => b"nan",
+            (true, true) => b"NAN",
+            (false, false) => b"inf",
+            (false, true) => b"INF",
+        };
+
+        // zero padding would make these look like numbers
+        let spec = Spec {
+            zero: false,
+            ..*spec
+        };
+
+        return write_with_padding(out, &spec, sign, body.len(), |out| out.write(body));
+    }
+
+    match spec.conversion.to_ascii_lowercase() {
+        b'f' => {
+            let precision = spec.precision.unwrap_or(DEFAULT_PRECISION);
+            let mut decimal = Decimal::new(value.abs());
+            decimal.round_to(decimal.point.saturating_add(precision as isize));
+
+            write_fixed(out, spec, sign, &decimal, precision, spec.alt)
+        }
+        b'e' => {
+            let precision = spec.precision.unwrap_or(DEFAULT_PRECISION);
+            let mut decimal = Decimal::new(value.abs());
+            decimal.round_to(precision.saturating_add(1) as isize);
+
+            write_exponential(out, spec, sign, &decimal, precision, spec.alt)
+        }
+        b'g' => {
+            let precision = cmp::max(spec.precision.unwrap_or(DEFAULT_PRECISION), 1);
+            let mut decimal = Decimal::new(value.abs());
+            let unrounded_exponent = decimal.exponent();
+            decimal.round_to(precision as isize);
+
+            let exponent = decimal.exponent();
+
+            // trailing zeros are already trimmed from decimal, so without '#' the precision is
+            // only an upper bound on how many fractional digits we print
+            if exponent < precision as isize && exponent >= -4 {
+                let precision = (precision as isize - 1 - exponent) as usize;
+                let precision = if spec.alt {
+                    precision
+                } else {
+                    cmp::min(
+                        precision,
+                        cmp::max(decimal.len as isize - decimal.point, 0) as usize,
+                    )
+                };
+
+                write_fixed(out, spec, sign, &decimal, precision, spec.alt)
+            } else {
+                let precision =
+                    if unrounded_exponent >= -4 && unrounded_exponent < precision as isize {
+                        // glibc quirk: rounding carried us out of fixed notation (e.g. "%#.2g" of
+                        // 99.5), and we keep the fixed notation's lack of fractional digits
+                        0
+                    } else if spec.alt {
+                        precision - 1
+                    } else {
+                        decimal.len.saturating_sub(1)
+                    };
+
+                write_exponential(out, spec, sign, &decimal, precision, spec.alt)
+            }
+        }
+        b'a' => write_hexadecimal(out, spec, sign, value.abs()),
+        _ => Err(ErrorNumber::Inval),
+    }
+}
+
+/// The exact decimal expansion of a finite, non-negative double.
+///
+/// The value is 0.D * 10^point, where D is `digits[..len]`. Leading and
+/// trailing zeros are never stored, so zero has `len == 0`.
+struct Decimal {
+    digits: [u8; MAX_DIGITS],
+    len: usize,
+    point: isize,
+}
+
+impl Decimal {
+    fn new(value: f64) -> Decimal {
+        let bits = value.to_bits();
+        let biased_exponent = ((bits >> 52) & 0x7ff) as i32;
+        let fraction = bits & ((1 << 52) - 1);
+
+        let (mantissa, exponent) = if biased_exponent == 0 {
+            (fraction, -1074)
+        } else {
+            (fraction | (1 << 52), biased_exponent - 1075)
+        };
+
+        let mut decimal = Decimal {
+            digits: [0; MAX_DIGITS],
+            len: 0,
+            point: 1,
+        };
+
+        if mantissa == 0 {
+            return decimal;
+        }
+
+        // mantissa * 2^exponent is either an integer or mantissa * 5^-exponent / 10^-exponent
+        let mut limbs = [0u32; MAX_LIMBS];
+        limbs[0] = (mantissa % LIMB_BASE) as u32;
+        limbs[1] = ((mantissa / LIMB_BASE) % LIMB_BASE) as u32;
+        let mut num_limbs = if limbs[1] != 0 { 2 } else { 1 };
+
+        let (mut remaining, max_step, factor_for): (i32, i32, fn(i32) -> u64) = if exponent >= 0 {
+            (exponent, 29, |n| 1u64 << n)
+        } else {
+            (-exponent, 13, |n| 5u64.pow(n as u32))
+        };
+
+        while remaining > 0 {
+            let step = cmp::min(remaining, max_step);
+            let factor = factor_for(step);
+            let mut carry = 0;
+
+            for limb in limbs[..num_limbs].iter_mut() {
+                let product = *limb as u64 * factor + carry;
+                *limb = (product % LIMB_BASE) as u32;
+                carry = product / LIMB_BASE;
+            }
+
+            // 5^13 > LIMB_BASE, so this can take more than one limb
+            while carry != 0 {
+                limbs[num_limbs] = (carry % LIMB_BASE) as u32;
+                carry /= LIMB_BASE;
+                num_limbs += 1;
+            }
+
+            remaining -= step;
+        }
+
+        let top = limbs[num_limbs - 1];
+        let mut top_digits = 0;
+        let mut t = top;
+
+        while t > 0 {
+            top_digits += 1;
+            t /= 10;
+        }
+
+        let mut len = 0;
+
+        for (i, limb) in limbs[..num_limbs].iter().rev().copied().enumerate() {
+            let num_digits = if i == 0 { top_digits } else { LIMB_DIGITS };
+            let mut limb = limb;
+
+            for j in (0..num_digits).rev() {
+                decimal.digits[len + j] = (limb % 10) as u8;
+                limb /= 10;
+            }
+
+            len += num_digits;
+        }
+
+        decimal.len = len;
+        decimal.point = len as isize + cmp::min(exponent, 0) as isize;
+        decimal.trim();
+
+        decimal
+    }
+
+    fn digit(&self, i: isize) -> u8 {
+        if i >= 0 && (i as usize) < self.len {
+            self.digits[i as usize]
+        } else {
+            0
+        }
+    }
+
+    /// The exponent that this number would have in scientific notation.
+    fn exponent(&self) -> isize {
+        if self.len == 0 {
+            0
+        } else {
+            self.point - 1
+        }
+    }
+
+    /// Rounds to `n` significant digits, ties to even. `n` may be zero or
+    /// negative, in which case the result is either zero or a single digit.
+    fn round_to(&mut self, n: isize) {
+        if n >= self.len as isize {
+            return;
+        }
+
+        if n < 0 {
+            self.len = 0;
+
+            return;
+        }
+
+        let n = n as usize;
+        let first_dropped = self.digits[n];
+        let is_tie = first_dropped == 5 && self.len == n + 1;
+        let last_kept_is_odd = n > 0 && self.digits[n - 1] % 2 == 1;
+
+        let round_up = first_dropped > 5 || (first_dropped == 5 && (!is_tie || last_kept_is_odd));
+
+        self.len = n;
+
+        if round_up {
+            let mut i = n;
+
+            loop {
+                if i == 0 {
+                    // carried out of the first digit, e.g. 999 -> 1000
+                    self.digits[0] = 1;
+                    self.len = 1;
+                    self.point += 1;
+
+                    break;
+                }
+
+                i -= 1;
+
+                if self.digits[i] == 9 {
+                    self.digits[i] = 0;
+                } else {
+                    self.digits[i] += 1;
+
+                    break;
+                }
+            }
+        }
+
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.len > 0 && self.digits[self.len - 1] == 0 {
+            self.len -= 1;
+        }
+
+        let leading_zeros = self.digits[..self.len]
+            .iter()
+            .take_while(|&&d| d == 0)
+            .count();
+
+        if leading_zeros > 0 {
+            self.digits.copy_within(leading_zeros..self.len, 0);
+            self.len -= leading_zeros;
+            self.point -= leading_zeros as isize;
+        }
+    }
+}
+
+fn write_fixed<S: Sink>(
+    out: &mut Counter<'_, S>,
+    spec: &Spec,
+    sign: &[u8],
+    decimal: &Decimal,
+    precision: usize,
+    force_point: bool,
+) -> Result<(), ErrorNumber> {
+    let has_point = precision > 0 || force_point;
+    let num_integer_digits = if decimal.len > 0 && decimal.point > 0 {
+        decimal.point as usize
+    } else {
+        1
+    };
+    let body_len = num_integer_digits + has_point as usize + precision;
+
+    write_with_padding(out, spec, sign, body_len, |out| {
+        if decimal.len > 0 && decimal.point > 0 {
+            write_digits(out, decimal, 0, decimal.point)?;
+        } else {
+            out.write(b"0")?;
+        }
+
+        if has_point {
+            out.write(b".")?;
+        }
+
+        write_digits(
+            out,
+            decimal,
+            decimal.point,
+            decimal.point.saturating_add(precision as isize),
+        )
+    })
+}
+
+fn write_exponential<S: Sink>(
+    out: &mut Counter<'_, S>,
+    spec: &Spec,
+    sign: &[u8],
+    decimal: &Decimal,
+    precision: usize,
+    force_point: bool,
+) -> Result<(), ErrorNumber> {
+    let has_point = precision > 0 || force_point;
+    let exponent = decimal.exponent();
+
+    let mut exponent_buf = [0u8; 6];
+    exponent_buf[0] = if spec.conversion.is_ascii_uppercase() {
+        b'E'
+    } else {
+        b'e'
+    };
+    exponent_buf[1] = if exponent < 0 { b'-' } else { b'+' };
+
+    let mut magnitude = exponent.abs();
+    let num_exponent_digits = if magnitude >= 100 { 3 } else { 2 };
+
+    for i in (0..num_exponent_digits).rev() {
+        exponent_buf[2 + i] = b'0' + (magnitude % 10) as u8;
+        magnitude /= 10;
+    }
+
+    let exponent_str = &exponent_buf[..2 + num_exponent_digits];
+    let body_len = 1 + has_point as usize + precision + exponent_str.len();
+
+    write_with_padding(out, spec, sign, body_len, |out| {
+        write_digits(out, decimal, 0, 1)?;
+
+        if has_point {
+            out.write(b".")?;
+        }
+
+        write_digits(out, decimal, 1, 1 + precision as isize)?;
+        out.write(exponent_str)
+    })
+}
+
+fn write_hexadecimal<S: Sink>(
+    out: &mut Counter<'_, S>,
+    spec: &Spec,
+    sign: &[u8],
+    value: f64,
+) -> Result<(), ErrorNumber> {
+    const FRACTION_DIGITS: usize = 13;
+
+    let is_upper = spec.conversion == b'A';
+    let hex_chars: &[u8; 16] = if is_upper {
+        b"0123456789ABCDEF"
+    } else {
+        b"0123456789abcdef"
+    };
+
+    let bits = value.to_bits();
+    let biased_exponent = ((bits >> 52) & 0x7ff) as i32;
+    let mut fraction = bits & ((1 << 52) - 1);
+
+    let (mut leading, exponent) = if biased_exponent == 0 {
+        (0, if fraction == 0 { 0 } else { -1022 })
+    } else {
+        (1, biased_exponent - 1023)
+    };
+
+    let precision = match spec.precision {
+        Some(p) => p,
+        None => {
+            let mut p = FRACTION_DIGITS;
+
+            while p > 0 && (fraction >> (4 * (FRACTION_DIGITS - p))) & 0xf == 0 {
+                p -= 1;
+            }
+
+            p
+        }
+    };
+
+    if precision < FRACTION_DIGITS {
+        let dropped_bits = 4 * (FRACTION_DIGITS - precision) as u32;
+        let dropped = fraction & ((1 << dropped_bits) - 1);
+        let half = 1 << (dropped_bits - 1);
+        let mut kept = fraction >> dropped_bits;
+
+        let kept_is_odd = if precision == 0 {
+            leading % 2 == 1
+        } else {
+            kept % 2 == 1
+        };
+
+        if dropped > half || (dropped == half && kept_is_odd) {
+            kept += 1;
+
+            // like glibc, carry into the leading digit instead of renormalizing
+            if kept >> (4 * precision) != 0 {
+                kept = 0;
+                leading += 1;
+            }
+        }
+
+        fraction = kept << dropped_bits;
+    }
+
+    let mut digits = [0u8; FRACTION_DIGITS];
+
+    for (i, digit) in digits.iter_mut().enumerate() {
+        *digit = hex_chars[((fraction >> (4 * (FRACTION_DIGITS - 1 - i))) & 0xf) as usize];
+    }
+
+    let num_fraction_digits = cmp::min(precision, FRACTION_DIGITS);
+    let num_trailing_zeros = precision - num_fraction_digits;
+    let has_point = precision > 0 || spec.alt;
+
+    let mut exponent_buf = [0u8; 7];
+    exponent_buf[0] = if is_upper { b'P' } else { b'p' };
+    exponent_buf[1] = if exponent < 0 { b'-' } else { b'+' };
+
+    let mut magnitude = exponent.abs();
+    let mut num_exponent_digits = 0;
+    let mut reversed = [0u8; 4];
+
+    loop {
+        reversed[num_exponent_digits] = b'0' + (magnitude % 10) as u8;
+        num_exponent_digits += 1;
+        magnitude /= 10;
+
+        if magnitude == 0 {
+            break;
+        }
+    }
+
+    for i in 0..num_exponent_digits {
+        exponent_buf[2 + i] = reversed[num_exponent_digits - 1 - i];
+    }
+
+    let exponent_str = &exponent_buf[..2 + num_exponent_digits];
+
+    let mut prefix_buf = [0u8; 3];
+    prefix_buf[..sign.len()].copy_from_slice(sign);
+    prefix_buf[sign.len()..sign.len() + 2].copy_from_slice(if is_upper { b"0X" } else { b"0x" });
+    let prefix = &prefix_buf[..sign.len() + 2];
+
+    let body_len = 1 + has_point as usize + precision + exponent_str.len();
+
+    write_with_padding(out, spec, prefix, body_len, |out| {
+        out.write(&[hex_chars[leading as usize]])?;
+
+        if has_point {
+            out.write(b".")?;
+        }
+
+        out.write(&digits[..num_fraction_digits])?;
+        out.pad(b'0', num_trailing_zeros)?;
+        out.write(exponent_str)
+    })
+}
+
+/// Writes digits `from..to` of `decimal`, where digit 0 is the most
+/// significant one. Digits outside of what `decimal` stores are zeros.
+fn write_digits<S: Sink>(
+    out: &mut Counter<'_, S>,
+    decimal: &Decimal,
+    from: isize,
+    to: isize,
+) -> Result<(), ErrorNumber> {
+    const CHUNK_LEN: usize = 32;
+
+    // everything past the last stored digit is zero, no need to look at each one
+    let stored_to = cmp::min(to, decimal.len as isize);
+    let mut i = from;
+
+    while i < stored_to {
+        let mut chunk = [0u8; CHUNK_LEN];
+        let this_len = cmp::min((stored_to - i) as usize, CHUNK_LEN);
+
+        for (j, ch) in chunk[..this_len].iter_mut().enumerate() {
+            *ch = b'0' + decimal.digit(i + j as isize);
+        }
+
+        out.write(&chunk[..this_len])?;
+        i += this_len as isize;
+    }
+
+    if to > i {
+        out.pad(b'0', (to - i) as usize)?;
+    }
+
+    Ok(())
+}
+
+fn write_with_padding<S: Sink, F: FnOnce(&mut Counter<'_, S>) -> Result<(), ErrorNumber>>(
+    out: &mut Counter<'_, S>,
+    spec: &Spec,
+    prefix: &[u8],
+    body_len: usize,
+    write_body: F,
+) -> Result<(), ErrorNumber> {
+    let padding = spec.width.saturating_sub(prefix.len() + body_len);
+
+    if spec.left {
+        out.write(prefix)?;
+        write_body(out)?;
+        out.pad(b' ', padding)
+    } else if spec.zero {
+        out.write(prefix)?;
+        out.pad(b'0', padding)?;
+        write_body(out)
+    } else {
+        out.pad(b' ', padding)?;
+        out.write(prefix)?;
+        write_body(out)
+    }
+}
+
+/// Which IEEE 754 binary format to round a parsed number to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Precision {
+    Single,
+    Double,
+}
+
+impl Precision {
+    fn mantissa_bits(self) -> i64 {
+        match self {
+            Precision::Single => 24,
+            Precision::Double => 53,
+        }
+    }
+
+    fn min_exponent(self) -> i64 {
+        match self {
+            Precision::Single => -126,
+            Precision::Double => -1022,
+        }
+    }
+
+    fn max_exponent(self) -> i64 {
+        match self {
+            Precision::Single => 127,
+            Precision::Double => 1023,
+        }
+    }
+
+    fn with_bits(self, bits: u64) -> f64 {
+        match self {
+            Precision::Single => f32::from_bits(bits as u32) as f64,
+            Precision::Double => f64::from_bits(bits),
+        }
+    }
+}
+
+/// Rounds 0.D * 10^point to the nearest number representable with
+/// `precision`, ties to even. D is `digits`, each between 0 and 9, and
+/// `is_truncated` says whether nonzero digits were dropped from its end.
+///
+/// The result is always exactly representable with `precision`.
+pub(crate) fn from_decimal(
+    digits: &[u8],
+    point: i64,
+    is_truncated: bool,
+    precision: Precision,
+) -> f64 {
+    // anything past 10^310 overflows and anything below 10^-324 is less
+    // than half of the smallest subnormal double
+    if digits.iter().all(|&d| d == 0) || point <= -324 {
+        return 0.0;
+    } else if point > 310 {
+        return f64::INFINITY;
+    }
+
+    let mut numerator = BigInt::zero();
+
+    for chunk in digits.chunks(LIMB_DIGITS) {
+        let chunk_value = chunk.iter().fold(0, |acc, &d| acc * 10 + d as u32);
+        numerator.mul_small(10u32.pow(chunk.len() as u32));
+        numerator.add_small(chunk_value);
+    }
+
+    let mut num_digits = digits.len() as i64;
+
+    // a trailing 1 is enough to break what would otherwise look like a tie
+    if is_truncated {
+        numerator.mul_small(10);
+        numerator.add_small(1);
+        num_digits += 1;
+    }
+
+    let exponent = point - num_digits;
+
+    if exponent >= 0 {
+        numerator.mul_pow10(exponent as u32);
+        let (mantissa, shift, is_inexact) = numerator.top_bits();
+
+        return from_binary(mantissa, shift, is_inexact, precision);
+    }
+
+    let mut denominator = BigInt::zero();
+    denominator.add_small(1);
+    denominator.mul_pow10(-exponent as u32);
+
+    // scale so that the quotient has 63 or 64 bits
+    let shift = 63 + denominator.bit_len() as i64 - numerator.bit_len() as i64;
+
+    if shift >= 0 {
+        numerator.shl(shift as usize);
+    } else {
+        denominator.shl(-shift as usize);
+    }
+
+    denominator.shl(63);
+    let mut quotient = 0u64;
+
+    for bit in (0..64).rev() {
+        if numerator >= denominator {
+            numerator.sub(&denominator);
+            quotient |= 1 << bit;
+        }
+
+        denominator.shr1();
+    }
+
+    from_binary(quotient, -shift, !numerator.is_zero(), precision)
+}
+
+/// Rounds mantissa * 2^exponent to the nearest number representable with
+/// `precision`, ties to even. `is_truncated` says whether nonzero bits were
+/// dropped from the end of `mantissa`.
+pub(crate) fn from_binary(
+    mantissa: u64,
+    exponent: i64,
+    is_truncated: bool,
+    precision: Precision,
+) -> f64 {
+    if mantissa == 0 {
+        return 0.0;
+    }
+
+    let leading_zeros = mantissa.leading_zeros();
+
+    assemble(
+        mantissa << leading_zeros,
+        exponent.saturating_sub(leading_zeros as i64),
+        is_truncated,
+        precision,
+    )
+}
+
+/// `mantissa` must have its top bit set.
+fn assemble(mantissa: u64, exponent: i64, is_truncated: bool, precision: Precision) -> f64 {
+    let mantissa_bits = precision.mantissa_bits();
+    let min_exponent = precision.min_exponent();
+    let top_exponent = exponent.saturating_add(63);
+
+    if top_exponent > precision.max_exponent() {
+        return f64::INFINITY;
+    }
+
+    // subnormals have fewer bits of precision, and anything smaller than
+    // half the smallest one rounds to zero
+    let num_kept = cmp::min(mantissa_bits, top_exponent - min_exponent + mantissa_bits);
+
+    if num_kept < 0 {
+        return 0.0;
+    }
+
+    let num_dropped = (64 - num_kept) as u32;
+    let (mut kept, dropped, half) = if num_dropped == 64 {
+        (0, mantissa, 1 << 63)
+    } else {
+        (
+            mantissa >> num_dropped,
+            mantissa & ((1 << num_dropped) - 1),
+            1 << (num_dropped - 1),
+        )
+    };
+
+    if dropped > half || (dropped == half && (is_truncated || kept % 2 == 1)) {
+        kept += 1;
+    }
+
+    // the implicit leading bit lands in the exponent field, so carries out of
+    // the mantissa (and out of subnormals) need no special handling
+    let min_kept_exponent = min_exponent - (mantissa_bits - 1);
+    let kept_exponent = exponent + num_dropped as i64;
+    let bits = (((kept_exponent - min_kept_exponent) as u64) << (mantissa_bits - 1)) + kept;
+
+    let infinity_bits = ((2 * precision.max_exponent() + 1) as u64) << (mantissa_bits - 1);
+
+    if bits >= infinity_bits {
+        f64::INFINITY
+    } else {
+        precision.with_bits(bits)
+    }
+}
+
+/// Just enough of an arbitrary precision unsigned integer for from_decimal.
+/// The largest number it needs to hold is 10^1125 shifted left by 127 bits.
+struct BigInt {
+    limbs: [u32; BigInt::MAX_LIMBS],
+    len: usize,
+}
+
+impl BigInt {
+    const MAX_LIMBS: usize = 128;
+
+    fn zero() -> BigInt {
+        BigInt {
+            limbs: [0; BigInt::MAX_LIMBS],
+            len: 0,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.len == 0
+    }
+
+    fn bit_len(&self) -> usize {
+        if self.len == 0 {
+            0
+        } else {
+            32 * self.len - self.limbs[self.len - 1].leading_zeros() as usize
+        }
+    }
+
+    fn add_small(&mut self, mut value: u32) {
+        let mut i = 0;
+
+        while value != 0 {
+            if i == self.len {
+                self.limbs[i] = 0;
+                self.len += 1;
+            }
+
+            let (sum, carried) = self.limbs[i].overflowing_add(value);
+            self.limbs[i] = sum;
+            value = carried as u32;
+            i += 1;
+        }
+    }
+
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0u64;
+
+        for limb in self.limbs[..self.len].iter_mut() {
+            let product = *limb as u64 * factor as u64 + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+
+        if carry != 0 {
+            self.limbs[self.len] = carry as u32;
+            self.len += 1;
+        }
+    }
+
+    fn mul_pow10(&mut self, mut n: u32) {
+        while n > 0 {
+            let step = cmp::min(n, LIMB_DIGITS as u32);
+            self.mul_small(10u32.pow(step));
+            n -= step;
+        }
+    }
+
+    fn shl(&mut self, n: usize) {
+        if self.len == 0 {
+            return;
+        }
+
+        let limb_shift = n / 32;
+        let bit_shift = n % 32;
+
+        if bit_shift != 0 {
+            self.limbs[self.len] = 0;
+            self.len += 1;
+
+            for i in (1..self.len).rev() {
+                self.limbs[i] =
+                    (self.limbs[i] << bit_shift) | (self.limbs[i - 1] >> (32 - bit_shift));
+            }
+
+            self.limbs[0] <<= bit_shift;
+        }
+
+        if limb_shift != 0 {
+            self.limbs.copy_within(..self.len, limb_shift);
+
+            for limb in self.limbs[..limb_shift].iter_mut() {
+                *limb = 0;
+            }
+
+            self.len += limb_shift;
+        }
+
+        self.normalize();
+    }
+
+    fn shr1(&mut self) {
+        for i in 0..self.len {
+            let next = if i + 1 < self.len {
+                self.limbs[i + 1]
+            } else {
+                0
+            };
+
+            self.limbs[i] = (self.limbs[i] >> 1) | (next << 31);
+        }
+
+        self.normalize();
+    }
+
+    /// `other` must not be greater than `self`.
+    fn sub(&mut self, other: &BigInt) {
+        let mut borrow = false;
+
+        for i in 0..self.len {
+            let rhs = if i < other.len { other.limbs[i] } else { 0 };
+            let (difference, borrowed_once) = self.limbs[i].overflowing_sub(rhs);
+            let (difference, borrowed_twice) = difference.overflowing_sub(borrow as u32);
+
+            self.limbs[i] = difference;
+            borrow = borrowed_once || borrowed_twice;
+        }
+
+        self.normalize();
+    }
+
+    /// Returns the top 64 bits, how far they are shifted right from their
+    /// actual position, and whether any of the remaining bits are set.
+    fn top_bits(&self) -> (u64, i64, bool) {
+        let bit_len = self.bit_len();
+
+        if bit_len <= 64 {
+            let mut value = 0u64;
+
+            for &limb in self.limbs[..self.len].iter().rev() {
+                value = (value << 32) | limb as u64;
+            }
+
+            return (value, 0, false);
+        }
+
+        let shift = bit_len - 64;
+        let limb_shift = shift / 32;
+        let bit_shift = shift % 32;
+
+        let mut value = 0u128;
+
+        for &limb in self.limbs[limb_shift..self.len].iter().rev() {
+            value = (value << 32) | limb as u128;
+        }
+
+        let value = (value >> bit_shift) as u64;
+        let is_inexact = self.limbs[..limb_shift].iter().any(|&l| l != 0)
+            || self.limbs[limb_shift] & ((1 << bit_shift) - 1) != 0;
+
+        (value, shift as i64, is_inexact)
+    }
+
+    fn normalize(&mut self) {
+        while self.len > 0 && self.limbs[self.len - 1] == 0 {
+            self.len -= 1;
+        }
+    }
+}
+
+impl PartialEq for BigInt {
+    fn eq(&self, other: &BigInt) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl Eq for BigInt {}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> cmp::Ordering {
+        self.len.cmp(&other.len).then_with(|| {
+            self.limbs[..self.len]
+                .iter()
+                .rev()
+                .cmp(other.limbs[..other.len].iter().rev())
+        })
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::FILE;
+
+use crate::internal::{alloc::Box, errno::ErrorNumber, sync::Mutex};
+
+use core::{mem, ptr};
+
+// every live FILE, so that exit and fflush(NULL) can flush the ones the program never closed. the
+// list is threaded through FILE::prev_open and FILE::next_open, which are only touched with
+// OPEN_FILES locked. a FILE is removed before it's freed, so holding the lock keeps them all alive
+static OPEN_FILES: Mutex<List> = Mutex::new(List {
+    head: ptr::null_mut(),
+});
+
+struct List {
+    head: *mut FILE,
+}
+
+// only ever touched with OPEN_FILES locked
+unsafe impl Send for List {}
+
+impl List {
+    /// `file` must be in this list.
+    unsafe fn unlink(&mut self, file: *mut FILE) {
+        let prev = (*file).prev_open.replace(ptr::null_mut());
+        let next = (*file).next_open.replace(ptr::null_mut());
+
+        if let Some(prev) = prev.as_ref() {
+            prev.next_open.set(next);
+        } else {
+            self.head = next;
+        }
+
+        if let Some(next) = next.as_ref() {
+            next.prev_open.set(prev);
+        }
+    }
+}
+
+/// Hands `file` over to C, keeping track of it until `remove`.
+pub(super) fn insert(file: Box<FILE>) -> *mut FILE {
+    let file = Box::into_raw(file);
+    let mut list = OPEN_FILES.lock();
+
+    unsafe {
+        (*file).prev_open.set(ptr::null_mut());
+        (*file).next_open.set(list.head);
+
+        if let Some(head) = list.head.as_ref() {
+            head.prev_open.set(file);
+        }
+    }
+
+    list.head = file;
+
+    file
+}
+
+/// Stops tracking `file`, which must have come from `insert`.
+pub(super) unsafe fn remove(file: *mut FILE) {
+    OPEN_FILES.lock().unlink(file);
+}
+
+/// Flushes every open stream's write buffer, carrying on past errors.
+/// Returns the last error.
+pub(super) fn flush_all() -> Result<(), ErrorNumber> {
+    let list = OPEN_FILES.lock();
+    let mut result = Ok(());
+    let mut file = list.head;
+
+    while let Some(f) = unsafe { file.as_ref() } {
+        // input-only streams have nothing to flush, and a reader can hold one across a blocking
+        // read
+        if f.is_writable {
+            if let Err(e) = f.inner.lock().flush_write_buffer() {
+                result = Err(e);
+            }
+        }
+
+        file = f.next_open.get();
+    }
+
+    result
+}
+
+/// Like `flush_all`, but skips streams another thread is using instead of
+/// waiting for them. That thread may be blocked in a read that will never
+/// finish, and waiting for it would keep the process from exiting.
+pub(super) fn flush_all_for_exit() {
+    let list = OPEN_FILES.lock();
+    let mut file = list.head;
+
+    while let Some(f) = unsafe { file.as_ref() } {
+        if f.is_writable {
+            if let Some(mut inner) = f.inner.try_lock() {
+                let _ = inner.flush_write_buffer();
+            }
+        }
+
+        file = f.next_open.get();
+    }
+}
+
+/// Stops tracking every stream for which `should_take` returns true,
+/// returning them as a list linked through FILE::next_open.
+pub(super) fn take_if<F: FnMut(*mut FILE) -> bool>(mut should_take: F) -> *mut FILE {
+    let mut list = OPEN_FILES.lock();
+    let mut taken = ptr::null_mut();
+    let mut file = list.head;
+
+    while !file.is_null() {
+        let next = unsafe { (*file).next_open.get() };
+
+        if should_take(file) {
+            unsafe {
+                list.unlink(file);
+                (*file).next_open.set(taken);
+            }
+
+            taken = file;
+        }
+
+        file = next;
+    }
+
+    taken
+}
+
+/// Locks the list, leaving it locked until `unlock_after_fork`. The streams
+/// themselves stay unlocked: another thread can hold one across a blocking
+/// read, and waiting for it would hang the fork.
+pub(super) fn lock_for_fork() {
+    mem::forget(OPEN_FILES.lock());
+}
+
+/// Unlocks every stream in the child of a fork, since the threads that held
+/// them didn't come along. Must follow `lock_for_fork` in the same thread.
+pub(super) unsafe fn reset_after_fork() {
+    let mut file = (*OPEN_FILES.as_ptr()).head;
+
+    while let Some(f) = file.as_ref() {
+        f.inner.reset_after_fork();
+        file = f.next_open.get();
+    }
+}
+
+/// Must follow `lock_for_fork` in the same thread.
+pub(super) unsafe fn unlock_after_fork() {
+    OPEN_FILES.force_unlock();
+}
@@ -0,0 +1,437 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::float;
+
+use crate::{c_char, c_int, c_long, c_short, c_void, internal::errno::ErrorNumber};
+
+use core::{cmp, ffi::VaList, slice};
+
+/// Destination for formatted output. Implementations decide what happens
+/// when they run out of space; the formatter only ever appends.
+pub(crate) trait Sink {
+    fn write(&mut self, data: &[u8]) -> Result<(), ErrorNumber>;
+}
+
+/// Formats `format` with arguments from `ap` into `sink`, following C's
+/// printf rules. Returns the number of bytes produced.
+pub(crate) unsafe fn format<S: Sink>(
+    sink: &mut S,
+    format: &[u8],
+    ap: &mut VaList,
+) -> Result<usize, ErrorNumber> {
+    let mut out = Counter { sink, count: 0 };
+    let mut rest = format;
+
+    while !rest.is_empty() {
+        let literal_len = rest
+            .iter()
+            .copied()
+            .position(|ch| ch == b'%')
+            .unwrap_or(rest.len());
+        out.write(&rest[..literal_len])?;
+        rest = &rest[literal_len..];
+
+        if rest.is_empty() {
+            break;
+        }
+
+        let (spec, spec_len) = Spec::parse(&rest[1..], ap)?;
+        rest = &rest[1 + spec_len..];
+
+        match spec.conversion {
+            b'%' => out.write(b"%")?,
+            b'd' | b'i' => {
+                let value = match spec.length {
+                    Length::Char => ap.arg::<c_int>() as i8 as i64,
+                    Length::Short => ap.arg::<c_int>() as c_short as i64,
+                    Length::Int => ap.arg::<c_int>() as i64,
+                    // glibc takes %Ld to mean %lld
+                    Length::Long | Length::LongLong | Length::IntMax | Length::LongDouble => {
+                        ap.arg::<c_long>()
+                    }
+                    Length::Size | Length::PtrDiff => ap.arg::<isize>() as i64,
+                };
+
+                let sign = if value < 0 {
+                    &b"-"[..]
+                } else if spec.plus {
+                    &b"+"[..]
+                } else if spec.space {
+                    &b" "[..]
+                } else {
+                    &b""[..]
+                };
+
+                write_integer(&mut out, &spec, sign, value.wrapping_abs() as u64, 10)?;
+            }
+            b'u' | b'x' | b'X' | b'o' => {
+                let value = match spec.length {
+                    Length::Char => ap.arg::<c_int>() as u8 as u64,
+                    Length::Short => ap.arg::<c_int>() as u16 as u64,
+                    Length::Int => ap.arg::<c_int>() as u32 as u64,
+                    Length::Long | Length::LongLong | Length::IntMax | Length::LongDouble => {
+                        ap.arg::<c_long>() as u64
+                    }
+                    Length::Size | Length::PtrDiff => ap.arg::<usize>() as u64,
+                };
+
+                let (prefix, radix) = match spec.conversion {
+                    b'x' if spec.alt && value != 0 => (&b"0x"[..], 16),
+                    b'X' if spec.alt && value != 0 => (&b"0X"[..], 16),
+                    b'x' | b'X' => (&b""[..], 16),
+                    b'o' => (&b""[..], 8),
+                    _ => (&b""[..], 10),
+                };
+
+                write_integer(&mut out, &spec, prefix, value, radix)?;
+            }
+            b'p' => {
+                let value = ap.arg::<*mut c_void>() as usize as u64;
+
+                if value == 0 {
+                    let spec = Spec {
+                        precision: None,
+                        ..spec
+                    };
+
+                    write_padded(&mut out, &spec, b"(nil)")?;
+                } else {
+                    // glibc honors the sign flags here too, so we do as well
+                    let prefix = if spec.plus {
+                        &b"+0x"[..]
+                    } else if spec.space {
+                        &b" 0x"[..]
+                    } else {
+                        &b"0x"[..]
+                    };
+
+                    write_integer(&mut out, &spec, prefix, value, 16)?;
+                }
+            }
+            b'c' => {
+                let ch = ap.arg::<c_int>() as u8;
+                let spec = Spec {
+                    precision: None,
+                    ..spec
+                };
+
+                write_padded(&mut out, &spec, &[ch])?;
+            }
+            b's' => {
+                if spec.length != Length::Int {
+                    // no wide character support
+                    return Err(ErrorNumber::Inval);
+                }
+
+                let s = ap.arg::<*const c_char>();
+
+                if s.is_null() {
+                    // same as glibc: print nothing if "(null)" wouldn't fit
+                    let null_str = match spec.precision {
+                        Some(p) if p < b"(null)".len() => &b""[..],
+                        _ => b"(null)",
+                    };
+
+                    write_padded(&mut out, &spec, null_str)?;
+                } else {
+                    let max_len = spec.precision.unwrap_or(usize::MAX);
+                    let mut len = 0;
+
+                    while len < max_len && *s.add(len) != 0 {
+                        len += 1;
+                    }
+
+                    write_padded(&mut out, &spec, slice::from_raw_parts(s as *const u8, len))?;
+                }
+            }
+            b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A' => {
+                if spec.length == Length::LongDouble {
+                    // there's no way to pull an x87 long double out of a VaList
+                    return Err(ErrorNumber::Inval);
+                }
+
+                float::write_float(&mut out, &spec, ap.arg::<f64>())?;
+            }
+            b'n' => {
+                let count = out.count;
+
+                match spec.length {
+                    Length::Char => *ap.arg::<*mut i8>() = count as i8,
+                    Length::Short => *ap.arg::<*mut c_short>() = count as c_short,
+                    Length::Int => *ap.arg::<*mut c_int>() = count as c_int,
+                    Length::Long | Length::LongLong | Length::IntMax | Length::LongDouble => {
+                        *ap.arg::<*mut c_long>() = count as c_long
+                    }
+                    Length::Size | Length::PtrDiff => *ap.arg::<*mut isize>() = count as isize,
+                }
+            }
+            _ => return Err(ErrorNumber::Inval),
+        }
+    }
+
+    Ok(out.count)
+}
+
+/// Wraps a `Sink` to keep track of how many bytes have been written to it.
+pub(crate) struct Counter<'s, S: Sink> {
+    sink: &'s mut S,
+    pub(crate) count: usize,
+}
+
+impl<'s, S: Sink> Counter<'s, S> {
+    pub(crate) fn write(&mut self, data: &[u8]) -> Result<(), ErrorNumber> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.sink.write(data)?;
+        self.count += data.len();
+
+        Ok(())
+    }
+
+    pub(crate) fn pad(&mut self, ch: u8, mut n: usize) -> Result<(), ErrorNumber> {
+        const CHUNK_LEN: usize = 32;
+        let chunk = [ch; CHUNK_LEN];
+
+        while n > 0 {
+            let this_len = cmp::min(n, CHUNK_LEN);
+            self.write(&chunk[..this_len])?;
+            n -= this_len;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Length {
+    Char,
+    Short,
+    Int,
+    Long,
+    LongLong,
+    IntMax,
+    Size,
+    PtrDiff,
+    LongDouble,
+}
+
+impl Length {
+    /// Parses an optional length modifier, returning it and how many bytes
+    /// of `s` it used.
+    pub(crate) fn parse(s: &[u8]) -> (Length, usize) {
+        match (s.get(0), s.get(1)) {
+            (Some(b'h'), Some(b'h')) => (Length::Char, 2),
+            (Some(b'h'), _) => (Length::Short, 1),
+            (Some(b'l'), Some(b'l')) => (Length::LongLong, 2),
+            (Some(b'l'), _) => (Length::Long, 1),
+            (Some(b'q'), _) => (Length::LongLong, 1),
+            (Some(b'j'), _) => (Length::IntMax, 1),
+            (Some(b'z'), _) => (Length::Size, 1),
+            (Some(b't'), _) => (Length::PtrDiff, 1),
+            (Some(b'L'), _) => (Length::LongDouble, 1),
+            _ => (Length::Int, 0),
+        }
+    }
+}
+
+/// One parsed conversion specification, i.e. everything after a '%'.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Spec {
+    pub(crate) left: bool,
+    pub(crate) plus: bool,
+    pub(crate) space: bool,
+    pub(crate) alt: bool,
+    pub(crate) zero: bool,
+    pub(crate) width: usize,
+    pub(crate) precision: Option<usize>,
+    pub(crate) length: Length,
+    pub(crate) conversion: u8,
+}
+
+impl Spec {
+    /// Parses a conversion specification from the bytes following a '%',
+    /// pulling '*' widths and precisions from `ap`. Returns the parsed
+    /// specification and how many bytes of `spec` it used.
+    unsafe fn parse(spec: &[u8], ap: &mut VaList) -> Result<(Spec, usize), ErrorNumber> {
+        let mut parsed = Spec {
+            left: false,
+            plus: false,
+            space: false,
+            alt: false,
+            zero: false,
+            width: 0,
+            precision: None,
+            length: Length::Int,
+            conversion: 0,
+        };
+
+        let mut i = 0;
+
+        loop {
+            match spec.get(i) {
+                Some(b'-') => parsed.left = true,
+                Some(b'+') => parsed.plus = true,
+                Some(b' ') => parsed.space = true,
+                Some(b'#') => parsed.alt = true,
+                Some(b'0') => parsed.zero = true,
+                _ => break,
+            }
+
+            i += 1;
+        }
+
+        if spec.get(i) == Some(&b'*') {
+            let width = ap.arg::<c_int>();
+            i += 1;
+
+            if width < 0 {
+                parsed.left = true;
+            }
+
+            parsed.width = (width as i64).abs() as usize;
+        } else {
+            let (width, width_len) = parse_decimal(&spec[i..])?;
+            parsed.width = width;
+            i += width_len;
+        }
+
+        if spec.get(i) == Some(&b'.') {
+            i += 1;
+
+            if spec.get(i) == Some(&b'*') {
+                let precision = ap.arg::<c_int>();
+                i += 1;
+
+                // a negative precision is taken as if it were omitted
+                if precision >= 0 {
+                    parsed.precision = Some(precision as usize);
+                }
+            } else {
+                let (precision, precision_len) = parse_decimal(&spec[i..])?;
+                parsed.precision = Some(precision);
+                i += precision_len;
+            }
+        }
+
+        let (length, length_len) = Length::parse(&spec[i..]);
+        parsed.length = length;
+        i += length_len;
+
+        parsed.conversion = *spec.get(i).ok_or(ErrorNumber::Inval)?;
+        i += 1;
+
+        // '0' is ignored when '-' is present or, for integers, when a precision is given
+        if parsed.left {
+            parsed.zero = false;
+        }
+
+        Ok((parsed, i))
+    }
+}
+
+pub(crate) fn parse_decimal(s: &[u8]) -> Result<(usize, usize), ErrorNumber> {
+    let mut value: usize = 0;
+    let mut len = 0;
+
+    for ch in s.iter().copied().take_while(u8::is_ascii_digit) {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add((ch - b'0') as usize))
+            .ok_or(ErrorNumber::Overflow)?;
+        len += 1;
+    }
+
+    Ok((value, len))
+}
+
+/// Writes `data` with the field width and justification of `spec`. The
+/// precision is not applied; callers truncate `data` themselves.
+pub(crate) fn write_padded<S: Sink>(
+    out: &mut Counter<'_, S>,
+    spec: &Spec,
+    data: &[u8],
+) -> Result<(), ErrorNumber> {
+    let padding = spec.width.saturating_sub(data.len());
+
+    if spec.left {
+        out.write(data)?;
+        out.pad(b' ', padding)
+    } else {
+        out.pad(b' ', padding)?;
+        out.write(data)
+    }
+}
+
+fn write_integer<S: Sink>(
+    out: &mut Counter<'_, S>,
+    spec: &Spec,
+    prefix: &[u8],
+    mut value: u64,
+    radix: u64,
+) -> Result<(), ErrorNumber> {
+    const DIGITS_LEN: usize = 22; // u64::MAX in octal
+    let digit_chars: &[u8; 16] = if spec.conversion == b'X' {
+        b"0123456789ABCDEF"
+    } else {
+        b"0123456789abcdef"
+    };
+
+    let mut digits = [0u8; DIGITS_LEN];
+    let mut num_digits = 0;
+
+    // "%.0d" prints nothing at all for zero
+    if value != 0 || spec.precision != Some(0) {
+        loop {
+            digits[DIGITS_LEN - 1 - num_digits] = digit_chars[(value % radix) as usize];
+            num_digits += 1;
+            value /= radix;
+
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    let digits = &digits[DIGITS_LEN - num_digits..];
+
+    let mut num_zeros = spec.precision.unwrap_or(0).saturating_sub(num_digits);
+
+    // "%#o" increases the precision so that the first digit is a zero
+    if radix == 8 && spec.alt && num_zeros == 0 && digits.first() != Some(&b'0') {
+        num_zeros = 1;
+    }
+
+    let len = prefix.len() + num_zeros + digits.len();
+    let padding = spec.width.saturating_sub(len);
+
+    if spec.left {
+        out.write(prefix)?;
+        out.pad(b'0', num_zeros)?;
+        out.write(digits)?;
+        out.pad(b' ', padding)
+    } else if spec.zero && spec.precision.is_none() {
+        out.write(prefix)?;
+        out.pad(b'0', padding + num_zeros)?;
+        out.write(digits)
+    } else {
+        out.pad(b' ', padding)?;
+        out.write(prefix)?;
+        out.pad(b'0', num_zeros)?;
+        out.write(digits)
+    }
+}
@@ -0,0 +1,621 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{
+    float::{self, Precision},
+    printf::{self, Length},
+    EOF,
+};
+
+use crate::{c_int, c_void, internal::errno::ErrorNumber};
+
+use core::{cmp, ffi::VaList};
+
+// enough to tell apart any two doubles, see float::from_decimal
+const MAX_SIGNIFICANT_DIGITS: usize = 800;
+const MAX_EXPONENT: i64 = 100_000;
+
+/// Where scanf reads from. Input is only consumed once a conversion
+/// accepts it, so whatever byte made a conversion fail is still there for
+/// the next read.
+pub(crate) trait Source {
+    /// Returns the next byte without consuming it, or `None` at the end of
+    /// input.
+    fn peek(&mut self) -> Option<u8>;
+
+    /// Consumes the byte that `peek` just returned.
+    fn advance(&mut self);
+}
+
+/// Parses input from `source` as directed by `format`, following C's scanf
+/// rules and storing through the pointers in `ap`. Returns the number of
+/// assignments made, or EOF if input ran out before the first one.
+pub(crate) unsafe fn scan<S: Source>(
+    source: &mut S,
+    format: &[u8],
+    ap: &mut VaList,
+) -> Result<c_int, ErrorNumber> {
+    let mut input = Input {
+        source,
+        consumed: 0,
+        limit: usize::MAX,
+    };
+    let mut num_assigned: c_int = 0;
+    let mut rest = format;
+
+    while let Some((&ch, after)) = rest.split_first() {
+        rest = after;
+
+        let result = if is_space(ch) {
+            input.skip_whitespace();
+
+            Ok(false)
+        } else if ch != b'%' {
+            input.expect(ch)
+        } else {
+            let (spec, spec_len) = Spec::parse(rest)?;
+            rest = &rest[spec_len..];
+
+            convert(&mut input, &spec, ap)
+        };
+
+        match result {
+            Ok(true) => num_assigned += 1,
+            Ok(false) => (),
+            Err(Stop::Input) if num_assigned == 0 => return Ok(EOF),
+            Err(Stop::Input) | Err(Stop::Matching) => break,
+            Err(Stop::Error(e)) => return Err(e),
+        }
+    }
+
+    Ok(num_assigned)
+}
+
+/// Why a scan stopped early.
+enum Stop {
+    /// Input ran out.
+    Input,
+    /// Input didn't match the format.
+    Matching,
+    Error(ErrorNumber),
+}
+
+impl From<ErrorNumber> for Stop {
+    fn from(e: ErrorNumber) -> Stop {
+        Stop::Error(e)
+    }
+}
+
+/// Performs one conversion, returning whether it assigned anything.
+unsafe fn convert<S: Source>(
+    input: &mut Input<'_, S>,
+    spec: &Spec<'_>,
+    ap: &mut VaList,
+) -> Result<bool, Stop> {
+    match spec.conversion {
+        b'%' => {
+            input.skip_whitespace();
+
+            return input.expect(b'%');
+        }
+        b'n' => {
+            if !spec.suppress {
+                store_integer(ap, spec.length, input.consumed as u64)?;
+            }
+
+            return Ok(false);
+        }
+        b'c' | b'[' => (),
+        _ => input.skip_whitespace(),
+    }
+
+    if input.peek().is_none() {
+        return Err(Stop::Input);
+    }
+
+    input.limit = spec.width.unwrap_or(usize::MAX);
+    let result = convert_limited(input, spec, ap);
+    input.limit = usize::MAX;
+
+    result.map(|_| !spec.suppress)
+}
+
+unsafe fn convert_limited<S: Source>(
+    input: &mut Input<'_, S>,
+    spec: &Spec<'_>,
+    ap: &mut VaList,
+) -> Result<(), Stop> {
+    match spec.conversion {
+        b'd' | b'i' => {
+            let (is_negative, magnitude, is_overflow) =
+                input.scan_integer(if spec.conversion == b'd' { 10 } else { 0 })?;
+
+            // like strtol, out of range values saturate
+            let limit = i64::MAX as u64 + is_negative as u64;
+            let value = if is_overflow || magnitude > limit {
+                if is_negative {
+                    i64::MIN as u64
+                } else {
+                    i64::MAX as u64
+                }
+            } else if is_negative {
+                magnitude.wrapping_neg()
+            } else {
+                magnitude
+            };
+
+            if !spec.suppress {
+                store_integer(ap, spec.length, value)?;
+            }
+        }
+        b'o' | b'u' | b'x' | b'X' | b'p' => {
+            let base = match spec.conversion {
+                b'o' => 8,
+                b'u' => 10,
+                _ => 16,
+            };
+            let (is_negative, magnitude, is_overflow) = input.scan_integer(base)?;
+
+            // like strtoul, out of range values saturate and negative ones wrap around
+            let value = if is_overflow {
+                u64::MAX
+            } else if is_negative {
+                magnitude.wrapping_neg()
+            } else {
+                magnitude
+            };
+
+            if spec.suppress {
+                return Ok(());
+            }
+
+            if spec.conversion == b'p' {
+                *ap.arg::<*mut *mut c_void>() = value as usize as *mut c_void;
+            } else {
+                store_integer(ap, spec.length, value)?;
+            }
+        }
+        b'a' | b'e' | b'f' | b'g' | b'A' | b'E' | b'F' | b'G' => {
+            let precision = match spec.length {
+                Length::Int => Precision::Single,
+                Length::Long => Precision::Double,
+                _ => return Err(Stop::Error(ErrorNumber::Inval)),
+            };
+            let value = input.scan_float(precision)?;
+
+            if spec.suppress {
+                return Ok(());
+            }
+
+            if precision == Precision::Single {
+                *ap.arg::<*mut f32>() = value as f32;
+            } else {
+                *ap.arg::<*mut f64>() = value;
+            }
+        }
+        b'c' | b's' | b'[' => {
+            if spec.length != Length::Int {
+                return Err(Stop::Error(ErrorNumber::Inval));
+            }
+
+            let mut dst = if spec.suppress {
+                None
+            } else {
+                Some(ap.arg::<*mut u8>())
+            };
+
+            let (limit, is_terminated) = match spec.conversion {
+                b'c' => (spec.width.unwrap_or(1), false),
+                _ => (usize::MAX, true),
+            };
+            input.limit = cmp::min(input.limit, limit);
+
+            let mut len = 0;
+
+            while let Some(ch) = input.peek() {
+                let is_accepted = match spec.conversion {
+                    b'c' => true,
+                    b's' => !is_space(ch),
+                    _ => spec.scanset.contains(ch),
+                };
+
+                if !is_accepted {
+                    break;
+                }
+
+                input.advance();
+                len += 1;
+
+                if let Some(ptr) = dst.as_mut() {
+                    **ptr = ch;
+                    *ptr = ptr.add(1);
+                }
+            }
+
+            // like glibc, a %c that runs out of input early still counts
+            if len == 0 {
+                return Err(Stop::Matching);
+            }
+
+            if let (Some(ptr), true) = (dst, is_terminated) {
+                *ptr = b'\0';
+            }
+        }
+        _ => return Err(Stop::Error(ErrorNumber::Inval)),
+    }
+
+    Ok(())
+}
+
+unsafe fn store_integer(ap: &mut VaList, length: Length, value: u64) -> Result<(), ErrorNumber> {
+    match length {
+        Length::Char => *ap.arg::<*mut u8>() = value as u8,
+        Length::Short => *ap.arg::<*mut u16>() = value as u16,
+        Length::Int => *ap.arg::<*mut u32>() = value as u32,
+        // glibc takes %Ld to mean %lld
+        Length::Long
+        | Length::LongLong
+        | Length::IntMax
+        | Length::Size
+        | Length::PtrDiff
+        | Length::LongDouble => *ap.arg::<*mut u64>() = value,
+    }
+
+    Ok(())
+}
+
+struct Input<'s, S: Source> {
+    source: &'s mut S,
+    consumed: usize,
+    /// How many more bytes the current conversion may consume.
+    limit: usize,
+}
+
+impl<'s, S: Source> Input<'s, S> {
+    fn peek(&mut self) -> Option<u8> {
+        if self.limit == 0 {
+            None
+        } else {
+            self.source.peek()
+        }
+    }
+
+    fn advance(&mut self) {
+        self.source.advance();
+        self.consumed += 1;
+        self.limit -= 1;
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, is_space) {
+            self.advance();
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<bool, Stop> {
+        match self.peek() {
+            Some(ch) if ch == expected => {
+                self.advance();
+
+                Ok(false)
+            }
+            Some(_) => Err(Stop::Matching),
+            None => Err(Stop::Input),
+        }
+    }
+
+    /// Consumes `expected`, ignoring case.
+    fn expect_word(&mut self, expected: &[u8]) -> Result<(), Stop> {
+        for &expected_ch in expected {
+            match self.peek() {
+                Some(ch) if ch.to_ascii_lowercase() == expected_ch => self.advance(),
+                _ => return Err(Stop::Matching),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn scan_sign(&mut self) -> bool {
+        match self.peek() {
+            Some(b'-') => {
+                self.advance();
+
+                true
+            }
+            Some(b'+') => {
+                self.advance();
+
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Scans an integer like strtoul would, with a base of 0 meaning the
+    /// prefix decides. Returns its sign, magnitude, and whether the
+    /// magnitude overflowed.
+    fn scan_integer(&mut self, mut base: u32) -> Result<(bool, u64, bool), Stop> {
+        let is_negative = self.scan_sign();
+        let mut has_digits = false;
+
+        if (base == 0 || base == 16) && self.peek() == Some(b'0') {
+            self.advance();
+            has_digits = true;
+
+            if let Some(b'x') | Some(b'X') = self.peek() {
+                self.advance();
+                base = 16;
+            } else if base == 0 {
+                base = 8;
+            }
+        } else if base == 0 {
+            base = 10;
+        }
+
+        let mut magnitude = 0u64;
+        let mut is_overflow = false;
+
+        while let Some(digit) = self.peek().and_then(|ch| (ch as char).to_digit(base)) {
+            self.advance();
+            has_digits = true;
+
+            match magnitude
+                .checked_mul(base as u64)
+                .and_then(|m| m.checked_add(digit as u64))
+            {
+                Some(m) => magnitude = m,
+                None => is_overflow = true,
+            }
+        }
+
+        if !has_digits {
+            return Err(Stop::Matching);
+        }
+
+        Ok((is_negative, magnitude, is_overflow))
+    }
+
+    /// Scans a floating-point number like strtod would, correctly rounded to
+    /// `precision`.
+    fn scan_float(&mut self, precision: Precision) -> Result<f64, Stop> {
+        let is_negative = self.scan_sign();
+
+        let magnitude = match self.peek() {
+            Some(b'i') | Some(b'I') => {
+                self.expect_word(b"inf")?;
+
+                if let Some(b'i') | Some(b'I') = self.peek() {
+                    self.expect_word(b"inity")?;
+                }
+
+                f64::INFINITY
+            }
+            Some(b'n') | Some(b'N') => {
+                // like glibc, the optional "(n-char-sequence)" is left unread
+                self.expect_word(b"nan")?;
+
+                f64::NAN
+            }
+            _ => self.scan_finite(precision)?,
+        };
+
+        Ok(if is_negative { -magnitude } else { magnitude })
+    }
+
+    fn scan_finite(&mut self, precision: Precision) -> Result<f64, Stop> {
+        let mut radix = 10;
+        let mut has_digits = false;
+
+        if self.peek() == Some(b'0') {
+            self.advance();
+            has_digits = true;
+
+            if let Some(b'x') | Some(b'X') = self.peek() {
+                self.advance();
+                radix = 16;
+                has_digits = false;
+            }
+        }
+
+        // the number is 0.D * radix^point, with D in digits and leading zeros skipped
+        let mut digits = [0u8; MAX_SIGNIFICANT_DIGITS];
+        let mut len = 0;
+        let mut point: i64 = 0;
+        let mut is_truncated = false;
+        let mut has_point = false;
+
+        loop {
+            match self.peek() {
+                Some(b'.') if !has_point => has_point = true,
+                Some(ch) => match (ch as char).to_digit(radix) {
+                    Some(digit) => {
+                        has_digits = true;
+
+                        if len == 0 && digit == 0 {
+                            if has_point {
+                                point -= 1;
+                            }
+                        } else {
+                            if len < digits.len() {
+                                digits[len] = digit as u8;
+                                len += 1;
+                            } else {
+                                is_truncated = is_truncated || digit != 0;
+                            }
+
+                            if !has_point {
+                                point += 1;
+                            }
+                        }
+                    }
+                    None => break,
+                },
+                None => break,
+            }
+
+            self.advance();
+        }
+
+        if !has_digits {
+            return Err(Stop::Matching);
+        }
+
+        let exponent_char = if radix == 10 { b'e' } else { b'p' };
+
+        // like glibc, an exponent without any digits is ignored but still consumed
+        let exponent = if self.peek().map(|ch| ch.to_ascii_lowercase()) == Some(exponent_char) {
+            self.advance();
+            let is_negative = self.scan_sign();
+            let mut exponent: i64 = 0;
+
+            while let Some(digit) = self.peek().and_then(|ch| (ch as char).to_digit(10)) {
+                self.advance();
+                exponent = cmp::min(exponent * 10 + digit as i64, MAX_EXPONENT);
+            }
+
+            if is_negative {
+                -exponent
+            } else {
+                exponent
+            }
+        } else {
+            0
+        };
+
+        if radix == 10 {
+            return Ok(float::from_decimal(
+                &digits[..len],
+                point + exponent,
+                is_truncated,
+                precision,
+            ));
+        }
+
+        // 16 hex digits fill up a u64
+        let num_kept = cmp::min(len, 16);
+        let mantissa = digits[..num_kept]
+            .iter()
+            .fold(0u64, |acc, &d| (acc << 4) | d as u64);
+        let is_truncated = is_truncated || digits[num_kept..len].iter().any(|&d| d != 0);
+
+        Ok(float::from_binary(
+            mantissa,
+            4 * (point - num_kept as i64) + exponent,
+            is_truncated,
+            precision,
+        ))
+    }
+}
+
+/// One parsed conversion specification, i.e. everything after a '%'.
+struct Spec<'f> {
+    suppress: bool,
+    width: Option<usize>,
+    length: Length,
+    conversion: u8,
+    scanset: Scanset<'f>,
+}
+
+impl<'f> Spec<'f> {
+    /// Parses a conversion specification from the bytes following a '%'.
+    /// Returns the parsed specification and how many bytes of `spec` it
+    /// used.
+    fn parse(spec: &'f [u8]) -> Result<(Spec<'f>, usize), ErrorNumber> {
+        let mut i = 0;
+
+        let suppress = spec.get(i) == Some(&b'*');
+
+        if suppress {
+            i += 1;
+        }
+
+        let (width, width_len) = printf::parse_decimal(&spec[i..])?;
+        i += width_len;
+
+        let (length, length_len) = Length::parse(&spec[i..]);
+        i += length_len;
+
+        let conversion = *spec.get(i).ok_or(ErrorNumber::Inval)?;
+        i += 1;
+
+        let mut scanset = Scanset {
+            is_negated: false,
+            members: &[],
+        };
+
+        if conversion == b'[' {
+            if spec.get(i) == Some(&b'^') {
+                scanset.is_negated = true;
+                i += 1;
+            }
+
+            // a ']' right at the start is a member, not the end of the set
+            let start = i;
+            let first_member_len = if spec.get(i) == Some(&b']') { 1 } else { 0 };
+
+            let end = spec[start + first_member_len..]
+                .iter()
+                .position(|&ch| ch == b']')
+                .ok_or(ErrorNumber::Inval)?
+                + start
+                + first_member_len;
+
+            scanset.members = &spec[start..end];
+            i = end + 1;
+        }
+
+        let parsed = Spec {
+            suppress,
+            width: if width == 0 { None } else { Some(width) },
+            length,
+            conversion,
+            scanset,
+        };
+
+        Ok((parsed, i))
+    }
+}
+
+/// The set of bytes in a %[ conversion.
+struct Scanset<'f> {
+    is_negated: bool,
+    members: &'f [u8],
+}
+
+impl<'f> Scanset<'f> {
+    fn contains(&self, ch: u8) -> bool {
+        let mut i = 0;
+        let mut is_member = false;
+
+        while i < self.members.len() {
+            // a '-' at either end is just a member
+            if i + 2 < self.members.len() && self.members[i + 1] == b'-' {
+                is_member = is_member || (self.members[i]..=self.members[i + 2]).contains(&ch);
+                i += 3;
+            } else {
+                is_member = is_member || self.members[i] == ch;
+                i += 1;
+            }
+        }
+
+        is_member != self.is_negated
+    }
+}
+
+fn is_space(ch: u8) -> bool {
+    // not u8::is_ascii_whitespace, that doesn't include '\v'
+    matches!(ch, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r')
+}
@@ -13,9 +13,25 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{c_char, c_int, c_long, c_void, errno, internal, stddef::size_t, stdio, syscall};
-
-use core::{hint, mem, num::IntErrorKind, ptr, slice, str};
+mod environment;
+mod exit_handlers;
+
+use crate::{
+    c_char, c_int, c_long, c_void, errno,
+    fcntl::AT_FDCWD,
+    internal::{self, errno::ErrorNumber, sync::Mutex},
+    signal::{self, sigset_t, KernelSigaction, SIGABRT, SIG_DFL, SIG_UNBLOCK},
+    stddef::size_t,
+    stdio, string,
+    sys::auxv::{self, AT_SECURE},
+    syscall,
+    unistd::{self, PATH_MAX},
+};
+
+use core::{cmp, hint, mem, num::IntErrorKind, ptr, slice, str};
+
+use environment::Environment;
+use exit_handlers::{Handler, Handlers};
 
 #[link(name = "kns-rpmalloc", kind = "static")]
 extern "C" {
@@ -123,22 +139,390 @@ pub unsafe extern "C" fn aligned_alloc(alignment: size_t, size: size_t) -> *mut
     }
 }
 
+static AT_EXIT: Mutex<Handlers> = Mutex::new(Handlers::new());
+static AT_QUICK_EXIT: Mutex<Handlers> = Mutex::new(Handlers::new());
+
 #[no_mangle]
 pub unsafe extern "C" fn exit(status: c_int) -> ! {
-    if !stdio::STDIN.is_null() {
-        stdio::fclose(stdio::STDIN);
+    exit_handlers::run(&AT_EXIT, ptr::null_mut());
+    stdio::flush_for_exit();
+
+    internal::finalize();
+    sys::exit_group(status)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn quick_exit(status: c_int) -> ! {
+    exit_handlers::run(&AT_QUICK_EXIT, ptr::null_mut());
+
+    _Exit(status)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn _Exit(status: c_int) -> ! {
+    sys::exit_group(status)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn abort() -> ! {
+    let mut abrt = sigset_t::empty();
+    signal::sigaddset(&mut abrt, SIGABRT);
+
+    // a blocked SIGABRT would just sit there pending
+    signal::sys::rt_sigprocmask(SIG_UNBLOCK, &abrt, ptr::null_mut());
+    signal::raise(SIGABRT);
+
+    // it was ignored, or caught by a handler that returned. the default action can't be
+    let default = KernelSigaction {
+        handler: SIG_DFL,
+        ..KernelSigaction::default()
+    };
+    signal::sys::rt_sigaction(SIGABRT, &default, ptr::null_mut());
+    signal::sys::rt_sigprocmask(SIG_UNBLOCK, &abrt, ptr::null_mut());
+    signal::raise(SIGABRT);
+
+    sys::exit_group(127)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn atexit(function: extern "C" fn()) -> c_int {
+    register(&AT_EXIT, Handler::Plain(function))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn at_quick_exit(function: extern "C" fn()) -> c_int {
+    register(&AT_QUICK_EXIT, Handler::Plain(function))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn __cxa_atexit(
+    function: extern "C" fn(*mut c_void),
+    arg: *mut c_void,
+    dso_handle: *mut c_void,
+) -> c_int {
+    register(
+        &AT_EXIT,
+        Handler::WithArg {
+            func: function,
+            arg,
+            dso_handle,
+        },
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn __cxa_finalize(dso_handle: *mut c_void) {
+    exit_handlers::run(&AT_EXIT, dso_handle);
+}
+
+fn register(handlers: &Mutex<Handlers>, handler: Handler) -> c_int {
+    if let Err(e) = handlers.lock().push(handler) {
+        unsafe { *internal::errno() = e.into_int() };
+
+        return -1;
+    }
+
+    0
+}
+
+/// Holds the exit handler and environment locks across a fork until
+/// `unlock_after_fork`.
+pub(crate) fn lock_for_fork() {
+    mem::forget(AT_EXIT.lock());
+    mem::forget(AT_QUICK_EXIT.lock());
+    mem::forget(ENVIRONMENT.lock());
+}
+
+/// Must follow `lock_for_fork` in the same thread, in the parent and child
+/// alike.
+pub(crate) unsafe fn unlock_after_fork() {
+    ENVIRONMENT.force_unlock();
+    AT_QUICK_EXIT.force_unlock();
+    AT_EXIT.force_unlock();
+}
+
+#[no_mangle]
+pub static mut environ: *mut *mut c_char = ptr::null_mut();
+
+static ENVIRONMENT: Mutex<Environment> = Mutex::new(Environment::new());
+
+#[no_mangle]
+pub unsafe extern "C" fn getenv(name: *const c_char) -> *mut c_char {
+    match variable_name(name) {
+        Some(name) => ENVIRONMENT.lock().get(name),
+        None => ptr::null_mut(),
     }
+}
 
-    if !stdio::STDOUT.is_null() {
-        stdio::fclose(stdio::STDOUT);
+#[no_mangle]
+pub unsafe extern "C" fn secure_getenv(name: *const c_char) -> *mut c_char {
+    // set for setuid and setgid programs, which shouldn't trust their environment
+    if auxv::getauxval(AT_SECURE) != 0 {
+        return ptr::null_mut();
     }
 
-    if !stdio::STDERR.is_null() {
-        stdio::fclose(stdio::STDERR);
+    getenv(name)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn setenv(
+    name: *const c_char,
+    value: *const c_char,
+    overwrite: c_int,
+) -> c_int {
+    let name = match variable_name(name) {
+        Some(n) => n,
+        None => {
+            *internal::errno() = errno::EINVAL;
+
+            return -1;
+        }
+    };
+
+    let mut environment = ENVIRONMENT.lock();
+
+    if overwrite == 0 && !environment.get(name).is_null() {
+        return 0;
     }
 
-    internal::finalize();
-    sys::exit_group(status)
+    let value_len = string::strlen(value) as usize;
+    let entry = malloc((name.len() + value_len + 2) as size_t) as *mut c_char;
+
+    if entry.is_null() {
+        return -1;
+    }
+
+    ptr::copy_nonoverlapping(name.as_ptr() as *const c_char, entry, name.len());
+    *entry.add(name.len()) = b'=' as c_char;
+    ptr::copy_nonoverlapping(value, entry.add(name.len() + 1), value_len + 1);
+
+    if let Err(e) = environment.put(entry, name) {
+        free(entry as *mut c_void);
+        *internal::errno() = e.into_int();
+
+        return -1;
+    }
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn unsetenv(name: *const c_char) -> c_int {
+    let name = match variable_name(name) {
+        Some(n) => n,
+        None => {
+            *internal::errno() = errno::EINVAL;
+
+            return -1;
+        }
+    };
+
+    if let Err(e) = ENVIRONMENT.lock().remove(name) {
+        *internal::errno() = e.into_int();
+
+        return -1;
+    }
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn putenv(string: *mut c_char) -> c_int {
+    let entry = slice::from_raw_parts(string as *const u8, string::strlen(string) as usize);
+
+    // string itself becomes part of the environment, so changing it changes the variable
+    let result = match entry.iter().position(|&c| c == b'=') {
+        Some(0) => Err(ErrorNumber::Inval),
+        Some(name_len) => ENVIRONMENT.lock().put(string, &entry[..name_len]),
+        // like glibc, a string without a value removes the variable
+        None => ENVIRONMENT.lock().remove(entry),
+    };
+
+    if let Err(e) = result {
+        *internal::errno() = e.into_int();
+
+        return -1;
+    }
+
+    0
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn clearenv() -> c_int {
+    ENVIRONMENT.lock().clear();
+
+    0
+}
+
+// the name of an environment variable can't be empty or contain '='
+unsafe fn variable_name<'a>(name: *const c_char) -> Option<&'a [u8]> {
+    if name.is_null() {
+        return None;
+    }
+
+    let name = slice::from_raw_parts(name as *const u8, string::strlen(name) as usize);
+
+    if name.is_empty() || name.contains(&b'=') {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+// as many symlinks as Linux will follow in one path
+const MAX_SYMLINKS: usize = 40;
+
+#[no_mangle]
+pub unsafe extern "C" fn realpath(path: *const c_char, resolved_path: *mut c_char) -> *mut c_char {
+    if path.is_null() {
+        *internal::errno() = errno::EINVAL;
+
+        return ptr::null_mut();
+    }
+
+    let mut resolved = [0; PATH_MAX];
+
+    let len = match resolve(path, &mut resolved) {
+        Ok(len) => len,
+        Err(e) => {
+            *internal::errno() = e;
+
+            return ptr::null_mut();
+        }
+    };
+
+    let resolved_path = if resolved_path.is_null() {
+        malloc((len + 1) as size_t) as *mut c_char
+    } else {
+        resolved_path
+    };
+
+    if !resolved_path.is_null() {
+        ptr::copy_nonoverlapping(resolved.as_ptr() as *const c_char, resolved_path, len + 1);
+    }
+
+    resolved_path
+}
+
+/// Writes the absolute form of `path`, with no symlinks or "." or ".."
+/// components, to `resolved` and returns its length. Works a component at
+/// a time rather than asking /proc, which may not be mounted.
+unsafe fn resolve(path: *const c_char, resolved: &mut [u8; PATH_MAX]) -> Result<usize, c_int> {
+    let path = slice::from_raw_parts(path as *const u8, string::strlen(path) as usize);
+
+    if path.is_empty() {
+        return Err(errno::ENOENT);
+    } else if path.len() >= PATH_MAX {
+        return Err(errno::ENAMETOOLONG);
+    }
+
+    // what's left to resolve is rest[start..], so symlink targets can be put in front of it
+    let mut rest = [0; PATH_MAX];
+    let mut start = PATH_MAX - path.len();
+    rest[start..].copy_from_slice(path);
+
+    let mut len = if path[0] == b'/' {
+        resolved[0] = b'/';
+
+        1
+    } else {
+        let result = unistd::sys::getcwd(resolved.as_mut_ptr() as *mut c_char, PATH_MAX as size_t);
+
+        if result < 0 {
+            return Err(-result as c_int);
+        } else if resolved[0] != b'/' {
+            return Err(errno::ENOENT);
+        }
+
+        // getcwd counts the null terminator
+        result as usize - 1
+    };
+
+    let mut num_symlinks = 0;
+
+    loop {
+        start += rest[start..].iter().take_while(|&&c| c == b'/').count();
+
+        if start == PATH_MAX {
+            break;
+        }
+
+        let end = rest[start..]
+            .iter()
+            .position(|&c| c == b'/')
+            .map_or(PATH_MAX, |i| start + i);
+        let component = &rest[start..end];
+
+        if component == b"." {
+            start = end;
+
+            continue;
+        } else if component == b".." {
+            // everything in resolved is already a real directory, so this is safe to do by hand
+            len = resolved[..len]
+                .iter()
+                .rposition(|&c| c == b'/')
+                .map_or(1, |i| cmp::max(i, 1));
+            resolved[len] = 0;
+            start = end;
+
+            continue;
+        }
+
+        let parent_len = len;
+        let separator = if len > 1 { 1 } else { 0 };
+
+        if len + separator + component.len() >= PATH_MAX {
+            return Err(errno::ENAMETOOLONG);
+        }
+
+        if separator != 0 {
+            resolved[len] = b'/';
+            len += 1;
+        }
+
+        resolved[len..len + component.len()].copy_from_slice(component);
+        len += component.len();
+        resolved[len] = 0;
+        start = end;
+
+        // read the target into the front of rest, then slide it up against what's left
+        let target_len = unistd::sys::readlinkat(
+            AT_FDCWD,
+            resolved.as_ptr() as *const c_char,
+            rest.as_mut_ptr() as *mut c_char,
+            start as size_t,
+        );
+
+        if target_len == -(errno::EINVAL as isize) {
+            // not a symlink
+            continue;
+        } else if target_len < 0 {
+            return Err(-target_len as c_int);
+        }
+
+        let target_len = target_len as usize;
+
+        // there has to be room for a slash between the target and the rest of the path
+        if target_len >= start {
+            return Err(errno::ENAMETOOLONG);
+        }
+
+        num_symlinks += 1;
+
+        if num_symlinks > MAX_SYMLINKS {
+            return Err(errno::ELOOP);
+        }
+
+        rest.copy_within(0..target_len, start - target_len - 1);
+        rest[start - 1] = b'/';
+        start -= target_len + 1;
+
+        len = if rest[start] == b'/' { 1 } else { parent_len };
+        resolved[len] = 0;
+    }
+
+    Ok(len)
 }
 
 #[no_mangle]
@@ -215,6 +599,57 @@ pub unsafe extern "C" fn strtol(
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn strfromd(
+    str: *mut c_char,
+    n: size_t,
+    format: *const c_char,
+    fp: f64,
+) -> c_int {
+    if format.is_null() || !is_strfrom_format(format) {
+        *internal::errno() = errno::EINVAL;
+
+        return -1;
+    }
+
+    stdio::snprintf(str, n, format, fp)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strfromf(
+    str: *mut c_char,
+    n: size_t,
+    format: *const c_char,
+    fp: f32,
+) -> c_int {
+    strfromd(str, n, format, fp as f64)
+}
+
+/// strfrom* only accept "%[.precision]conversion", with none of printf's
+/// flags or widths.
+unsafe fn is_strfrom_format(format: *const c_char) -> bool {
+    let format = slice::from_raw_parts(format as *const u8, string::strlen(format) as usize);
+
+    let rest = match format.split_first() {
+        Some((b'%', rest)) => rest,
+        _ => return false,
+    };
+
+    let rest = match rest.split_first() {
+        Some((b'.', rest)) => {
+            let num_digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+
+            &rest[num_digits..]
+        }
+        _ => rest,
+    };
+
+    matches!(
+        rest,
+        [b'a'] | [b'A'] | [b'e'] | [b'E'] | [b'f'] | [b'F'] | [b'g'] | [b'G']
+    )
+}
+
 pub(crate) mod sys {
     use super::*;
 
@@ -0,0 +1,161 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::environ;
+
+use crate::{c_char, c_void, internal::errno::ErrorNumber, stddef::size_t, stdlib, string};
+
+use core::{cmp, mem, ptr, slice};
+
+// environ starts out pointing at the array the kernel put on the stack, and is copied before it's
+// first modified. strings are never freed, since getenv's callers may still be using them
+pub(super) struct Environment {
+    // the array we allocated, which is only ours to change while environ still points at it
+    owned: *mut *mut c_char,
+    capacity: usize,
+}
+
+// only ever touched with the surrounding Mutex locked
+unsafe impl Send for Environment {}
+
+impl Environment {
+    pub(super) const fn new() -> Environment {
+        Environment {
+            owned: ptr::null_mut(),
+            capacity: 0,
+        }
+    }
+
+    /// Returns the value of `name`, or null if it isn't set.
+    pub(super) unsafe fn get(&self, name: &[u8]) -> *mut c_char {
+        match find(name) {
+            Some(i) => (*environ.add(i)).add(name.len() + 1),
+            None => ptr::null_mut(),
+        }
+    }
+
+    /// Adds `entry`, which is `name` followed by '=' and a value, replacing
+    /// any entry that already has that name.
+    pub(super) unsafe fn put(
+        &mut self,
+        entry: *mut c_char,
+        name: &[u8],
+    ) -> Result<(), ErrorNumber> {
+        match find(name) {
+            Some(i) => {
+                self.make_owned(0)?;
+                *environ.add(i) = entry;
+            }
+            None => {
+                let len = self.make_owned(1)?;
+                *environ.add(len) = entry;
+                *environ.add(len + 1) = ptr::null_mut();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes every entry named `name`.
+    pub(super) unsafe fn remove(&mut self, name: &[u8]) -> Result<(), ErrorNumber> {
+        if find(name).is_none() {
+            return Ok(());
+        }
+
+        let len = self.make_owned(0)?;
+        let mut kept = 0;
+
+        for i in 0..len {
+            let entry = *environ.add(i);
+
+            if !is_named(entry, name) {
+                *environ.add(kept) = entry;
+                kept += 1;
+            }
+        }
+
+        *environ.add(kept) = ptr::null_mut();
+
+        Ok(())
+    }
+
+    pub(super) unsafe fn clear(&mut self) {
+        if environ == self.owned {
+            stdlib::free(self.owned as *mut c_void);
+        }
+
+        self.owned = ptr::null_mut();
+        self.capacity = 0;
+        environ = ptr::null_mut();
+    }
+
+    // makes sure that environ is ours and has room for extra more entries, returning its length
+    unsafe fn make_owned(&mut self, extra: usize) -> Result<usize, ErrorNumber> {
+        let len = len();
+
+        if environ == self.owned && len + extra < self.capacity {
+            return Ok(len);
+        }
+
+        let capacity = cmp::max((len + extra + 1) * 2, 16);
+        let array = stdlib::malloc((capacity * mem::size_of::<*mut c_char>()) as size_t)
+            as *mut *mut c_char;
+
+        if array.is_null() {
+            return Err(ErrorNumber::Nomem);
+        }
+
+        if !environ.is_null() {
+            ptr::copy_nonoverlapping(environ, array, len);
+        }
+
+        *array.add(len) = ptr::null_mut();
+
+        // if the program replaced environ, it might still be using our old array
+        if environ == self.owned {
+            stdlib::free(self.owned as *mut c_void);
+        }
+
+        self.owned = array;
+        self.capacity = capacity;
+        environ = array;
+
+        Ok(len)
+    }
+}
+
+unsafe fn len() -> usize {
+    if environ.is_null() {
+        return 0;
+    }
+
+    let mut len = 0;
+
+    while !(*environ.add(len)).is_null() {
+        len += 1;
+    }
+
+    len
+}
+
+unsafe fn find(name: &[u8]) -> Option<usize> {
+    (0..len()).find(|&i| is_named(*environ.add(i), name))
+}
+
+unsafe fn is_named(entry: *const c_char, name: &[u8]) -> bool {
+    let entry = slice::from_raw_parts(entry as *const u8, string::strlen(entry) as usize);
+
+    entry.len() > name.len() && entry.starts_with(name) && entry[name.len()] == b'='
+}
@@ -0,0 +1,173 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_void,
+    internal::{alloc::Box, errno::ErrorNumber, sync::Mutex},
+};
+
+use core::ptr;
+
+const BLOCK_LEN: usize = 32;
+
+// handlers are stored in fixed-size blocks so that the first 32 registrations never allocate. every
+// block but the last is full, so the newest handler is always at the end of the last block
+pub(super) struct Handlers {
+    first: Block,
+    last: *mut Block,
+}
+
+// only ever touched with the surrounding Mutex locked
+unsafe impl Send for Handlers {}
+
+struct Block {
+    entries: [Option<Handler>; BLOCK_LEN],
+    len: usize,
+    prev: *mut Block,
+}
+
+#[derive(Copy, Clone)]
+pub(super) enum Handler {
+    Plain(extern "C" fn()),
+    WithArg {
+        func: extern "C" fn(*mut c_void),
+        arg: *mut c_void,
+        dso_handle: *mut c_void,
+    },
+}
+
+impl Handler {
+    fn call(self) {
+        match self {
+            Handler::Plain(func) => func(),
+            Handler::WithArg { func, arg, .. } => func(arg),
+        }
+    }
+
+    fn is_from(&self, dso_handle: *mut c_void) -> bool {
+        match *self {
+            Handler::Plain(_) => false,
+            Handler::WithArg { dso_handle: d, .. } => d == dso_handle,
+        }
+    }
+}
+
+impl Handlers {
+    pub(super) const fn new() -> Handlers {
+        Handlers {
+            first: Block {
+                entries: [None; BLOCK_LEN],
+                len: 0,
+                prev: ptr::null_mut(),
+            },
+            last: ptr::null_mut(),
+        }
+    }
+
+    fn last_block(&mut self) -> &mut Block {
+        match unsafe { self.last.as_mut() } {
+            Some(last) => last,
+            None => &mut self.first,
+        }
+    }
+
+    pub(super) fn push(&mut self, handler: Handler) -> Result<(), ErrorNumber> {
+        if self.last_block().len == BLOCK_LEN {
+            let block = Box::new(Block {
+                entries: [None; BLOCK_LEN],
+                len: 0,
+                prev: self.last,
+            })
+            .map_err(|_| ErrorNumber::Nomem)?;
+
+            self.last = Box::into_raw(block);
+        }
+
+        let block = self.last_block();
+        block.entries[block.len] = Some(handler);
+        block.len += 1;
+
+        Ok(())
+    }
+
+    // the oldest heap block's prev is null rather than pointing at first, so that trim can tell
+    // when it has freed them all
+    fn block_before(&mut self, block: *mut Block) -> *mut Block {
+        let prev = unsafe { (*block).prev };
+
+        if prev.is_null() && !ptr::eq(block, &self.first) {
+            &mut self.first
+        } else {
+            prev
+        }
+    }
+
+    // takes the newest handler that matches dso_handle, or the newest of all of them if it's null
+    fn take_newest(&mut self, dso_handle: *mut c_void) -> Option<Handler> {
+        let mut block = self.last_block() as *mut Block;
+        let mut found = None;
+
+        while let Some(b) = unsafe { block.as_mut() } {
+            let slot = b.entries[..b.len].iter_mut().rev().find(|e| match e {
+                Some(h) => dso_handle.is_null() || h.is_from(dso_handle),
+                None => false,
+            });
+
+            if let Some(slot) = slot {
+                found = slot.take();
+
+                break;
+            }
+
+            block = self.block_before(b);
+        }
+
+        self.trim();
+
+        found
+    }
+
+    // drops taken entries from the end, so that exit doesn't have to step over them again
+    fn trim(&mut self) {
+        loop {
+            let block = self.last_block();
+
+            while block.len > 0 && block.entries[block.len - 1].is_none() {
+                block.len -= 1;
+            }
+
+            if block.len > 0 || self.last.is_null() {
+                break;
+            }
+
+            let emptied = unsafe { Box::from_raw(self.last) };
+            self.last = emptied.prev;
+        }
+    }
+}
+
+/// Calls, newest first, each handler registered for `dso_handle` (or every
+/// handler if it's null). Handlers may register more handlers; those are
+/// called next. The lock is not held while a handler runs.
+pub(super) fn run(handlers: &Mutex<Handlers>, dso_handle: *mut c_void) {
+    loop {
+        let handler = handlers.lock().take_newest(dso_handle);
+
+        match handler {
+            Some(h) => h.call(),
+            None => break,
+        }
+    }
+}
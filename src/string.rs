@@ -13,9 +13,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{c_char, c_int, c_void, stddef::size_t};
+mod simd;
+mod two_way;
 
-use core::{cmp::Ordering, ptr, slice};
+use crate::{
+    c_char, c_int, c_void, errno,
+    internal::{self, errno::ErrorNumber},
+    signal,
+    stddef::size_t,
+    stdio, stdlib,
+};
+
+use core::{ptr, slice};
 
 #[link(name = "kns-asm", kind = "static")]
 extern "C" {
@@ -24,16 +33,113 @@ extern "C" {
     pub fn memset(s: *mut c_void, c: c_int, n: size_t) -> *mut c_void;
 }
 
+/// Picks the fastest versions of the vectorized routines this processor
+/// supports.
+pub(crate) unsafe fn initialize() {
+    simd::initialize();
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn memcmp(s1: *const c_void, s2: *const c_void, n: size_t) -> c_int {
-    let lhs = slice::from_raw_parts(s1 as *const u8, n as usize);
-    let rhs = slice::from_raw_parts(s2 as *const u8, n as usize);
-
-    for (l, r) in lhs.iter().cloned().zip(rhs.iter().cloned()) {
-        match l.cmp(&r) {
-            Ordering::Less => return -1,
-            Ordering::Greater => return 1,
-            _ => (),
+    (simd::get().memcmp)(s1 as *const u8, s2 as *const u8, n as usize)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strstr(haystack: *const c_char, needle: *const c_char) -> *mut c_char {
+    if haystack.is_null() || needle.is_null() {
+        return ptr::null_mut();
+    }
+
+    memmem(
+        haystack as *const c_void,
+        strlen(haystack),
+        needle as *const c_void,
+        strlen(needle),
+    ) as *mut c_char
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strlen(s: *const c_char) -> size_t {
+    if s.is_null() {
+        return 0;
+    }
+
+    (simd::get().strlen)(s as *const u8) as size_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strnlen(s: *const c_char, maxlen: size_t) -> size_t {
+    let mut len = 0;
+
+    while len < maxlen && *s.add(len as usize) != 0 {
+        len += 1;
+    }
+
+    len
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn memchr(s: *const c_void, c: c_int, n: size_t) -> *mut c_void {
+    (simd::get().memchr)(s as *const u8, c as u8, n as usize) as *mut c_void
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn memrchr(s: *const c_void, c: c_int, n: size_t) -> *mut c_void {
+    let bytes = slice::from_raw_parts(s as *const u8, n as usize);
+
+    match bytes.iter().rposition(|&b| b == c as u8) {
+        Some(i) => (s as *mut u8).add(i) as *mut c_void,
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn memmem(
+    haystack: *const c_void,
+    haystacklen: size_t,
+    needle: *const c_void,
+    needlelen: size_t,
+) -> *mut c_void {
+    if needlelen == 0 {
+        return haystack as *mut c_void;
+    }
+
+    let needle_bytes = slice::from_raw_parts(needle as *const u8, needlelen as usize);
+
+    // skip straight to the first place the needle could start
+    let start = memchr(haystack, needle_bytes[0] as c_int, haystacklen) as *const u8;
+
+    if start.is_null() || needlelen == 1 {
+        return start as *mut c_void;
+    }
+
+    let skipped = start as usize - haystack as usize;
+    let haystack_bytes = slice::from_raw_parts(start, haystacklen as usize - skipped);
+
+    match two_way::find(haystack_bytes, needle_bytes) {
+        Some(i) => start.add(i) as *mut c_void,
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn mempcpy(dest: *mut c_void, src: *const c_void, n: size_t) -> *mut c_void {
+    (memcpy(dest, src, n) as *mut u8).add(n as usize) as *mut c_void
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strcmp(s1: *const c_char, s2: *const c_char) -> c_int {
+    (simd::get().strcmp)(s1 as *const u8, s2 as *const u8)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strncmp(s1: *const c_char, s2: *const c_char, n: size_t) -> c_int {
+    for i in 0..n as usize {
+        let l = *s1.add(i) as u8;
+        let r = *s2.add(i) as u8;
+
+        if l != r || l == 0 {
+            return l as c_int - r as c_int;
         }
     }
 
@@ -41,40 +147,410 @@ pub unsafe extern "C" fn memcmp(s1: *const c_void, s2: *const c_void, n: size_t)
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn strstr(mut haystack: *const c_char, needle: *const c_char) -> *mut c_char {
-    if haystack.is_null() || needle.is_null() {
-        return ptr::null_mut();
+pub unsafe extern "C" fn strchr(s: *const c_char, c: c_int) -> *mut c_char {
+    (simd::get().strchr)(s as *const u8, c as u8) as *mut c_char
+}
+
+/// Like `strchr`, but returns a pointer to the terminator instead of null
+/// if `c` isn't in `s`.
+#[no_mangle]
+pub unsafe extern "C" fn strchrnul(s: *const c_char, c: c_int) -> *mut c_char {
+    let found = strchr(s, c);
+
+    if found.is_null() {
+        s.add(strlen(s) as usize) as *mut c_char
+    } else {
+        found
     }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strrchr(mut s: *const c_char, c: c_int) -> *mut c_char {
+    let c = c as c_char;
+    let mut last = ptr::null();
 
-    let mut needle_next = needle;
-    let mut haystack_start = haystack;
+    loop {
+        if *s == c {
+            last = s;
+        }
 
-    while *haystack != 0 && *needle_next != 0 {
-        if *needle_next == *haystack {
-            needle_next = needle.add(1);
-            haystack = haystack.add(1);
-        } else {
-            haystack_start = haystack_start.add(1);
-            haystack = haystack_start;
-            needle_next = needle;
+        if *s == 0 {
+            return last as *mut c_char;
         }
+
+        s = s.add(1);
     }
+}
 
-    if *needle_next == 0 {
-        haystack_start as *mut c_char
-    } else {
+#[no_mangle]
+pub unsafe extern "C" fn strcpy(dest: *mut c_char, src: *const c_char) -> *mut c_char {
+    stpcpy(dest, src);
+
+    dest
+}
+
+/// Like `strcpy`, but returns a pointer to the terminator it wrote.
+#[no_mangle]
+pub unsafe extern "C" fn stpcpy(dest: *mut c_char, src: *const c_char) -> *mut c_char {
+    let len = strlen(src) as usize;
+    ptr::copy_nonoverlapping(src, dest, len + 1);
+
+    dest.add(len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strncpy(dest: *mut c_char, src: *const c_char, n: size_t) -> *mut c_char {
+    stpncpy(dest, src, n);
+
+    dest
+}
+
+/// Like `strncpy`, but returns a pointer to the first terminator it wrote,
+/// or `dest + n` if it wrote none.
+#[no_mangle]
+pub unsafe extern "C" fn stpncpy(dest: *mut c_char, src: *const c_char, n: size_t) -> *mut c_char {
+    let len = strnlen(src, n);
+    ptr::copy_nonoverlapping(src, dest, len as usize);
+    ptr::write_bytes(dest.add(len as usize), 0, (n - len) as usize);
+
+    dest.add(len as usize)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strcat(dest: *mut c_char, src: *const c_char) -> *mut c_char {
+    stpcpy(dest.add(strlen(dest) as usize), src);
+
+    dest
+}
+
+/// Appends at most `n` characters of `src` to `dest`, then a terminator.
+#[no_mangle]
+pub unsafe extern "C" fn strncat(dest: *mut c_char, src: *const c_char, n: size_t) -> *mut c_char {
+    let end = dest.add(strlen(dest) as usize);
+    let len = strnlen(src, n) as usize;
+    ptr::copy_nonoverlapping(src, end, len);
+    *end.add(len) = 0;
+
+    dest
+}
+
+/// Copies as much of `src` as fits in `size` bytes, always terminating
+/// `dst` unless `size` is 0, and returns the length of `src`.
+#[no_mangle]
+pub unsafe extern "C" fn strlcpy(dst: *mut c_char, src: *const c_char, size: size_t) -> size_t {
+    let len = strlen(src);
+
+    if size > 0 {
+        let copied = len.min(size - 1) as usize;
+        ptr::copy_nonoverlapping(src, dst, copied);
+        *dst.add(copied) = 0;
+    }
+
+    len
+}
+
+/// Appends as much of `src` as fits in `dst`, which holds `size` bytes, and
+/// returns the length of the string it tried to make.
+#[no_mangle]
+pub unsafe extern "C" fn strlcat(dst: *mut c_char, src: *const c_char, size: size_t) -> size_t {
+    let dst_len = strnlen(dst, size);
+
+    if dst_len == size {
+        return size + strlen(src);
+    }
+
+    dst_len + strlcpy(dst.add(dst_len as usize), src, size - dst_len)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strdup(s: *const c_char) -> *mut c_char {
+    strndup(s, size_t::MAX)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strndup(s: *const c_char, n: size_t) -> *mut c_char {
+    let len = strnlen(s, n) as usize;
+    let copy = stdlib::malloc((len + 1) as size_t) as *mut c_char;
+
+    if !copy.is_null() {
+        ptr::copy_nonoverlapping(s, copy, len);
+        *copy.add(len) = 0;
+    }
+
+    copy
+}
+
+/// The set of bytes in a nul-terminated string.
+struct ByteSet([bool; 256]);
+
+impl ByteSet {
+    unsafe fn new(mut chars: *const c_char) -> ByteSet {
+        let mut set = ByteSet([false; 256]);
+
+        while *chars != 0 {
+            set.0[*chars as u8 as usize] = true;
+            chars = chars.add(1);
+        }
+
+        set
+    }
+
+    fn contains(&self, c: c_char) -> bool {
+        self.0[c as u8 as usize]
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strspn(s: *const c_char, accept: *const c_char) -> size_t {
+    let accept = ByteSet::new(accept);
+    let mut len = 0;
+
+    while *s.add(len) != 0 && accept.contains(*s.add(len)) {
+        len += 1;
+    }
+
+    len as size_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strcspn(s: *const c_char, reject: *const c_char) -> size_t {
+    let reject = ByteSet::new(reject);
+    let mut len = 0;
+
+    while *s.add(len) != 0 && !reject.contains(*s.add(len)) {
+        len += 1;
+    }
+
+    len as size_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strpbrk(s: *const c_char, accept: *const c_char) -> *mut c_char {
+    let found = s.add(strcspn(s, accept) as usize);
+
+    if *found == 0 {
         ptr::null_mut()
+    } else {
+        found as *mut c_char
     }
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn strlen(s: *const c_char) -> size_t {
+pub unsafe extern "C" fn strtok(s: *mut c_char, delim: *const c_char) -> *mut c_char {
+    static mut SAVEPTR: *mut c_char = ptr::null_mut();
+
+    strtok_r(s, delim, &mut SAVEPTR)
+}
+
+/// Returns the next token in `s`, or in the string left over from the last
+/// call if `s` is null, skipping runs of delimiters.
+#[no_mangle]
+pub unsafe extern "C" fn strtok_r(
+    s: *mut c_char,
+    delim: *const c_char,
+    saveptr: *mut *mut c_char,
+) -> *mut c_char {
+    let mut s = if s.is_null() { *saveptr } else { s };
+
     if s.is_null() {
-        return 0;
+        return ptr::null_mut();
+    }
+
+    s = s.add(strspn(s, delim) as usize);
+
+    if *s == 0 {
+        *saveptr = s;
+
+        return ptr::null_mut();
+    }
+
+    let end = s.add(strcspn(s, delim) as usize);
+
+    if *end == 0 {
+        *saveptr = end;
+    } else {
+        *end = 0;
+        *saveptr = end.add(1);
+    }
+
+    s
+}
+
+/// Splits off the part of `*stringp` up to the first delimiter, which unlike
+/// `strtok` means empty fields come back as empty strings.
+#[no_mangle]
+pub unsafe extern "C" fn strsep(stringp: *mut *mut c_char, delim: *const c_char) -> *mut c_char {
+    let s = *stringp;
+
+    if s.is_null() {
+        return ptr::null_mut();
+    }
+
+    let end = s.add(strcspn(s, delim) as usize);
+
+    if *end == 0 {
+        *stringp = ptr::null_mut();
+    } else {
+        *end = 0;
+        *stringp = end.add(1);
+    }
+
+    s
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strsignal(sig: c_int) -> *mut c_char {
+    let description = match sig {
+        signal::SIGHUP => "Hangup\0",
+        signal::SIGINT => "Interrupt\0",
+        signal::SIGQUIT => "Quit\0",
+        signal::SIGILL => "Illegal instruction\0",
+        signal::SIGTRAP => "Trace/breakpoint trap\0",
+        signal::SIGABRT => "Aborted\0",
+        signal::SIGBUS => "Bus error\0",
+        signal::SIGFPE => "Floating point exception\0",
+        signal::SIGKILL => "Killed\0",
+        signal::SIGUSR1 => "User defined signal 1\0",
+        signal::SIGSEGV => "Segmentation fault\0",
+        signal::SIGUSR2 => "User defined signal 2\0",
+        signal::SIGPIPE => "Broken pipe\0",
+        signal::SIGALRM => "Alarm clock\0",
+        signal::SIGTERM => "Terminated\0",
+        signal::SIGSTKFLT => "Stack fault\0",
+        signal::SIGCHLD => "Child exited\0",
+        signal::SIGCONT => "Continued\0",
+        signal::SIGSTOP => "Stopped (signal)\0",
+        signal::SIGTSTP => "Stopped\0",
+        signal::SIGTTIN => "Stopped (tty input)\0",
+        signal::SIGTTOU => "Stopped (tty output)\0",
+        signal::SIGURG => "Urgent I/O condition\0",
+        signal::SIGXCPU => "CPU time limit exceeded\0",
+        signal::SIGXFSZ => "File size limit exceeded\0",
+        signal::SIGVTALRM => "Virtual timer expired\0",
+        signal::SIGPROF => "Profiling timer expired\0",
+        signal::SIGWINCH => "Window changed\0",
+        signal::SIGIO => "I/O possible\0",
+        signal::SIGPWR => "Power failure\0",
+        signal::SIGSYS => "Bad system call\0",
+        _ => {
+            let buf = &mut internal::tcb().strsignal_buf;
+            let (format, number): (&[u8], c_int) =
+                if (signal::SIGRTMIN..=signal::SIGRTMAX).contains(&sig) {
+                    (b"Real-time signal %d\0", sig - signal::SIGRTMIN)
+                } else {
+                    (b"Unknown signal %d\0", sig)
+                };
+
+            stdio::snprintf(
+                buf.as_mut_ptr(),
+                buf.len() as size_t,
+                format.as_ptr() as *const c_char,
+                number,
+            );
+
+            return buf.as_mut_ptr();
+        }
+    };
+
+    description.as_ptr() as *mut c_char
+}
+
+/// Returns the nul-terminated message for `errnum`, if it has one.
+fn error_message(errnum: c_int) -> Option<&'static str> {
+    if errnum == 0 {
+        Some("Success\0")
+    } else {
+        ErrorNumber::from_int(errnum).message()
+    }
+}
+
+unsafe fn format_unknown_error(errnum: c_int, buf: *mut c_char, buflen: size_t) -> c_int {
+    stdio::snprintf(
+        buf,
+        buflen,
+        b"Unknown error %d\0".as_ptr() as *const c_char,
+        errnum,
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn strerror(errnum: c_int) -> *mut c_char {
+    match error_message(errnum) {
+        Some(message) => message.as_ptr() as *mut c_char,
+        None => {
+            let buf = &mut internal::tcb().strerror_buf;
+            format_unknown_error(errnum, buf.as_mut_ptr(), buf.len() as size_t);
+
+            buf.as_mut_ptr()
+        }
+    }
+}
+
+/// The GNU strerror_r, which returns either a static string or `buf`.
+#[no_mangle]
+pub unsafe extern "C" fn strerror_r(
+    errnum: c_int,
+    buf: *mut c_char,
+    buflen: size_t,
+) -> *mut c_char {
+    match error_message(errnum) {
+        Some(message) => message.as_ptr() as *mut c_char,
+        None => {
+            format_unknown_error(errnum, buf, buflen);
+
+            buf
+        }
+    }
+}
+
+/// The XSI strerror_r, which always copies into `buf` and returns an error
+/// number: `EINVAL` if `errnum` is unknown or `ERANGE` if `buf` is too
+/// small, in which case it holds as much of the message as fits.
+#[no_mangle]
+pub unsafe extern "C" fn __xpg_strerror_r(
+    errnum: c_int,
+    buf: *mut c_char,
+    buflen: size_t,
+) -> c_int {
+    let message = match error_message(errnum) {
+        Some(message) => message.as_bytes(),
+        None => {
+            format_unknown_error(errnum, buf, buflen);
+
+            return errno::EINVAL;
+        }
+    };
+
+    if buflen == 0 {
+        return errno::ERANGE;
     }
 
-    // oh boy
-    let as_slice = slice::from_raw_parts(s as *mut u8, usize::MAX);
+    let len = message.len().min(buflen as usize);
+    ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, buf, len);
+    *buf.add(len - 1) = 0;
+
+    if len < message.len() {
+        errno::ERANGE
+    } else {
+        0
+    }
+}
 
-    as_slice.iter().cloned().position(|ch| ch == 0).unwrap() as size_t
+/// Returns the name of the constant for `errnum`, like `"ENOENT"`, or null
+/// if it has none.
+#[no_mangle]
+pub unsafe extern "C" fn strerrorname_np(errnum: c_int) -> *const c_char {
+    let name = if errnum == 0 {
+        Some("0\0")
+    } else {
+        ErrorNumber::from_int(errnum).name()
+    };
+
+    name.map_or(ptr::null(), |n| n.as_ptr() as *const c_char)
+}
+
+/// Returns the message for `errnum`, or null if it has none.
+#[no_mangle]
+pub unsafe extern "C" fn strerrordesc_np(errnum: c_int) -> *const c_char {
+    error_message(errnum).map_or(ptr::null(), |m| m.as_ptr() as *const c_char)
 }
@@ -0,0 +1,291 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::c_int;
+
+use core::{arch::x86_64::*, mem, ptr};
+
+// the smallest page size there is. loads that stay inside one can't fault if any byte they touch
+// is readable, which is what lets these read past the end of a string
+const PAGE_SIZE: usize = 4096;
+
+/// One implementation of each accelerated routine, all for the same
+/// instruction set.
+pub(crate) struct Functions {
+    pub(crate) strlen: unsafe fn(*const u8) -> usize,
+    pub(crate) memchr: unsafe fn(*const u8, u8, usize) -> *const u8,
+    pub(crate) memcmp: unsafe fn(*const u8, *const u8, usize) -> c_int,
+    pub(crate) strchr: unsafe fn(*const u8, u8) -> *const u8,
+    pub(crate) strcmp: unsafe fn(*const u8, *const u8) -> c_int,
+}
+
+// every x86_64 processor has SSE2, so this is right until initialize says otherwise
+static mut SELECTED: &Functions = &sse2::FUNCTIONS;
+
+pub(crate) fn get() -> &'static Functions {
+    unsafe { SELECTED }
+}
+
+/// Picks the widest implementation the processor and kernel support. Runs
+/// once, at startup, before there are any other threads to race with.
+pub(crate) unsafe fn initialize() {
+    if has_avx2() {
+        SELECTED = &avx2::FUNCTIONS;
+    }
+}
+
+unsafe fn has_avx2() -> bool {
+    const OSXSAVE: u32 = 1 << 27;
+    const AVX: u32 = 1 << 28;
+    const AVX2: u32 = 1 << 5;
+    // XMM and YMM state
+    const XCR0_AVX: u64 = 0b110;
+
+    let features = __cpuid(1);
+
+    if features.ecx & (OSXSAVE | AVX) != OSXSAVE | AVX || __get_cpuid_max(0).0 < 7 {
+        return false;
+    }
+
+    // the processor may support AVX without the kernel saving the upper halves of the ymm
+    // registers across context switches
+    if _xgetbv(0) & XCR0_AVX != XCR0_AVX {
+        return false;
+    }
+
+    __cpuid_count(7, 0).ebx & AVX2 != 0
+}
+
+macro_rules! vector_functions {
+    (
+        $module:ident,
+        $feature:tt,
+        $vector:ty,
+        $load:ident,
+        $loadu:ident,
+        $set1:ident,
+        $setzero:ident,
+        $cmpeq:ident,
+        $movemask:ident
+    ) => {
+        pub(crate) mod $module {
+            use super::*;
+
+            const WIDTH: usize = mem::size_of::<$vector>();
+            const ALL_LANES: u32 = ((1u64 << WIDTH) - 1) as u32;
+
+            pub(crate) static FUNCTIONS: Functions = Functions {
+                strlen,
+                memchr,
+                memcmp,
+                strchr,
+                strcmp,
+            };
+
+            /// Loads the aligned vector containing `addr`.
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn load_block(addr: usize) -> $vector {
+                $load((addr - addr % WIDTH) as *const $vector)
+            }
+
+            /// Returns a bit for each lane, set where `lhs` and `rhs` are equal.
+            #[target_feature(enable = $feature)]
+            #[inline]
+            unsafe fn equal_lanes(lhs: $vector, rhs: $vector) -> u32 {
+                $movemask($cmpeq(lhs, rhs)) as u32
+            }
+
+            #[target_feature(enable = $feature)]
+            unsafe fn strlen(s: *const u8) -> usize {
+                let zero = $setzero();
+                let start = s as usize;
+                let mut block = start - start % WIDTH;
+
+                // the first block can start before s, so ignore anything found there
+                let mut found = equal_lanes(load_block(start), zero) >> (start % WIDTH);
+
+                if found != 0 {
+                    return found.trailing_zeros() as usize;
+                }
+
+                loop {
+                    block += WIDTH;
+                    found = equal_lanes(load_block(block), zero);
+
+                    if found != 0 {
+                        return block - start + found.trailing_zeros() as usize;
+                    }
+                }
+            }
+
+            #[target_feature(enable = $feature)]
+            unsafe fn memchr(s: *const u8, c: u8, n: usize) -> *const u8 {
+                if n == 0 {
+                    return ptr::null();
+                }
+
+                let needle = $set1(c as i8);
+                let start = s as usize;
+                let mut block = start - start % WIDTH;
+                let mut base = start;
+                let mut found = equal_lanes(load_block(start), needle) >> (start % WIDTH);
+
+                // every block loaded holds at least one byte of s, so none of them can fault
+                loop {
+                    if found != 0 {
+                        let addr = base + found.trailing_zeros() as usize;
+
+                        // offsets from start, since start + n can overflow: memchr(s, c,
+                        // SIZE_MAX) is fine when c is known to be there
+                        return if addr - start < n {
+                            addr as *const u8
+                        } else {
+                            ptr::null()
+                        };
+                    }
+
+                    block += WIDTH;
+
+                    if block - start >= n {
+                        return ptr::null();
+                    }
+
+                    base = block;
+                    found = equal_lanes(load_block(block), needle);
+                }
+            }
+
+            #[target_feature(enable = $feature)]
+            unsafe fn memcmp(s1: *const u8, s2: *const u8, n: usize) -> c_int {
+                let mut i = 0;
+
+                while i + WIDTH <= n {
+                    let lhs = $loadu(s1.add(i) as *const $vector);
+                    let rhs = $loadu(s2.add(i) as *const $vector);
+                    let different = !equal_lanes(lhs, rhs) & ALL_LANES;
+
+                    if different != 0 {
+                        let j = i + different.trailing_zeros() as usize;
+
+                        return *s1.add(j) as c_int - *s2.add(j) as c_int;
+                    }
+
+                    i += WIDTH;
+                }
+
+                while i < n {
+                    if *s1.add(i) != *s2.add(i) {
+                        return *s1.add(i) as c_int - *s2.add(i) as c_int;
+                    }
+
+                    i += 1;
+                }
+
+                0
+            }
+
+            #[target_feature(enable = $feature)]
+            unsafe fn strchr(s: *const u8, c: u8) -> *const u8 {
+                let zero = $setzero();
+                let needle = $set1(c as i8);
+                let start = s as usize;
+                let mut block = start - start % WIDTH;
+                let mut base = start;
+
+                let mut vector = load_block(start);
+                let mut found =
+                    (equal_lanes(vector, needle) | equal_lanes(vector, zero)) >> (start % WIDTH);
+
+                loop {
+                    if found != 0 {
+                        let addr = (base + found.trailing_zeros() as usize) as *const u8;
+
+                        return if *addr == c { addr } else { ptr::null() };
+                    }
+
+                    block += WIDTH;
+                    base = block;
+                    vector = load_block(block);
+                    found = equal_lanes(vector, needle) | equal_lanes(vector, zero);
+                }
+            }
+
+            #[target_feature(enable = $feature)]
+            unsafe fn strcmp(s1: *const u8, s2: *const u8) -> c_int {
+                let zero = $setzero();
+                let mut i = 0;
+
+                loop {
+                    // s1 and s2 are rarely aligned the same way, so these loads are unaligned and
+                    // have to step around page boundaries one byte at a time
+                    if near_page_end(s1 as usize + i) || near_page_end(s2 as usize + i) {
+                        for _ in 0..WIDTH {
+                            let (l, r) = (*s1.add(i), *s2.add(i));
+
+                            if l != r || l == 0 {
+                                return l as c_int - r as c_int;
+                            }
+
+                            i += 1;
+                        }
+
+                        continue;
+                    }
+
+                    let lhs = $loadu(s1.add(i) as *const $vector);
+                    let rhs = $loadu(s2.add(i) as *const $vector);
+                    let stop = (!equal_lanes(lhs, rhs) & ALL_LANES) | equal_lanes(lhs, zero);
+
+                    if stop != 0 {
+                        let j = i + stop.trailing_zeros() as usize;
+
+                        return *s1.add(j) as c_int - *s2.add(j) as c_int;
+                    }
+
+                    i += WIDTH;
+                }
+            }
+
+            fn near_page_end(addr: usize) -> bool {
+                addr % PAGE_SIZE > PAGE_SIZE - WIDTH
+            }
+        }
+    };
+}
+
+vector_functions!(
+    sse2,
+    "sse2",
+    __m128i,
+    _mm_load_si128,
+    _mm_loadu_si128,
+    _mm_set1_epi8,
+    _mm_setzero_si128,
+    _mm_cmpeq_epi8,
+    _mm_movemask_epi8
+);
+
+vector_functions!(
+    avx2,
+    "avx2",
+    __m256i,
+    _mm256_load_si256,
+    _mm256_loadu_si256,
+    _mm256_set1_epi8,
+    _mm256_setzero_si256,
+    _mm256_cmpeq_epi8,
+    _mm256_movemask_epi8
+);
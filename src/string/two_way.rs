@@ -0,0 +1,137 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use core::cmp::{self, Ordering};
+
+/// Finds the first occurrence of `needle` in `haystack` with the Two-Way
+/// algorithm, in linear time and constant space. `needle` must not be empty.
+///
+/// Crochemore and Perrin, "Two-way string-matching", Journal of the ACM
+/// 38(3), 1991. The shift table on the last byte of each window is the same
+/// shortcut musl takes.
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let len = needle.len();
+
+    if len > haystack.len() {
+        return None;
+    }
+
+    // how far the window can move when its last byte is b: past the last occurrence of b in the
+    // needle, or past the whole window if b isn't in it at all
+    let mut shift = [0; 256];
+
+    for (i, &b) in needle.iter().enumerate() {
+        shift[b as usize] = i + 1;
+    }
+
+    // the critical factorization splits the needle into needle[..split] and needle[split..]
+    let (split, period) = {
+        let (less_split, less_period) = maximal_suffix(needle, Ordering::Less);
+        let (greater_split, greater_period) = maximal_suffix(needle, Ordering::Greater);
+
+        if less_split > greater_split {
+            (less_split, less_period)
+        } else {
+            (greater_split, greater_period)
+        }
+    };
+
+    // a periodic needle lets matched prefixes be remembered across shifts; otherwise shift by
+    // more than either half
+    let (period, memory_after_shift) = if needle[..split] == needle[period..period + split] {
+        (period, len - period)
+    } else {
+        (cmp::max(split, len - split) + 1, 0)
+    };
+
+    let mut pos = 0;
+    // bytes at the start of the window already known to match
+    let mut memory = 0;
+
+    while haystack.len() - pos >= len {
+        let window = &haystack[pos..pos + len];
+
+        let last_shift = len - shift[window[len - 1] as usize];
+
+        if last_shift != 0 {
+            pos += cmp::max(last_shift, memory);
+            memory = 0;
+
+            continue;
+        }
+
+        // right half first, left to right
+        let right_start = cmp::max(split, memory);
+        let right_mismatch = (right_start..len).find(|&i| needle[i] != window[i]);
+
+        if let Some(i) = right_mismatch {
+            pos += i + 1 - split;
+            memory = 0;
+
+            continue;
+        }
+
+        // then the left half, right to left
+        let left_matches = (memory..split).rev().all(|i| needle[i] == window[i]);
+
+        if left_matches {
+            return Some(pos);
+        }
+
+        pos += period;
+        memory = memory_after_shift;
+    }
+
+    None
+}
+
+/// Returns the start of the maximal suffix of `needle` under the ordering
+/// `order` picks for bytes, and the period of that suffix.
+fn maximal_suffix(needle: &[u8], order: Ordering) -> (usize, usize) {
+    // candidate suffix starts after `suffix`; `suffix` is one past it so it can start at 0
+    let mut suffix = 0;
+    let mut candidate = 1;
+    let mut offset = 0;
+    let mut period = 1;
+
+    while candidate + offset < needle.len() {
+        let current = needle[suffix + offset];
+        let challenger = needle[candidate + offset];
+
+        match challenger.cmp(&current) {
+            Ordering::Equal => {
+                if offset + 1 == period {
+                    candidate += period;
+                    offset = 0;
+                } else {
+                    offset += 1;
+                }
+            }
+            o if o == order => {
+                candidate += offset + 1;
+                offset = 0;
+                period = candidate - suffix;
+            }
+            _ => {
+                suffix = candidate;
+                candidate += 1;
+                offset = 0;
+                period = 1;
+            }
+        }
+    }
+
+    (suffix, period)
+}
@@ -13,9 +13,50 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{c_int, c_void, stddef::size_t, string};
+use crate::{c_char, c_int, c_long, c_longlong, c_void, stddef::size_t, string};
 
 #[no_mangle]
 pub unsafe extern "C" fn bcmp(s1: *const c_void, s2: *const c_void, n: size_t) -> c_int {
     string::memcmp(s1, s2, n)
 }
+
+#[no_mangle]
+pub unsafe extern "C" fn strcasecmp(s1: *const c_char, s2: *const c_char) -> c_int {
+    strncasecmp(s1, s2, size_t::MAX)
+}
+
+/// Compares at most `n` characters, ignoring ASCII case.
+#[no_mangle]
+pub unsafe extern "C" fn strncasecmp(s1: *const c_char, s2: *const c_char, n: size_t) -> c_int {
+    for i in 0..n as usize {
+        let l = (*s1.add(i) as u8).to_ascii_lowercase();
+        let r = (*s2.add(i) as u8).to_ascii_lowercase();
+
+        if l != r || l == 0 {
+            return l as c_int - r as c_int;
+        }
+    }
+
+    0
+}
+
+/// Returns the one-based index of the least significant set bit in `i`, or 0
+/// if there is none.
+#[no_mangle]
+pub extern "C" fn ffs(i: c_int) -> c_int {
+    ffsll(i as c_longlong)
+}
+
+#[no_mangle]
+pub extern "C" fn ffsl(i: c_long) -> c_int {
+    ffsll(i as c_longlong)
+}
+
+#[no_mangle]
+pub extern "C" fn ffsll(i: c_longlong) -> c_int {
+    if i == 0 {
+        0
+    } else {
+        i.trailing_zeros() as c_int + 1
+    }
+}
@@ -13,7 +13,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+pub mod auxv;
+pub mod file;
 pub mod mman;
+pub mod resource;
+pub mod sendfile;
+pub mod signalfd;
 pub mod stat;
+pub mod statfs;
+pub mod sysinfo;
 pub mod time;
 pub mod types;
+pub mod uio;
+pub mod wait;
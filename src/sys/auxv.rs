@@ -0,0 +1,54 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{c_unsignedlong, errno, internal};
+
+pub const AT_NULL: c_unsignedlong = 0;
+pub const AT_IGNORE: c_unsignedlong = 1;
+pub const AT_EXECFD: c_unsignedlong = 2;
+pub const AT_PHDR: c_unsignedlong = 3;
+pub const AT_PHENT: c_unsignedlong = 4;
+pub const AT_PHNUM: c_unsignedlong = 5;
+pub const AT_PAGESZ: c_unsignedlong = 6;
+pub const AT_BASE: c_unsignedlong = 7;
+pub const AT_FLAGS: c_unsignedlong = 8;
+pub const AT_ENTRY: c_unsignedlong = 9;
+pub const AT_NOTELF: c_unsignedlong = 10;
+pub const AT_UID: c_unsignedlong = 11;
+pub const AT_EUID: c_unsignedlong = 12;
+pub const AT_GID: c_unsignedlong = 13;
+pub const AT_EGID: c_unsignedlong = 14;
+pub const AT_PLATFORM: c_unsignedlong = 15;
+pub const AT_HWCAP: c_unsignedlong = 16;
+pub const AT_CLKTCK: c_unsignedlong = 17;
+pub const AT_SECURE: c_unsignedlong = 23;
+pub const AT_BASE_PLATFORM: c_unsignedlong = 24;
+pub const AT_RANDOM: c_unsignedlong = 25;
+pub const AT_HWCAP2: c_unsignedlong = 26;
+pub const AT_EXECFN: c_unsignedlong = 31;
+pub const AT_SYSINFO_EHDR: c_unsignedlong = 33;
+pub const AT_MINSIGSTKSZ: c_unsignedlong = 51;
+
+#[no_mangle]
+pub unsafe extern "C" fn getauxval(type_: c_unsignedlong) -> c_unsignedlong {
+    match internal::auxv::get(type_) {
+        Some(value) => value,
+        None => {
+            *internal::errno() = errno::ENOENT;
+
+            0
+        }
+    }
+}
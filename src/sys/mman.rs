@@ -92,6 +92,11 @@ pub unsafe extern "C" fn munmap(addr: *mut c_void, length: size_t) -> c_int {
     wrap_syscall!(sys::munmap(addr, length)) as c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn mprotect(addr: *mut c_void, length: size_t, prot: c_int) -> c_int {
+    wrap_syscall!(sys::mprotect(addr, length, prot)) as c_int
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn madvise(addr: *mut c_void, length: size_t, advice: c_int) -> c_int {
     wrap_syscall!(sys::madvise(addr, length, advice)) as c_int
@@ -128,6 +133,10 @@ pub(crate) mod sys {
         syscall!(11, addr as isize, length as isize)
     }
 
+    pub(crate) unsafe fn mprotect(addr: *mut c_void, length: size_t, prot: c_int) -> isize {
+        syscall!(10, addr as isize, length as isize, prot as isize)
+    }
+
     pub(crate) unsafe fn madvise(addr: *mut c_void, length: size_t, advice: c_int) -> isize {
         syscall!(28, addr as isize, length as isize, advice as isize)
     }
@@ -0,0 +1,100 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{c_int, c_long, c_unsignedlong, sys::time::timeval, syscall, wrap_syscall};
+
+pub type rlim_t = c_unsignedlong;
+
+pub const RLIM_INFINITY: rlim_t = !0;
+
+pub const RLIMIT_CPU: c_int = 0;
+pub const RLIMIT_FSIZE: c_int = 1;
+pub const RLIMIT_DATA: c_int = 2;
+pub const RLIMIT_STACK: c_int = 3;
+pub const RLIMIT_CORE: c_int = 4;
+pub const RLIMIT_RSS: c_int = 5;
+pub const RLIMIT_NPROC: c_int = 6;
+pub const RLIMIT_NOFILE: c_int = 7;
+pub const RLIMIT_MEMLOCK: c_int = 8;
+pub const RLIMIT_AS: c_int = 9;
+pub const RLIMIT_LOCKS: c_int = 10;
+pub const RLIMIT_SIGPENDING: c_int = 11;
+pub const RLIMIT_MSGQUEUE: c_int = 12;
+pub const RLIMIT_NICE: c_int = 13;
+pub const RLIMIT_RTPRIO: c_int = 14;
+pub const RLIMIT_RTTIME: c_int = 15;
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct rlimit {
+    pub rlim_cur: rlim_t,
+    pub rlim_max: rlim_t,
+}
+
+pub const RUSAGE_SELF: c_int = 0;
+pub const RUSAGE_CHILDREN: c_int = -1;
+pub const RUSAGE_THREAD: c_int = 1;
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct rusage {
+    pub ru_utime: timeval,
+    pub ru_stime: timeval,
+    pub ru_maxrss: c_long,
+    pub ru_ixrss: c_long,
+    pub ru_idrss: c_long,
+    pub ru_isrss: c_long,
+    pub ru_minflt: c_long,
+    pub ru_majflt: c_long,
+    pub ru_nswap: c_long,
+    pub ru_inblock: c_long,
+    pub ru_oublock: c_long,
+    pub ru_msgsnd: c_long,
+    pub ru_msgrcv: c_long,
+    pub ru_nsignals: c_long,
+    pub ru_nvcsw: c_long,
+    pub ru_nivcsw: c_long,
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getrlimit(resource: c_int, rlim: *mut rlimit) -> c_int {
+    wrap_syscall!(sys::getrlimit(resource, rlim)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn setrlimit(resource: c_int, rlim: *const rlimit) -> c_int {
+    wrap_syscall!(sys::setrlimit(resource, rlim)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getrusage(who: c_int, usage: *mut rusage) -> c_int {
+    wrap_syscall!(sys::getrusage(who, usage)) as c_int
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    pub(crate) unsafe fn getrlimit(resource: c_int, rlim: *mut rlimit) -> isize {
+        syscall!(97, resource as isize, rlim as isize)
+    }
+
+    pub(crate) unsafe fn setrlimit(resource: c_int, rlim: *const rlimit) -> isize {
+        syscall!(160, resource as isize, rlim as isize)
+    }
+
+    pub(crate) unsafe fn getrusage(who: c_int, usage: *mut rusage) -> isize {
+        syscall!(98, who as isize, usage as isize)
+    }
+}
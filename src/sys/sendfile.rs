@@ -0,0 +1,52 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_int,
+    stddef::{size_t, ssize_t},
+    sys::types::off_t,
+    wrap_syscall,
+};
+
+#[no_mangle]
+pub unsafe extern "C" fn sendfile(
+    out_fd: c_int,
+    in_fd: c_int,
+    offset: *mut off_t,
+    count: size_t,
+) -> ssize_t {
+    wrap_syscall!(sys::sendfile(out_fd, in_fd, offset, count)) as ssize_t
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    use crate::syscall;
+
+    pub(crate) unsafe fn sendfile(
+        out_fd: c_int,
+        in_fd: c_int,
+        offset: *mut off_t,
+        count: size_t,
+    ) -> isize {
+        syscall!(
+            40,
+            out_fd as isize,
+            in_fd as isize,
+            offset as isize,
+            count as isize
+        )
+    }
+}
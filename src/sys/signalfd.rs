@@ -0,0 +1,74 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_int, c_unsignedint, c_unsignedlong, c_unsignedshort,
+    fcntl::{O_CLOEXEC, O_NONBLOCK},
+    signal::sigset_t,
+    syscall, wrap_syscall,
+};
+
+use core::mem;
+
+pub const SFD_CLOEXEC: c_int = O_CLOEXEC;
+pub const SFD_NONBLOCK: c_int = O_NONBLOCK;
+
+/// What a read from a signalfd gets for each signal.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct signalfd_siginfo {
+    pub ssi_signo: c_unsignedint,
+    pub ssi_errno: c_int,
+    pub ssi_code: c_int,
+    pub ssi_pid: c_unsignedint,
+    pub ssi_uid: c_unsignedint,
+    pub ssi_fd: c_int,
+    pub ssi_tid: c_unsignedint,
+    pub ssi_band: c_unsignedint,
+    pub ssi_overrun: c_unsignedint,
+    pub ssi_trapno: c_unsignedint,
+    pub ssi_status: c_int,
+    pub ssi_int: c_int,
+    pub ssi_ptr: c_unsignedlong,
+    pub ssi_utime: c_unsignedlong,
+    pub ssi_stime: c_unsignedlong,
+    pub ssi_addr: c_unsignedlong,
+    pub ssi_addr_lsb: c_unsignedshort,
+    pad2: c_unsignedshort,
+    pub ssi_syscall: c_int,
+    pub ssi_call_addr: c_unsignedlong,
+    pub ssi_arch: c_unsignedint,
+    pad: [u8; 28],
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn signalfd(fd: c_int, mask: *const sigset_t, flags: c_int) -> c_int {
+    wrap_syscall!(sys::signalfd4(fd, mask, flags)) as c_int
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    pub(crate) unsafe fn signalfd4(fd: c_int, mask: *const sigset_t, flags: c_int) -> isize {
+        // like every other signal syscall, only the first word of the set
+        syscall!(
+            289,
+            fd as isize,
+            mask as isize,
+            mem::size_of::<c_unsignedlong>() as isize,
+            flags as isize
+        )
+    }
+}
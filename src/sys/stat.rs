@@ -14,8 +14,9 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    c_int,
-    sys::types::{mode_t, stat},
+    c_char, c_int, c_long, c_unsignedint, c_unsignedlong, c_unsignedshort,
+    fcntl::{AT_FDCWD, AT_SYMLINK_NOFOLLOW},
+    sys::types::{mode_t, stat, timespec},
     wrap_syscall,
 };
 
@@ -29,11 +30,133 @@ pub const S_IFDIR: mode_t = 0o040000; // directory
 pub const S_IFCHR: mode_t = 0o020000; // character device
 pub const S_IFIFO: mode_t = 0o010000; // FIFO
 
+pub const UTIME_NOW: c_long = (1 << 30) - 1;
+pub const UTIME_OMIT: c_long = (1 << 30) - 2;
+
+pub const STATX_TYPE: c_unsignedint = 0x0001;
+pub const STATX_MODE: c_unsignedint = 0x0002;
+pub const STATX_NLINK: c_unsignedint = 0x0004;
+pub const STATX_UID: c_unsignedint = 0x0008;
+pub const STATX_GID: c_unsignedint = 0x0010;
+pub const STATX_ATIME: c_unsignedint = 0x0020;
+pub const STATX_MTIME: c_unsignedint = 0x0040;
+pub const STATX_CTIME: c_unsignedint = 0x0080;
+pub const STATX_INO: c_unsignedint = 0x0100;
+pub const STATX_SIZE: c_unsignedint = 0x0200;
+pub const STATX_BLOCKS: c_unsignedint = 0x0400;
+pub const STATX_BASIC_STATS: c_unsignedint = 0x07ff;
+pub const STATX_BTIME: c_unsignedint = 0x0800;
+pub const STATX_ALL: c_unsignedint = 0x0fff;
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct statx_timestamp {
+    pub tv_sec: i64,
+    pub tv_nsec: u32,
+    reserved: i32,
+}
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct statx {
+    pub stx_mask: c_unsignedint,
+    pub stx_blksize: c_unsignedint,
+    pub stx_attributes: c_unsignedlong,
+    pub stx_nlink: c_unsignedint,
+    pub stx_uid: c_unsignedint,
+    pub stx_gid: c_unsignedint,
+    pub stx_mode: c_unsignedshort,
+    spare0: c_unsignedshort,
+    pub stx_ino: c_unsignedlong,
+    pub stx_size: c_unsignedlong,
+    pub stx_blocks: c_unsignedlong,
+    pub stx_attributes_mask: c_unsignedlong,
+
+    pub stx_atime: statx_timestamp,
+    pub stx_btime: statx_timestamp,
+    pub stx_ctime: statx_timestamp,
+    pub stx_mtime: statx_timestamp,
+
+    pub stx_rdev_major: c_unsignedint,
+    pub stx_rdev_minor: c_unsignedint,
+    pub stx_dev_major: c_unsignedint,
+    pub stx_dev_minor: c_unsignedint,
+
+    spare2: [c_unsignedlong; 14],
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn stat(pathname: *const c_char, statbuf: *mut stat) -> c_int {
+    fstatat(AT_FDCWD, pathname, statbuf, 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lstat(pathname: *const c_char, statbuf: *mut stat) -> c_int {
+    fstatat(AT_FDCWD, pathname, statbuf, AT_SYMLINK_NOFOLLOW)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn fstat(fd: c_int, statbuf: *mut stat) -> c_int {
     wrap_syscall!(sys::fstat(fd, statbuf)) as c_int
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn fstatat(
+    dirfd: c_int,
+    pathname: *const c_char,
+    statbuf: *mut stat,
+    flags: c_int,
+) -> c_int {
+    wrap_syscall!(sys::fstatat(dirfd, pathname, statbuf, flags)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn statx(
+    dirfd: c_int,
+    pathname: *const c_char,
+    flags: c_int,
+    mask: c_unsignedint,
+    statxbuf: *mut statx,
+) -> c_int {
+    wrap_syscall!(sys::statx(dirfd, pathname, flags, mask, statxbuf)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn mkdir(pathname: *const c_char, mode: mode_t) -> c_int {
+    mkdirat(AT_FDCWD, pathname, mode)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn mkdirat(dirfd: c_int, pathname: *const c_char, mode: mode_t) -> c_int {
+    wrap_syscall!(sys::mkdirat(dirfd, pathname, mode)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn chmod(pathname: *const c_char, mode: mode_t) -> c_int {
+    wrap_syscall!(sys::fchmodat(AT_FDCWD, pathname, mode)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fchmod(fd: c_int, mode: mode_t) -> c_int {
+    wrap_syscall!(sys::fchmod(fd, mode)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn utimensat(
+    dirfd: c_int,
+    pathname: *const c_char,
+    times: *const timespec,
+    flags: c_int,
+) -> c_int {
+    wrap_syscall!(sys::utimensat(dirfd, pathname, times, flags)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn futimens(fd: c_int, times: *const timespec) -> c_int {
+    // a null path makes utimensat work on dirfd itself
+    wrap_syscall!(sys::utimensat(fd, core::ptr::null(), times, 0)) as c_int
+}
+
 pub(crate) mod sys {
     use super::*;
 
@@ -42,4 +165,63 @@ pub(crate) mod sys {
     pub(crate) unsafe fn fstat(fd: c_int, statbuf: *mut stat) -> isize {
         syscall!(5, fd as isize, statbuf as isize)
     }
+
+    pub(crate) unsafe fn fstatat(
+        dirfd: c_int,
+        pathname: *const c_char,
+        statbuf: *mut stat,
+        flags: c_int,
+    ) -> isize {
+        syscall!(
+            262,
+            dirfd as isize,
+            pathname as isize,
+            statbuf as isize,
+            flags as isize
+        )
+    }
+
+    pub(crate) unsafe fn statx(
+        dirfd: c_int,
+        pathname: *const c_char,
+        flags: c_int,
+        mask: c_unsignedint,
+        statxbuf: *mut statx,
+    ) -> isize {
+        syscall!(
+            332,
+            dirfd as isize,
+            pathname as isize,
+            flags as isize,
+            mask as isize,
+            statxbuf as isize
+        )
+    }
+
+    pub(crate) unsafe fn mkdirat(dirfd: c_int, pathname: *const c_char, mode: mode_t) -> isize {
+        syscall!(258, dirfd as isize, pathname as isize, mode as isize)
+    }
+
+    pub(crate) unsafe fn fchmodat(dirfd: c_int, pathname: *const c_char, mode: mode_t) -> isize {
+        syscall!(268, dirfd as isize, pathname as isize, mode as isize)
+    }
+
+    pub(crate) unsafe fn fchmod(fd: c_int, mode: mode_t) -> isize {
+        syscall!(91, fd as isize, mode as isize)
+    }
+
+    pub(crate) unsafe fn utimensat(
+        dirfd: c_int,
+        pathname: *const c_char,
+        times: *const timespec,
+        flags: c_int,
+    ) -> isize {
+        syscall!(
+            280,
+            dirfd as isize,
+            pathname as isize,
+            times as isize,
+            flags as isize
+        )
+    }
 }
@@ -0,0 +1,64 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{c_char, c_int, c_long, c_unsignedlong, syscall, wrap_syscall};
+
+pub type fsblkcnt_t = c_unsignedlong;
+pub type fsfilcnt_t = c_unsignedlong;
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct fsid_t {
+    val: [c_int; 2],
+}
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct statfs {
+    pub f_type: c_long,
+    pub f_bsize: c_long,
+    pub f_blocks: fsblkcnt_t,
+    pub f_bfree: fsblkcnt_t,
+    pub f_bavail: fsblkcnt_t,
+    pub f_files: fsfilcnt_t,
+    pub f_ffree: fsfilcnt_t,
+    pub f_fsid: fsid_t,
+    pub f_namelen: c_long,
+    pub f_frsize: c_long,
+    pub f_flags: c_long,
+    spare: [c_long; 4],
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn statfs(path: *const c_char, buf: *mut statfs) -> c_int {
+    wrap_syscall!(sys::statfs(path, buf)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fstatfs(fd: c_int, buf: *mut statfs) -> c_int {
+    wrap_syscall!(sys::fstatfs(fd, buf)) as c_int
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    pub(crate) unsafe fn statfs(path: *const c_char, buf: *mut super::statfs) -> isize {
+        syscall!(137, path as isize, buf as isize)
+    }
+
+    pub(crate) unsafe fn fstatfs(fd: c_int, buf: *mut super::statfs) -> isize {
+        syscall!(138, fd as isize, buf as isize)
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_char, c_int, c_long, c_unsignedint, c_unsignedlong, c_unsignedshort, c_void, fcntl,
+    internal::{self, errno::ErrorNumber, FileDescriptor},
+    sched::{self, cpu_set_t},
+    stddef::size_t,
+    syscall, unistd, wrap_syscall,
+};
+
+use core::{
+    convert::TryInto,
+    mem::{self, MaybeUninit},
+};
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct sysinfo {
+    pub uptime: c_long,
+    pub loads: [c_unsignedlong; 3],
+    pub totalram: c_unsignedlong,
+    pub freeram: c_unsignedlong,
+    pub sharedram: c_unsignedlong,
+    pub bufferram: c_unsignedlong,
+    pub totalswap: c_unsignedlong,
+    pub freeswap: c_unsignedlong,
+    pub procs: c_unsignedshort,
+    pad: c_unsignedshort,
+    pub totalhigh: c_unsignedlong,
+    pub freehigh: c_unsignedlong,
+    pub mem_unit: c_unsignedint,
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn sysinfo(info: *mut sysinfo) -> c_int {
+    wrap_syscall!(sys::sysinfo(info)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn get_nprocs() -> c_int {
+    count_cpus(b"/sys/devices/system/cpu/online\0")
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn get_nprocs_conf() -> c_int {
+    count_cpus(b"/sys/devices/system/cpu/possible\0")
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn get_phys_pages() -> c_long {
+    memory_in_pages(|info| info.totalram)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn get_avphys_pages() -> c_long {
+    memory_in_pages(|info| info.freeram)
+}
+
+fn memory_in_pages<F: FnOnce(&sysinfo) -> c_unsignedlong>(amount: F) -> c_long {
+    let mut info = sysinfo::default();
+
+    if let Err(e) = ErrorNumber::from_syscall::<isize>(unsafe { sys::sysinfo(&mut info) }) {
+        unsafe { *internal::errno() = e.into_int() };
+
+        return -1;
+    }
+
+    let bytes = amount(&info).saturating_mul(info.mem_unit as c_unsignedlong);
+
+    (bytes / internal::page_size() as c_unsignedlong) as c_long
+}
+
+// prefers the list in sysfs, but it's missing in some containers. the affinity mask is then the
+// best guess, and it's at least one CPU, since we're running on it
+fn count_cpus(list_path: &[u8]) -> c_int {
+    if let Some(count) = read_cpu_list(list_path) {
+        return count;
+    }
+
+    let mut set = MaybeUninit::<cpu_set_t>::uninit();
+    let set_len = mem::size_of::<cpu_set_t>() as size_t;
+
+    unsafe {
+        if sched::sched_getaffinity(0, set_len, set.as_mut_ptr()) == 0 {
+            sched::__sched_cpucount(set_len, set.as_ptr())
+        } else {
+            1
+        }
+    }
+}
+
+// reads a list like "0-3,6,8-11\n"
+fn read_cpu_list(path: &[u8]) -> Option<c_int> {
+    let fd: FileDescriptor = ErrorNumber::from_syscall(unsafe {
+        fcntl::sys::open(
+            path.as_ptr() as *const c_char,
+            fcntl::O_RDONLY | fcntl::O_CLOEXEC,
+            0,
+        )
+    })
+    .ok()?;
+
+    let mut buf = [0u8; 256];
+    let len: usize = ErrorNumber::from_syscall(unsafe {
+        unistd::sys::read(
+            fd.as_raw(),
+            buf.as_mut_ptr() as *mut c_void,
+            buf.len() as size_t,
+        )
+    })
+    .ok()?;
+
+    let mut count = 0;
+
+    for range in buf[..len]
+        .split(|&b| b == b',' || b == b'\n')
+        .filter(|r| !r.is_empty())
+    {
+        let mut bounds = range.splitn(2, |&b| b == b'-').map(parse_cpu);
+        let first = bounds.next()??;
+        let last = bounds.next().unwrap_or(Some(first))?;
+
+        count += last.checked_sub(first)? + 1;
+    }
+
+    if count > 0 {
+        count.try_into().ok()
+    } else {
+        None
+    }
+}
+
+fn parse_cpu(s: &[u8]) -> Option<u32> {
+    if s.is_empty() {
+        return None;
+    }
+
+    s.iter().try_fold(0u32, |acc, &b| {
+        if b.is_ascii_digit() {
+            acc.checked_mul(10)?.checked_add((b - b'0') as u32)
+        } else {
+            None
+        }
+    })
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    pub(crate) unsafe fn sysinfo(info: *mut super::sysinfo) -> isize {
+        syscall!(99, info as isize)
+    }
+}
@@ -14,3 +14,14 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 pub use crate::time::timespec;
+
+use crate::{c_long, time::time_t};
+
+pub type suseconds_t = c_long;
+
+#[repr(C)]
+#[derive(Default, Copy, Clone, Debug)]
+pub struct timeval {
+    pub tv_sec: time_t,
+    pub tv_usec: suseconds_t,
+}
@@ -27,6 +27,8 @@ pub type mode_t = c_unsignedint;
 pub type nlink_t = c_unsignedlong;
 pub type uid_t = c_unsignedint;
 pub type gid_t = c_unsignedint;
+pub type pid_t = c_int;
+pub type id_t = c_unsignedint;
 pub type blksize_t = c_long;
 pub type blkcnt_t = c_long;
 
@@ -0,0 +1,183 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_int, c_void,
+    stddef::{size_t, ssize_t},
+    sys::types::off_t,
+    wrap_syscall,
+};
+
+pub const IOV_MAX: c_int = 1024;
+pub const UIO_MAXIOV: c_int = IOV_MAX;
+
+pub const RWF_HIPRI: c_int = 0x01;
+pub const RWF_DSYNC: c_int = 0x02;
+pub const RWF_SYNC: c_int = 0x04;
+pub const RWF_NOWAIT: c_int = 0x08;
+pub const RWF_APPEND: c_int = 0x10;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct iovec {
+    pub iov_base: *mut c_void,
+    pub iov_len: size_t,
+}
+
+impl iovec {
+    pub(crate) fn from_slice(data: &[u8]) -> iovec {
+        iovec {
+            iov_base: data.as_ptr() as *mut c_void,
+            iov_len: data.len() as size_t,
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn readv(fd: c_int, iov: *const iovec, iovcnt: c_int) -> ssize_t {
+    wrap_syscall!(sys::readv(fd, iov, iovcnt)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn writev(fd: c_int, iov: *const iovec, iovcnt: c_int) -> ssize_t {
+    wrap_syscall!(sys::writev(fd, iov, iovcnt)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn preadv(
+    fd: c_int,
+    iov: *const iovec,
+    iovcnt: c_int,
+    offset: off_t,
+) -> ssize_t {
+    wrap_syscall!(sys::preadv(fd, iov, iovcnt, offset)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pwritev(
+    fd: c_int,
+    iov: *const iovec,
+    iovcnt: c_int,
+    offset: off_t,
+) -> ssize_t {
+    wrap_syscall!(sys::pwritev(fd, iov, iovcnt, offset)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn preadv2(
+    fd: c_int,
+    iov: *const iovec,
+    iovcnt: c_int,
+    offset: off_t,
+    flags: c_int,
+) -> ssize_t {
+    wrap_syscall!(sys::preadv2(fd, iov, iovcnt, offset, flags)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pwritev2(
+    fd: c_int,
+    iov: *const iovec,
+    iovcnt: c_int,
+    offset: off_t,
+    flags: c_int,
+) -> ssize_t {
+    wrap_syscall!(sys::pwritev2(fd, iov, iovcnt, offset, flags)) as ssize_t
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    use crate::syscall;
+
+    pub(crate) unsafe fn readv(fd: c_int, iov: *const iovec, iovcnt: c_int) -> isize {
+        syscall!(19, fd as isize, iov as isize, iovcnt as isize)
+    }
+
+    pub(crate) unsafe fn writev(fd: c_int, iov: *const iovec, iovcnt: c_int) -> isize {
+        syscall!(20, fd as isize, iov as isize, iovcnt as isize)
+    }
+
+    // the p*v calls split offset into low and high words, which on x86_64 means the high word
+    // is always zero
+
+    pub(crate) unsafe fn preadv(
+        fd: c_int,
+        iov: *const iovec,
+        iovcnt: c_int,
+        offset: off_t,
+    ) -> isize {
+        syscall!(
+            295,
+            fd as isize,
+            iov as isize,
+            iovcnt as isize,
+            offset as isize,
+            0
+        )
+    }
+
+    pub(crate) unsafe fn pwritev(
+        fd: c_int,
+        iov: *const iovec,
+        iovcnt: c_int,
+        offset: off_t,
+    ) -> isize {
+        syscall!(
+            296,
+            fd as isize,
+            iov as isize,
+            iovcnt as isize,
+            offset as isize,
+            0
+        )
+    }
+
+    pub(crate) unsafe fn preadv2(
+        fd: c_int,
+        iov: *const iovec,
+        iovcnt: c_int,
+        offset: off_t,
+        flags: c_int,
+    ) -> isize {
+        syscall!(
+            327,
+            fd as isize,
+            iov as isize,
+            iovcnt as isize,
+            offset as isize,
+            0,
+            flags as isize
+        )
+    }
+
+    pub(crate) unsafe fn pwritev2(
+        fd: c_int,
+        iov: *const iovec,
+        iovcnt: c_int,
+        offset: off_t,
+        flags: c_int,
+    ) -> isize {
+        syscall!(
+            328,
+            fd as isize,
+            iov as isize,
+            iovcnt as isize,
+            offset as isize,
+            0,
+            flags as isize
+        )
+    }
+}
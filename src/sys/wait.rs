@@ -0,0 +1,106 @@
+// Copyright (C) 2020 Gregory Meyer
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+    c_int,
+    signal::siginfo_t,
+    sys::{
+        resource::rusage,
+        types::{id_t, pid_t},
+    },
+    syscall, wrap_syscall,
+};
+
+use core::ptr;
+
+pub const WNOHANG: c_int = 0x00000001;
+pub const WUNTRACED: c_int = 0x00000002;
+pub const WSTOPPED: c_int = WUNTRACED;
+pub const WEXITED: c_int = 0x00000004;
+pub const WCONTINUED: c_int = 0x00000008;
+pub const WNOWAIT: c_int = 0x01000000;
+
+pub type idtype_t = c_int;
+
+pub const P_ALL: idtype_t = 0;
+pub const P_PID: idtype_t = 1;
+pub const P_PGID: idtype_t = 2;
+pub const P_PIDFD: idtype_t = 3;
+
+#[no_mangle]
+pub unsafe extern "C" fn wait(wstatus: *mut c_int) -> pid_t {
+    waitpid(-1, wstatus, 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn waitpid(pid: pid_t, wstatus: *mut c_int, options: c_int) -> pid_t {
+    wait4(pid, wstatus, options, ptr::null_mut())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn waitid(
+    idtype: idtype_t,
+    id: id_t,
+    infop: *mut siginfo_t,
+    options: c_int,
+) -> c_int {
+    wrap_syscall!(sys::waitid(idtype, id, infop, options, ptr::null_mut())) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wait4(
+    pid: pid_t,
+    wstatus: *mut c_int,
+    options: c_int,
+    rusage: *mut rusage,
+) -> pid_t {
+    wrap_syscall!(sys::wait4(pid, wstatus, options, rusage)) as pid_t
+}
+
+pub(crate) mod sys {
+    use super::*;
+
+    pub(crate) unsafe fn wait4(
+        pid: pid_t,
+        wstatus: *mut c_int,
+        options: c_int,
+        rusage: *mut rusage,
+    ) -> isize {
+        syscall!(
+            61,
+            pid as isize,
+            wstatus as isize,
+            options as isize,
+            rusage as isize
+        )
+    }
+
+    pub(crate) unsafe fn waitid(
+        idtype: idtype_t,
+        id: id_t,
+        infop: *mut siginfo_t,
+        options: c_int,
+        rusage: *mut rusage,
+    ) -> isize {
+        syscall!(
+            247,
+            idtype as isize,
+            id as isize,
+            infop as isize,
+            options as isize,
+            rusage as isize
+        )
+    }
+}
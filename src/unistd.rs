@@ -14,12 +14,27 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    c_char, c_int, c_long, c_void, errno, internal,
+    c_char, c_int, c_long, c_short, c_unsignedint, c_void, errno,
+    fcntl::{
+        self, flock, AT_EACCESS, AT_FDCWD, AT_REMOVEDIR, AT_SYMLINK_NOFOLLOW, F_GETLK, F_RDLCK,
+        F_SETLK, F_SETLKW, F_UNLCK, F_WRLCK,
+    },
+    internal::{self, alloc, errno::ErrorNumber},
+    pthread,
     stddef::{size_t, ssize_t},
-    sys::types::mode_t,
+    stdio, stdlib, string,
+    sys::{
+        auxv::{self, AT_CLKTCK},
+        resource::{self, rlimit, RLIMIT_NOFILE, RLIMIT_NPROC, RLIMIT_SIGPENDING, RLIMIT_STACK},
+        statfs::{self, statfs as Statfs},
+        sysinfo,
+        types::{gid_t, mode_t, off_t, pid_t, uid_t},
+    },
     syscall, wrap_syscall,
 };
 
+use core::{cmp, ptr, slice};
+
 pub const STDIN_FILENO: c_int = 0;
 pub const STDOUT_FILENO: c_int = 1;
 pub const STDERR_FILENO: c_int = 2;
@@ -37,24 +52,67 @@ pub const S_IROTH: mode_t = 0o0004;
 pub const S_IWOTH: mode_t = 0o0002;
 pub const S_IXOTH: mode_t = 0o0001;
 
-pub const O_RDONLY: c_int = 0o0000000;
-pub const O_WRONLY: c_int = 0o0000001;
-pub const O_RDWR: c_int = 0o0000002;
-pub const O_CREAT: c_int = 0o0000100;
-pub const O_EXCL: c_int = 0o0000200;
-pub const O_NOCTTY: c_int = 0o0000400;
-pub const O_TRUNC: c_int = 0o0001000;
-pub const O_APPEND: c_int = 0o0002000;
-pub const O_NONBLOCK: c_int = 0o0004000;
-pub const O_DSYNC: c_int = 0o0010000;
-pub const FASYNC: c_int = 0o0020000;
-pub const O_DIRECT: c_int = 0o0040000;
-pub const O_LARGEFILE: c_int = 0o0100000;
-pub const O_DIRECTORY: c_int = 0o0200000;
-pub const O_NOFOLLOW: c_int = 0o0400000;
-pub const O_CLOEXEC: c_int = 0o2000000;
-
-pub const _SC_PAGESIZE: c_int = 0;
+pub const F_OK: c_int = 0;
+pub const X_OK: c_int = 1;
+pub const W_OK: c_int = 2;
+pub const R_OK: c_int = 4;
+
+pub const F_ULOCK: c_int = 0;
+pub const F_LOCK: c_int = 1;
+pub const F_TLOCK: c_int = 2;
+pub const F_TEST: c_int = 3;
+
+pub const SEEK_SET: c_int = 0;
+pub const SEEK_CUR: c_int = 1;
+pub const SEEK_END: c_int = 2;
+
+pub const _SC_ARG_MAX: c_int = 0;
+pub const _SC_CHILD_MAX: c_int = 1;
+pub const _SC_CLK_TCK: c_int = 2;
+pub const _SC_NGROUPS_MAX: c_int = 3;
+pub const _SC_OPEN_MAX: c_int = 4;
+pub const _SC_STREAM_MAX: c_int = 5;
+pub const _SC_TZNAME_MAX: c_int = 6;
+pub const _SC_VERSION: c_int = 29;
+pub const _SC_PAGESIZE: c_int = 30;
+pub const _SC_PAGE_SIZE: c_int = _SC_PAGESIZE;
+pub const _SC_RTSIG_MAX: c_int = 31;
+pub const _SC_SIGQUEUE_MAX: c_int = 34;
+pub const _SC_LINE_MAX: c_int = 43;
+pub const _SC_IOV_MAX: c_int = 60;
+pub const _SC_THREADS: c_int = 67;
+pub const _SC_LOGIN_NAME_MAX: c_int = 71;
+pub const _SC_TTY_NAME_MAX: c_int = 72;
+pub const _SC_THREAD_STACK_MIN: c_int = 75;
+pub const _SC_NPROCESSORS_CONF: c_int = 83;
+pub const _SC_NPROCESSORS_ONLN: c_int = 84;
+pub const _SC_PHYS_PAGES: c_int = 85;
+pub const _SC_AVPHYS_PAGES: c_int = 86;
+pub const _SC_ATEXIT_MAX: c_int = 87;
+pub const _SC_MONOTONIC_CLOCK: c_int = 149;
+pub const _SC_SYMLOOP_MAX: c_int = 173;
+pub const _SC_HOST_NAME_MAX: c_int = 180;
+
+pub const _PC_LINK_MAX: c_int = 0;
+pub const _PC_MAX_CANON: c_int = 1;
+pub const _PC_MAX_INPUT: c_int = 2;
+pub const _PC_NAME_MAX: c_int = 3;
+pub const _PC_PATH_MAX: c_int = 4;
+pub const _PC_PIPE_BUF: c_int = 5;
+pub const _PC_CHOWN_RESTRICTED: c_int = 6;
+pub const _PC_NO_TRUNC: c_int = 7;
+pub const _PC_VDISABLE: c_int = 8;
+pub const _PC_FILESIZEBITS: c_int = 13;
+pub const _PC_SYMLINK_MAX: c_int = 19;
+pub const _PC_2_SYMLINKS: c_int = 20;
+
+const POSIX_VERSION: c_long = 200809;
+
+const NAME_MAX: usize = 255;
+pub(crate) const PATH_MAX: usize = 4096;
+
+// where execvp and posix_spawnp look when PATH isn't set
+const DEFAULT_PATH: &[u8] = b"/usr/local/bin:/bin:/usr/bin";
 
 #[no_mangle]
 pub unsafe extern "C" fn read(fd: c_int, buf: *mut c_void, count: size_t) -> ssize_t {
@@ -67,8 +125,42 @@ pub unsafe extern "C" fn write(fd: c_int, buf: *const c_void, count: size_t) ->
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn open(pathname: *const c_char, flags: c_int, mode: mode_t) -> c_int {
-    wrap_syscall!(sys::open(pathname, flags, mode)) as c_int
+pub unsafe extern "C" fn pread(
+    fd: c_int,
+    buf: *mut c_void,
+    count: size_t,
+    offset: off_t,
+) -> ssize_t {
+    wrap_syscall!(sys::pread(fd, buf, count, offset)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pwrite(
+    fd: c_int,
+    buf: *const c_void,
+    count: size_t,
+    offset: off_t,
+) -> ssize_t {
+    wrap_syscall!(sys::pwrite(fd, buf, count, offset)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn copy_file_range(
+    fd_in: c_int,
+    off_in: *mut off_t,
+    fd_out: c_int,
+    off_out: *mut off_t,
+    len: size_t,
+    flags: c_unsignedint,
+) -> ssize_t {
+    wrap_syscall!(sys::copy_file_range(
+        fd_in, off_in, fd_out, off_out, len, flags
+    )) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lseek(fd: c_int, offset: off_t, whence: c_int) -> off_t {
+    wrap_syscall!(sys::lseek(fd, offset, whence)) as off_t
 }
 
 #[no_mangle]
@@ -76,10 +168,107 @@ pub unsafe extern "C" fn close(fd: c_int) -> c_int {
     wrap_syscall!(sys::close(fd)) as c_int
 }
 
+// -1 without touching errno means there's no limit
 #[no_mangle]
 pub unsafe extern "C" fn sysconf(name: c_int) -> c_long {
     match name {
-        _SC_PAGESIZE => 4096, // TODO: get this from sysfs
+        // the kernel allows a quarter of the stack for arguments and environment
+        _SC_ARG_MAX => cmp::max(resource_limit(RLIMIT_STACK) / 4, 131072),
+        _SC_CHILD_MAX => resource_limit(RLIMIT_NPROC),
+        _SC_CLK_TCK => auxv::getauxval(AT_CLKTCK) as c_long,
+        _SC_NGROUPS_MAX => 65536,
+        _SC_OPEN_MAX => resource_limit(RLIMIT_NOFILE),
+        _SC_STREAM_MAX | _SC_TZNAME_MAX | _SC_SYMLOOP_MAX => -1,
+        _SC_VERSION | _SC_THREADS | _SC_MONOTONIC_CLOCK => POSIX_VERSION,
+        _SC_PAGESIZE => internal::page_size() as c_long,
+        _SC_RTSIG_MAX => 32,
+        _SC_SIGQUEUE_MAX => resource_limit(RLIMIT_SIGPENDING),
+        _SC_LINE_MAX => 2048,
+        _SC_IOV_MAX => 1024,
+        _SC_LOGIN_NAME_MAX => 256,
+        _SC_TTY_NAME_MAX => 32,
+        _SC_THREAD_STACK_MIN => pthread::PTHREAD_STACK_MIN as c_long,
+        _SC_NPROCESSORS_CONF => sysinfo::get_nprocs_conf() as c_long,
+        _SC_NPROCESSORS_ONLN => sysinfo::get_nprocs() as c_long,
+        _SC_PHYS_PAGES => sysinfo::get_phys_pages(),
+        _SC_AVPHYS_PAGES => sysinfo::get_avphys_pages(),
+        _SC_ATEXIT_MAX => c_int::MAX as c_long,
+        _SC_HOST_NAME_MAX => 64,
+        _ => {
+            *internal::errno() = errno::EINVAL;
+
+            -1
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getpagesize() -> c_int {
+    internal::page_size() as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn pathconf(path: *const c_char, name: c_int) -> c_long {
+    path_limit(name, |buf| statfs::sys::statfs(path, buf))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fpathconf(fd: c_int, name: c_int) -> c_long {
+    path_limit(name, |buf| statfs::sys::fstatfs(fd, buf))
+}
+
+// the soft limit, or -1 if there is none
+unsafe fn resource_limit(resource: c_int) -> c_long {
+    let mut limit = rlimit::default();
+
+    if resource::getrlimit(resource, &mut limit) == -1 || limit.rlim_cur == resource::RLIM_INFINITY
+    {
+        return -1;
+    }
+
+    limit.rlim_cur as c_long
+}
+
+unsafe fn path_limit<F: FnOnce(&mut Statfs) -> isize>(name: c_int, statfs: F) -> c_long {
+    const EXT2_SUPER_MAGIC: c_long = 0xef53;
+    const BTRFS_SUPER_MAGIC: c_long = 0x9123683e;
+    const XFS_SUPER_MAGIC: c_long = 0x58465342;
+
+    let mut buf = Statfs::default();
+    let statfs = || match ErrorNumber::from_syscall::<isize>(statfs(&mut buf)) {
+        Ok(_) => Ok(buf),
+        Err(e) => {
+            *internal::errno() = e.into_int();
+
+            Err(())
+        }
+    };
+
+    match name {
+        _PC_LINK_MAX => match statfs() {
+            // ext2 and ext3 allow 32000; ext4 allows more, but shares the magic number
+            Ok(Statfs {
+                f_type: EXT2_SUPER_MAGIC,
+                ..
+            }) => 32000,
+            Ok(Statfs {
+                f_type: BTRFS_SUPER_MAGIC,
+                ..
+            }) => 65535,
+            Ok(Statfs {
+                f_type: XFS_SUPER_MAGIC,
+                ..
+            }) => c_int::MAX as c_long,
+            Ok(_) => 127,
+            Err(_) => -1,
+        },
+        _PC_NAME_MAX => statfs().map_or(-1, |buf| buf.f_namelen),
+        _PC_MAX_CANON | _PC_MAX_INPUT => 255,
+        _PC_PATH_MAX | _PC_PIPE_BUF => 4096,
+        _PC_CHOWN_RESTRICTED | _PC_NO_TRUNC | _PC_2_SYMLINKS => 1,
+        _PC_VDISABLE => 0,
+        _PC_FILESIZEBITS => 64,
+        _PC_SYMLINK_MAX => -1,
         _ => {
             *internal::errno() = errno::EINVAL;
 
@@ -97,6 +286,405 @@ pub unsafe extern "C" fn readlink(
     wrap_syscall!(sys::readlink(pathname, buf, bufsiz)) as ssize_t
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn fork() -> pid_t {
+    // the child only gets a copy of this thread, so any lock another thread holds at the moment
+    // of the fork would stay locked in the child forever. take the short-lived ones first, in the
+    // same order as everyone else. stream locks can be held across a blocking read, so rather than
+    // wait for them, the child just resets them
+    stdlib::lock_for_fork();
+    stdio::lock_for_fork();
+    alloc::lock_for_fork();
+
+    let pid = sys::fork();
+
+    if pid == 0 {
+        pthread::reset_after_fork();
+        stdio::reset_after_fork();
+    }
+
+    alloc::unlock_after_fork();
+    stdio::unlock_after_fork();
+    stdlib::unlock_after_fork();
+
+    wrap_syscall!(pid) as pid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn execve(
+    pathname: *const c_char,
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+) -> c_int {
+    wrap_syscall!(sys::execve(pathname, argv, envp)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn execv(pathname: *const c_char, argv: *const *const c_char) -> c_int {
+    execve(pathname, argv, stdlib::environ as *const *const c_char)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn execvp(file: *const c_char, argv: *const *const c_char) -> c_int {
+    execvpe(file, argv, stdlib::environ as *const *const c_char)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn execvpe(
+    file: *const c_char,
+    argv: *const *const c_char,
+    envp: *const *const c_char,
+) -> c_int {
+    let path = stdlib::getenv(b"PATH\0".as_ptr() as *const c_char);
+
+    *internal::errno() = exec_search(file, path, |pathname| sys::execve(pathname, argv, envp));
+
+    -1
+}
+
+/// Tries `exec` on each place that `file` might be found in `path`, a
+/// colon-separated list of directories, until one fails for some reason other
+/// than there being nothing to run there. `file` is used as-is if it has a
+/// slash in it, and `path` defaults to `DEFAULT_PATH` if it's null.
+///
+/// Returns the error number to report. Touches no locks, allocates nothing
+/// and leaves errno alone, so it's safe to call in a vfork child.
+pub(crate) unsafe fn exec_search<F: FnMut(*const c_char) -> isize>(
+    file: *const c_char,
+    path: *const c_char,
+    mut exec: F,
+) -> c_int {
+    let file = slice::from_raw_parts(file as *const u8, string::strlen(file) as usize);
+
+    if file.is_empty() {
+        return errno::ENOENT;
+    }
+
+    if file.contains(&b'/') {
+        return -exec(file.as_ptr() as *const c_char) as c_int;
+    }
+
+    if file.len() > NAME_MAX {
+        return errno::ENAMETOOLONG;
+    }
+
+    let path = if path.is_null() {
+        DEFAULT_PATH
+    } else {
+        slice::from_raw_parts(path as *const u8, string::strlen(path) as usize)
+    };
+
+    let mut buf = [0u8; PATH_MAX];
+    let mut saw_eacces = false;
+
+    for dir in path.split(|&c| c == b':') {
+        // an empty entry means the current directory
+        let prefix_len = if dir.is_empty() { 0 } else { dir.len() + 1 };
+
+        if prefix_len + file.len() >= buf.len() {
+            continue;
+        }
+
+        if !dir.is_empty() {
+            buf[..dir.len()].copy_from_slice(dir);
+            buf[dir.len()] = b'/';
+        }
+
+        buf[prefix_len..prefix_len + file.len()].copy_from_slice(file);
+        buf[prefix_len + file.len()] = 0;
+
+        match -exec(buf.as_ptr() as *const c_char) as c_int {
+            errno::EACCES => saw_eacces = true,
+            errno::ENOENT | errno::ENOTDIR => (),
+            e => return e,
+        }
+    }
+
+    if saw_eacces {
+        errno::EACCES
+    } else {
+        errno::ENOENT
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn _exit(status: c_int) -> ! {
+    stdlib::_Exit(status)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getpid() -> pid_t {
+    sys::getpid() as pid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getppid() -> pid_t {
+    sys::getppid() as pid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getuid() -> uid_t {
+    sys::getuid() as uid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn geteuid() -> uid_t {
+    sys::geteuid() as uid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getgid() -> gid_t {
+    sys::getgid() as gid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getegid() -> gid_t {
+    sys::getegid() as gid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn setpgid(pid: pid_t, pgid: pid_t) -> c_int {
+    wrap_syscall!(sys::setpgid(pid, pgid)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getpgid(pid: pid_t) -> pid_t {
+    wrap_syscall!(sys::getpgid(pid)) as pid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn setsid() -> pid_t {
+    wrap_syscall!(sys::setsid()) as pid_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn access(pathname: *const c_char, mode: c_int) -> c_int {
+    faccessat(AT_FDCWD, pathname, mode, 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn faccessat(
+    dirfd: c_int,
+    pathname: *const c_char,
+    mode: c_int,
+    flags: c_int,
+) -> c_int {
+    if flags == 0 {
+        return wrap_syscall!(sys::faccessat(dirfd, pathname, mode)) as c_int;
+    }
+
+    let result = sys::faccessat2(dirfd, pathname, mode, flags);
+
+    // faccessat2 is new in Linux 5.8. without it, AT_EACCESS only matters if the effective ids
+    // differ from the real ones
+    if result == -(errno::ENOSYS as isize)
+        && flags == AT_EACCESS
+        && sys::geteuid() == sys::getuid()
+        && sys::getegid() == sys::getgid()
+    {
+        return wrap_syscall!(sys::faccessat(dirfd, pathname, mode)) as c_int;
+    }
+
+    wrap_syscall!(result) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn rmdir(pathname: *const c_char) -> c_int {
+    unlinkat(AT_FDCWD, pathname, AT_REMOVEDIR)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn unlink(pathname: *const c_char) -> c_int {
+    unlinkat(AT_FDCWD, pathname, 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn unlinkat(dirfd: c_int, pathname: *const c_char, flags: c_int) -> c_int {
+    wrap_syscall!(sys::unlinkat(dirfd, pathname, flags)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn link(oldpath: *const c_char, newpath: *const c_char) -> c_int {
+    linkat(AT_FDCWD, oldpath, AT_FDCWD, newpath, 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn linkat(
+    olddirfd: c_int,
+    oldpath: *const c_char,
+    newdirfd: c_int,
+    newpath: *const c_char,
+    flags: c_int,
+) -> c_int {
+    wrap_syscall!(sys::linkat(olddirfd, oldpath, newdirfd, newpath, flags)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn symlink(target: *const c_char, linkpath: *const c_char) -> c_int {
+    symlinkat(target, AT_FDCWD, linkpath)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn symlinkat(
+    target: *const c_char,
+    newdirfd: c_int,
+    linkpath: *const c_char,
+) -> c_int {
+    wrap_syscall!(sys::symlinkat(target, newdirfd, linkpath)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn readlinkat(
+    dirfd: c_int,
+    pathname: *const c_char,
+    buf: *mut c_char,
+    bufsiz: size_t,
+) -> ssize_t {
+    wrap_syscall!(sys::readlinkat(dirfd, pathname, buf, bufsiz)) as ssize_t
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn chown(pathname: *const c_char, owner: uid_t, group: gid_t) -> c_int {
+    fchownat(AT_FDCWD, pathname, owner, group, 0)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lchown(pathname: *const c_char, owner: uid_t, group: gid_t) -> c_int {
+    fchownat(AT_FDCWD, pathname, owner, group, AT_SYMLINK_NOFOLLOW)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fchown(fd: c_int, owner: uid_t, group: gid_t) -> c_int {
+    wrap_syscall!(sys::fchown(fd, owner, group)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fchownat(
+    dirfd: c_int,
+    pathname: *const c_char,
+    owner: uid_t,
+    group: gid_t,
+    flags: c_int,
+) -> c_int {
+    wrap_syscall!(sys::fchownat(dirfd, pathname, owner, group, flags)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn truncate(path: *const c_char, length: off_t) -> c_int {
+    wrap_syscall!(sys::truncate(path, length)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ftruncate(fd: c_int, length: off_t) -> c_int {
+    wrap_syscall!(sys::ftruncate(fd, length)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lockf(fd: c_int, cmd: c_int, len: off_t) -> c_int {
+    // lockf locks are just fcntl locks on [current offset, current offset + len)
+    let mut lock = flock {
+        l_type: F_WRLCK,
+        l_whence: SEEK_CUR as c_short,
+        l_start: 0,
+        l_len: len,
+        l_pid: 0,
+    };
+
+    let fcntl_cmd = match cmd {
+        F_TEST => {
+            lock.l_type = F_RDLCK;
+            let result = fcntl::sys::fcntl(fd, F_GETLK, &mut lock as *mut flock as c_long);
+
+            if result < 0 {
+                return wrap_syscall!(result) as c_int;
+            } else if lock.l_type == F_UNLCK || lock.l_pid == getpid() {
+                return 0;
+            }
+
+            *internal::errno() = errno::EACCES;
+
+            return -1;
+        }
+        F_ULOCK => {
+            lock.l_type = F_UNLCK;
+
+            F_SETLK
+        }
+        F_TLOCK => F_SETLK,
+        F_LOCK => F_SETLKW,
+        _ => {
+            *internal::errno() = errno::EINVAL;
+
+            return -1;
+        }
+    };
+
+    wrap_syscall!(fcntl::sys::fcntl(
+        fd,
+        fcntl_cmd,
+        &mut lock as *mut flock as c_long
+    )) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn getcwd(buf: *mut c_char, size: size_t) -> *mut c_char {
+    if !buf.is_null() {
+        if size == 0 {
+            *internal::errno() = errno::EINVAL;
+
+            return ptr::null_mut();
+        }
+
+        return match sys::getcwd(buf, size) {
+            len if len < 0 => {
+                *internal::errno() = -len as c_int;
+
+                ptr::null_mut()
+            }
+            // the kernel says "(unreachable)" when we're outside the current root
+            _ if *buf != b'/' as c_char => {
+                *internal::errno() = errno::ENOENT;
+
+                ptr::null_mut()
+            }
+            _ => buf,
+        };
+    }
+
+    // a null buf asks us to allocate at least size bytes, or as many as it takes if size is 0
+    let mut path = [0 as c_char; PATH_MAX];
+
+    if getcwd(path.as_mut_ptr(), PATH_MAX as size_t).is_null() {
+        return ptr::null_mut();
+    }
+
+    let len = string::strlen(path.as_ptr()) + 1;
+
+    if size != 0 && size < len {
+        *internal::errno() = errno::ERANGE;
+
+        return ptr::null_mut();
+    }
+
+    let buf = stdlib::malloc(cmp::max(size, len)) as *mut c_char;
+
+    if !buf.is_null() {
+        ptr::copy_nonoverlapping(path.as_ptr(), buf, len as usize);
+    }
+
+    buf
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn chdir(path: *const c_char) -> c_int {
+    wrap_syscall!(sys::chdir(path)) as c_int
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn fchdir(fd: c_int) -> c_int {
+    wrap_syscall!(sys::fchdir(fd)) as c_int
+}
+
 pub(crate) mod sys {
     use super::*;
 
@@ -108,14 +696,162 @@ pub(crate) mod sys {
         syscall!(1, fd as isize, buf as isize, count as isize)
     }
 
-    pub(crate) unsafe fn open(pathname: *const c_char, flags: c_int, mode: mode_t) -> isize {
-        syscall!(2, pathname as isize, flags as isize, mode as isize)
+    pub(crate) unsafe fn pread(fd: c_int, buf: *mut c_void, count: size_t, offset: off_t) -> isize {
+        syscall!(
+            17,
+            fd as isize,
+            buf as isize,
+            count as isize,
+            offset as isize
+        )
+    }
+
+    pub(crate) unsafe fn pwrite(
+        fd: c_int,
+        buf: *const c_void,
+        count: size_t,
+        offset: off_t,
+    ) -> isize {
+        syscall!(
+            18,
+            fd as isize,
+            buf as isize,
+            count as isize,
+            offset as isize
+        )
+    }
+
+    pub(crate) unsafe fn copy_file_range(
+        fd_in: c_int,
+        off_in: *mut off_t,
+        fd_out: c_int,
+        off_out: *mut off_t,
+        len: size_t,
+        flags: c_unsignedint,
+    ) -> isize {
+        syscall!(
+            326,
+            fd_in as isize,
+            off_in as isize,
+            fd_out as isize,
+            off_out as isize,
+            len as isize,
+            flags as isize
+        )
+    }
+
+    pub(crate) unsafe fn lseek(fd: c_int, offset: off_t, whence: c_int) -> isize {
+        syscall!(8, fd as isize, offset as isize, whence as isize)
     }
 
     pub(crate) unsafe fn close(fd: c_int) -> isize {
         syscall!(3, fd as isize)
     }
 
+    pub(crate) unsafe fn fchdir(fd: c_int) -> isize {
+        syscall!(81, fd as isize)
+    }
+
+    pub(crate) unsafe fn faccessat(dirfd: c_int, pathname: *const c_char, mode: c_int) -> isize {
+        syscall!(269, dirfd as isize, pathname as isize, mode as isize)
+    }
+
+    pub(crate) unsafe fn faccessat2(
+        dirfd: c_int,
+        pathname: *const c_char,
+        mode: c_int,
+        flags: c_int,
+    ) -> isize {
+        syscall!(
+            439,
+            dirfd as isize,
+            pathname as isize,
+            mode as isize,
+            flags as isize
+        )
+    }
+
+    pub(crate) unsafe fn unlinkat(dirfd: c_int, pathname: *const c_char, flags: c_int) -> isize {
+        syscall!(263, dirfd as isize, pathname as isize, flags as isize)
+    }
+
+    pub(crate) unsafe fn linkat(
+        olddirfd: c_int,
+        oldpath: *const c_char,
+        newdirfd: c_int,
+        newpath: *const c_char,
+        flags: c_int,
+    ) -> isize {
+        syscall!(
+            265,
+            olddirfd as isize,
+            oldpath as isize,
+            newdirfd as isize,
+            newpath as isize,
+            flags as isize
+        )
+    }
+
+    pub(crate) unsafe fn symlinkat(
+        target: *const c_char,
+        newdirfd: c_int,
+        linkpath: *const c_char,
+    ) -> isize {
+        syscall!(266, target as isize, newdirfd as isize, linkpath as isize)
+    }
+
+    pub(crate) unsafe fn readlinkat(
+        dirfd: c_int,
+        pathname: *const c_char,
+        buf: *mut c_char,
+        bufsiz: size_t,
+    ) -> isize {
+        syscall!(
+            267,
+            dirfd as isize,
+            pathname as isize,
+            buf as isize,
+            bufsiz as isize
+        )
+    }
+
+    pub(crate) unsafe fn fchown(fd: c_int, owner: uid_t, group: gid_t) -> isize {
+        syscall!(93, fd as isize, owner as isize, group as isize)
+    }
+
+    pub(crate) unsafe fn fchownat(
+        dirfd: c_int,
+        pathname: *const c_char,
+        owner: uid_t,
+        group: gid_t,
+        flags: c_int,
+    ) -> isize {
+        syscall!(
+            260,
+            dirfd as isize,
+            pathname as isize,
+            owner as isize,
+            group as isize,
+            flags as isize
+        )
+    }
+
+    pub(crate) unsafe fn truncate(path: *const c_char, length: off_t) -> isize {
+        syscall!(76, path as isize, length as isize)
+    }
+
+    pub(crate) unsafe fn ftruncate(fd: c_int, length: off_t) -> isize {
+        syscall!(77, fd as isize, length as isize)
+    }
+
+    pub(crate) unsafe fn getcwd(buf: *mut c_char, size: size_t) -> isize {
+        syscall!(79, buf as isize, size as isize)
+    }
+
+    pub(crate) unsafe fn chdir(path: *const c_char) -> isize {
+        syscall!(80, path as isize)
+    }
+
     pub(crate) unsafe fn readlink(
         pathname: *const c_char,
         buf: *mut c_char,
@@ -123,4 +859,60 @@ pub(crate) mod sys {
     ) -> isize {
         syscall!(89, pathname as isize, buf as isize, bufsiz as isize)
     }
+
+    pub(crate) unsafe fn fork() -> isize {
+        syscall!(57)
+    }
+
+    pub(crate) unsafe fn execve(
+        pathname: *const c_char,
+        argv: *const *const c_char,
+        envp: *const *const c_char,
+    ) -> isize {
+        syscall!(59, pathname as isize, argv as isize, envp as isize)
+    }
+
+    pub(crate) unsafe fn getpid() -> isize {
+        syscall!(39)
+    }
+
+    pub(crate) unsafe fn getppid() -> isize {
+        syscall!(110)
+    }
+
+    pub(crate) unsafe fn getuid() -> isize {
+        syscall!(102)
+    }
+
+    pub(crate) unsafe fn geteuid() -> isize {
+        syscall!(107)
+    }
+
+    pub(crate) unsafe fn getgid() -> isize {
+        syscall!(104)
+    }
+
+    pub(crate) unsafe fn getegid() -> isize {
+        syscall!(108)
+    }
+
+    pub(crate) unsafe fn setuid(uid: uid_t) -> isize {
+        syscall!(105, uid as isize)
+    }
+
+    pub(crate) unsafe fn setgid(gid: gid_t) -> isize {
+        syscall!(106, gid as isize)
+    }
+
+    pub(crate) unsafe fn setpgid(pid: pid_t, pgid: pid_t) -> isize {
+        syscall!(109, pid as isize, pgid as isize)
+    }
+
+    pub(crate) unsafe fn getpgid(pid: pid_t) -> isize {
+        syscall!(121, pid as isize)
+    }
+
+    pub(crate) unsafe fn setsid() -> isize {
+        syscall!(112)
+    }
 }
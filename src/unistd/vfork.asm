@@ -0,0 +1,39 @@
+; Copyright (C) 2020 Gregory Meyer
+;
+; This program is free software: you can redistribute it and/or modify
+; it under the terms of the GNU Affero General Public License as published by
+; the Free Software Foundation, either version 3 of the License, or
+; (at your option) any later version.
+;
+; This program is distributed in the hope that it will be useful,
+; but WITHOUT ANY WARRANTY; without even the implied warranty of
+; MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+; GNU Affero General Public License for more details.
+;
+; You should have received a copy of the GNU Affero General Public License
+; along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+global vfork
+extern __KNS_errno
+
+section .text
+; the child runs on our stack until it execs or exits, and its ret pops our return address off
+; it. keep the return address in a register across the syscall and put it back afterwards
+vfork:
+    pop rdx
+    mov eax, 58
+    syscall
+    push rdx
+
+    cmp rax, -4095
+    jae .error
+    ret
+
+.error:
+    neg eax
+    push rax
+    call __KNS_errno
+    pop rcx
+    mov [rax], ecx
+    mov eax, -1
+    ret
@@ -36,7 +36,7 @@ macro_rules! syscall {
         $crate::syscall4($rax, $rdi, $rsi, $rdx, $r10)
     };
     ($rax:expr, $rdi:expr, $rsi:expr, $rdx:expr, $r10:expr, $r8:expr) => {
-        $crate::syscall5($rax, $rdi, $rsi, $rdx, $r8, $r10)
+        $crate::syscall5($rax, $rdi, $rsi, $rdx, $r10, $r8)
     };
     ($rax:expr, $rdi:expr, $rsi:expr, $rdx:expr, $r10:expr, $r8:expr, $r9:expr) => {
         $crate::syscall6($rax, $rdi, $rsi, $rdx, $r10, $r8, $r9)
@@ -197,3 +197,39 @@ pub(crate) mod arch {
         rax
     }
 }
+
+#[cfg(test)]
+mod tests {
+    // statx(2) takes its mask in r10 and its buffer in r8, so it fails with EFAULT or EINVAL
+    // if the fourth and fifth arguments are passed in the wrong registers
+    #[test]
+    fn syscall5_passes_r10_then_r8() {
+        const SYS_STATX: isize = 332;
+        const AT_FDCWD: isize = -100;
+        const STATX_TYPE: u32 = 0x1;
+        const S_IFMT: u16 = 0o170000;
+        const S_IFDIR: u16 = 0o040000;
+
+        // struct statx is 256 bytes; stx_mask is the first u32 and stx_mode the u16 at 28
+        let mut buf = [0u64; 32];
+
+        let rax = unsafe {
+            syscall!(
+                SYS_STATX,
+                AT_FDCWD,
+                b"/\0".as_ptr() as isize,
+                0,
+                STATX_TYPE as isize,
+                buf.as_mut_ptr() as isize
+            )
+        };
+
+        assert_eq!(rax, 0);
+
+        let mask = buf[0] as u32;
+        let mode = (buf[3] >> 32) as u16;
+
+        assert_ne!(mask & STATX_TYPE, 0);
+        assert_eq!(mode & S_IFMT, S_IFDIR);
+    }
+}
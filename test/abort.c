@@ -0,0 +1,64 @@
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "check.h"
+
+// shared with the children, so they can tell us their handler ran
+static volatile int *handler_calls;
+
+static void returning_handler(int sig) {
+  (void)sig;
+
+  ++*handler_calls;
+}
+
+static void block_abrt(void) {
+  sigset_t set;
+  sigemptyset(&set);
+  sigaddset(&set, SIGABRT);
+  sigprocmask(SIG_BLOCK, &set, NULL);
+}
+
+static int aborted_by_signal(void (*setup)(void)) {
+  pid_t pid = fork();
+
+  if (pid == 0) {
+    if (setup) {
+      setup();
+    }
+
+    abort();
+  }
+
+  int status;
+
+  if (waitpid(pid, &status, 0) != pid) {
+    return 0;
+  }
+
+  return WIFSIGNALED(status) && WTERMSIG(status) == SIGABRT;
+}
+
+static void catch_abrt(void) { signal(SIGABRT, returning_handler); }
+
+static void ignore_abrt(void) { signal(SIGABRT, SIG_IGN); }
+
+int main(void) {
+  handler_calls = mmap(NULL, sizeof(int), PROT_READ | PROT_WRITE,
+                       MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+  CHECK(handler_calls != MAP_FAILED);
+
+  CHECK(aborted_by_signal(NULL));
+  CHECK(aborted_by_signal(block_abrt));
+  CHECK(aborted_by_signal(ignore_abrt));
+  CHECK(aborted_by_signal(catch_abrt));
+
+  // abort carried on after the handler returned, without calling it again
+  CHECK(*handler_calls == 1);
+
+  return check_status();
+}
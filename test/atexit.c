@@ -0,0 +1,66 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "check.h"
+
+extern int __cxa_atexit(void (*function)(void *), void *arg, void *dso_handle);
+extern void __cxa_finalize(void *dso_handle);
+
+static char dso;
+
+static char order[64];
+static size_t order_len = 0;
+
+static void record(char c) { order[order_len++] = c; }
+
+static void a(void) { record('a'); }
+static void c(void) { record('c'); }
+static void never(void) { record('!'); }
+
+// registers c while exit is running, which runs it next
+static void b(void) {
+  record('b');
+  CHECK(atexit(c) == 0);
+}
+
+static void with_arg(void *arg) { record(*(const char *)arg); }
+
+static void many(void) { record('m'); }
+
+// registered first, so it runs last
+static void check(void) {
+  static const char expected[] = "ebcdmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmmma";
+
+  CHECK(order_len == sizeof(expected) - 1);
+  CHECK(memcmp(order, expected, sizeof(expected) - 1) == 0);
+
+  _Exit(check_status());
+}
+
+int main(void) {
+  static const char d = 'd';
+  static const char e = 'e';
+
+  CHECK(atexit(check) == 0);
+  CHECK(atexit(a) == 0);
+  CHECK(__cxa_atexit(with_arg, (void *)&e, &dso) == 0);
+
+  // enough to spill past any fixed-size table
+  for (int i = 0; i < 40; ++i) {
+    CHECK(atexit(many) == 0);
+  }
+
+  CHECK(__cxa_atexit(with_arg, (void *)&d, NULL) == 0);
+  CHECK(atexit(b) == 0);
+
+  // finds handlers for a DSO however long ago they were registered, and
+  // exit doesn't run them again
+  __cxa_finalize(&dso);
+  CHECK(order_len == 1);
+
+  // quick_exit handlers are kept apart and never run by exit
+  CHECK(at_quick_exit(never) == 0);
+
+  exit(EXIT_FAILURE);
+}
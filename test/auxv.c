@@ -0,0 +1,37 @@
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/auxv.h>
+
+#include "check.h"
+
+// only readable if the PT_TLS segment was found through AT_PHDR
+static _Thread_local int initialized_tls = 42;
+
+int main(void) {
+  CHECK(initialized_tls == 42);
+
+  const unsigned long page_size = getauxval(AT_PAGESZ);
+  CHECK(page_size >= 4096 && (page_size & (page_size - 1)) == 0);
+
+  CHECK(getauxval(AT_PHDR) != 0);
+  CHECK(getauxval(AT_PHNUM) != 0);
+  CHECK(getauxval(AT_ENTRY) != 0);
+  CHECK(getauxval(AT_SECURE) == 0);
+
+  // sixteen random bytes from the kernel
+  CHECK(getauxval(AT_RANDOM) != 0);
+
+  const char *const execfn = (const char *)getauxval(AT_EXECFN);
+  CHECK(execfn != NULL && strlen(execfn) > 0);
+
+  errno = 0;
+  CHECK(getauxval(AT_NULL) == 0);
+  CHECK(errno == ENOENT);
+
+  errno = 0;
+  CHECK(getauxval(12345) == 0);
+  CHECK(errno == ENOENT);
+
+  return check_status();
+}
@@ -0,0 +1,26 @@
+#ifndef __KNS_TEST_CHECK_H
+#define __KNS_TEST_CHECK_H
+
+#include <stdio.h>
+
+static int num_failed = 0;
+
+// reports a failed condition and carries on with the rest of the test
+#define CHECK(cond)                                                            \
+  do {                                                                         \
+    if (!(cond)) {                                                             \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+      ++num_failed;                                                            \
+    }                                                                          \
+  } while (0)
+
+// prints how many checks failed and returns the matching exit status. stdout
+// is flushed, so it's fine to _Exit with the result
+static int check_status(void) {
+  printf("%d failed\n", num_failed);
+  fflush(stdout);
+
+  return num_failed != 0;
+}
+
+#endif
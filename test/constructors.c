@@ -0,0 +1,57 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "check.h"
+
+static char order[16];
+static size_t order_len = 0;
+
+static void record(char c) { order[order_len++] = c; }
+
+static int order_is(const char *expected) {
+  return order_len == strlen(expected) &&
+         memcmp(order, expected, order_len) == 0;
+}
+
+static void preinit(void) { record('p'); }
+
+__attribute__((section(".preinit_array"), used)) static void (
+    *const preinit_entry)(void) = preinit;
+
+// constructors get the same arguments as main
+__attribute__((constructor(101))) static void first(int argc, char **argv) {
+  record('1');
+  CHECK(argc >= 1 && argv[0] != NULL);
+}
+
+__attribute__((constructor(102))) static void second(void) { record('2'); }
+
+__attribute__((constructor)) static void unprioritized(void) { record('c'); }
+
+static void handler(void) { record('x'); }
+
+__attribute__((destructor)) static void unprioritized_destructor(void) {
+  record('C');
+}
+
+__attribute__((destructor(102))) static void second_destructor(void) {
+  record('B');
+}
+
+// destructors run in the reverse order of constructors, so this one is last
+__attribute__((destructor(101))) static void first_destructor(void) {
+  CHECK(order_is("xCB"));
+
+  _Exit(check_status());
+}
+
+int main(void) {
+  CHECK(order_is("p12c"));
+  order_len = 0;
+
+  // registered after the destructors, so it runs before them
+  CHECK(atexit(handler) == 0);
+
+  return EXIT_FAILURE;
+}
@@ -0,0 +1,188 @@
+#include <dirent.h>
+#include <errno.h>
+#include <ftw.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include "check.h"
+
+#define DIR_PATH "/tmp/kns-dirent-test"
+
+static int same(const char *a, const char *b) {
+  size_t len = strlen(a);
+
+  return len == strlen(b) && memcmp(a, b, len) == 0;
+}
+
+static int is_dot(const char *name) {
+  return same(name, ".") || same(name, "..");
+}
+
+static int touch(const char *path) {
+  FILE *f = fopen(path, "w");
+
+  return f && fclose(f) == 0;
+}
+
+static int no_dots(const struct dirent *entry) {
+  return !is_dot(entry->d_name);
+}
+
+static int num_visited;
+static int b_seen_at;
+static int c_seen_at;
+
+static int visit(const char *fpath, const struct stat *sb, int typeflag,
+                 struct FTW *ftwbuf) {
+  (void)sb;
+  ++num_visited;
+
+  if (same(fpath, DIR_PATH)) {
+    CHECK(typeflag == FTW_DP);
+    CHECK(ftwbuf->level == 0);
+    CHECK(same(fpath + ftwbuf->base, "kns-dirent-test"));
+  } else if (same(fpath, DIR_PATH "/b")) {
+    CHECK(typeflag == FTW_DP);
+    CHECK(ftwbuf->level == 1);
+    b_seen_at = num_visited;
+  } else if (same(fpath, DIR_PATH "/b/c")) {
+    CHECK(typeflag == FTW_F);
+    CHECK(ftwbuf->level == 2);
+    CHECK(same(fpath + ftwbuf->base, "c"));
+    c_seen_at = num_visited;
+  } else if (same(fpath, DIR_PATH "/l")) {
+    CHECK(typeflag == FTW_SL);
+  }
+
+  return 0;
+}
+
+static int remove_entry(const char *fpath, const struct stat *sb, int typeflag,
+                        struct FTW *ftwbuf) {
+  (void)sb;
+  (void)ftwbuf;
+
+  return typeflag == FTW_DP ? rmdir(fpath) : unlink(fpath);
+}
+
+static int count(const char *fpath, const struct stat *sb, int typeflag) {
+  (void)fpath;
+  (void)sb;
+  (void)typeflag;
+
+  return ++num_visited;
+}
+
+int main(void) {
+  nftw(DIR_PATH, remove_entry, 8, FTW_PHYS | FTW_DEPTH);
+
+  CHECK(mkdir(DIR_PATH, 0755) == 0);
+  CHECK(mkdir(DIR_PATH "/b", 0755) == 0);
+  CHECK(mkdir(DIR_PATH "/d", 0755) == 0);
+  CHECK(touch(DIR_PATH "/a"));
+  CHECK(touch(DIR_PATH "/b/c"));
+  CHECK(symlink("a", DIR_PATH "/l") == 0);
+
+  DIR *dir = opendir(DIR_PATH);
+  CHECK(dir != NULL);
+  CHECK(dirfd(dir) >= 0);
+
+  int num_entries = 0;
+  long second = -1;
+  char second_name[256] = {0};
+  struct dirent *entry;
+
+  while ((entry = readdir(dir))) {
+    if (num_entries == 1) {
+      memcpy(second_name, entry->d_name, strlen(entry->d_name) + 1);
+    }
+
+    if (num_entries == 0) {
+      second = telldir(dir);
+    }
+
+    if (same(entry->d_name, "a")) {
+      CHECK(entry->d_type == DT_REG);
+    } else if (same(entry->d_name, "b")) {
+      CHECK(entry->d_type == DT_DIR);
+    } else if (same(entry->d_name, "l")) {
+      CHECK(entry->d_type == DT_LNK);
+    }
+
+    ++num_entries;
+  }
+
+  CHECK(num_entries == 6);
+
+  seekdir(dir, second);
+  CHECK(telldir(dir) == second);
+  entry = readdir(dir);
+  CHECK(entry && same(entry->d_name, second_name));
+
+  rewinddir(dir);
+
+  struct dirent buf;
+  struct dirent *result;
+  int num_read = 0;
+
+  while (readdir_r(dir, &buf, &result) == 0 && result) {
+    CHECK(result == &buf);
+    ++num_read;
+  }
+
+  CHECK(num_read == 6);
+  CHECK(closedir(dir) == 0);
+
+  errno = 0;
+  CHECK(opendir(DIR_PATH "/a") == NULL);
+  CHECK(errno == ENOTDIR);
+
+  errno = 0;
+  CHECK(opendir(DIR_PATH "/nope") == NULL);
+  CHECK(errno == ENOENT);
+
+  struct dirent **names;
+  int num_names = scandir(DIR_PATH, &names, no_dots, alphasort);
+  CHECK(num_names == 4);
+
+  if (num_names == 4) {
+    CHECK(same(names[0]->d_name, "a"));
+    CHECK(same(names[1]->d_name, "b"));
+    CHECK(same(names[2]->d_name, "d"));
+    CHECK(same(names[3]->d_name, "l"));
+  }
+
+  for (int i = 0; i < num_names; ++i) {
+    free(names[i]);
+  }
+
+  free(names);
+
+  CHECK(scandir(DIR_PATH "/nope", &names, NULL, NULL) == -1);
+
+  num_visited = 0;
+  CHECK(nftw(DIR_PATH "/", visit, 8, FTW_PHYS | FTW_DEPTH) == 0);
+  CHECK(num_visited == 6);
+  CHECK(c_seen_at != 0 && c_seen_at < b_seen_at);
+
+  num_visited = 0;
+  CHECK(nftw(DIR_PATH, visit, 8, FTW_PHYS | FTW_DEPTH | FTW_CHDIR) == 0);
+  CHECK(num_visited == 6);
+
+  // a callback's nonzero return stops the walk and comes back out
+  num_visited = 0;
+  CHECK(ftw(DIR_PATH, count, 8) == 1);
+  CHECK(num_visited == 1);
+
+  errno = 0;
+  CHECK(ftw(DIR_PATH "/nope", count, 8) == -1);
+  CHECK(errno == ENOENT);
+
+  CHECK(nftw(DIR_PATH, remove_entry, 8, FTW_PHYS | FTW_DEPTH) == 0);
+  CHECK(opendir(DIR_PATH) == NULL);
+
+  return check_status();
+}
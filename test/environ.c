@@ -0,0 +1,89 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include "check.h"
+
+static int string_is(const char *s, const char *expected) {
+  return s != NULL && strlen(s) == strlen(expected) &&
+         memcmp(s, expected, strlen(expected)) == 0;
+}
+
+static int count_entries(const char *entry) {
+  int count = 0;
+
+  for (char **e = environ; e != NULL && *e != NULL; ++e) {
+    count += string_is(*e, entry);
+  }
+
+  return count;
+}
+
+int main(int argc, char *argv[], char *envp[]) {
+  (void)argc;
+  (void)argv;
+
+  CHECK(environ == envp);
+
+  CHECK(setenv("KNS_ENVIRON_TEST", "first", 0) == 0);
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST"), "first"));
+  CHECK(string_is(secure_getenv("KNS_ENVIRON_TEST"), "first"));
+  CHECK(count_entries("KNS_ENVIRON_TEST=first") == 1);
+
+  // the array from the kernel was copied, not written to
+  CHECK(environ != envp);
+
+  CHECK(setenv("KNS_ENVIRON_TEST", "second", 0) == 0);
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST"), "first"));
+  CHECK(setenv("KNS_ENVIRON_TEST", "second", 1) == 0);
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST"), "second"));
+  CHECK(count_entries("KNS_ENVIRON_TEST=second") == 1);
+
+  // a prefix of a name isn't a match
+  CHECK(getenv("KNS_ENVIRON") == NULL);
+  CHECK(getenv("KNS_ENVIRON_TEST_") == NULL);
+
+  CHECK(unsetenv("KNS_ENVIRON_TEST") == 0);
+  CHECK(getenv("KNS_ENVIRON_TEST") == NULL);
+  CHECK(unsetenv("KNS_ENVIRON_TEST") == 0);
+
+  // putenv doesn't copy the string
+  static char entry[] = "KNS_ENVIRON_TEST=put";
+  CHECK(putenv(entry) == 0);
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST"), "put"));
+  entry[17] = 'P';
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST"), "Put"));
+
+  // enough to grow the array a few times
+  for (int i = 0; i < 100; ++i) {
+    char name[32];
+    snprintf(name, sizeof(name), "KNS_ENVIRON_TEST_%d", i);
+    CHECK(setenv(name, name, 1) == 0);
+  }
+
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST_0"), "KNS_ENVIRON_TEST_0"));
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST_99"), "KNS_ENVIRON_TEST_99"));
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST"), "Put"));
+
+  errno = 0;
+  CHECK(setenv("", "value", 1) == -1 && errno == EINVAL);
+  errno = 0;
+  CHECK(setenv("A=B", "value", 1) == -1 && errno == EINVAL);
+  errno = 0;
+  CHECK(setenv(NULL, "value", 1) == -1 && errno == EINVAL);
+  errno = 0;
+  CHECK(unsetenv("A=B") == -1 && errno == EINVAL);
+
+  CHECK(clearenv() == 0);
+  CHECK(environ == NULL);
+  CHECK(getenv("KNS_ENVIRON_TEST") == NULL);
+
+  CHECK(setenv("KNS_ENVIRON_TEST", "again", 1) == 0);
+  CHECK(string_is(getenv("KNS_ENVIRON_TEST"), "again"));
+  CHECK(environ != NULL && environ[1] == NULL);
+
+  return check_status();
+}
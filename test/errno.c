@@ -0,0 +1,140 @@
+#include <err.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "check.h"
+
+#define PATH "/tmp/kns-errno-test"
+
+static int same(const char *lhs, const char *rhs) {
+  return lhs != NULL && rhs != NULL && strlen(lhs) == strlen(rhs) &&
+         memcmp(lhs, rhs, strlen(lhs)) == 0;
+}
+
+static void check_messages(void) {
+  CHECK(same(strerror(0), "Success"));
+  CHECK(same(strerror(EPERM), "Operation not permitted"));
+  CHECK(same(strerror(ENOENT), "No such file or directory"));
+  CHECK(same(strerror(EAGAIN), "Resource temporarily unavailable"));
+  CHECK(same(strerror(ETIMEDOUT), "Connection timed out"));
+  CHECK(same(strerror(EHWPOISON), "Memory page has hardware error"));
+  CHECK(same(strerror(41), "Unknown error 41"));
+  CHECK(same(strerror(-5), "Unknown error -5"));
+  CHECK(same(strerror(4096), "Unknown error 4096"));
+
+  CHECK(EWOULDBLOCK == EAGAIN && EDEADLOCK == EDEADLK && ENOTSUP == EOPNOTSUPP);
+
+  CHECK(same(strerrorname_np(ENOENT), "ENOENT"));
+  CHECK(same(strerrorname_np(E2BIG), "E2BIG"));
+  CHECK(same(strerrorname_np(EWOULDBLOCK), "EAGAIN"));
+  CHECK(strerrorname_np(41) == NULL);
+  CHECK(same(strerrordesc_np(EINVAL), "Invalid argument"));
+  CHECK(strerrordesc_np(1000) == NULL);
+}
+
+static void check_strerror_r(void) {
+  char buf[64];
+  CHECK(strerror_r(EACCES, buf, sizeof(buf)) == 0);
+  CHECK(same(buf, "Permission denied"));
+
+  char small[8];
+  CHECK(strerror_r(ENOENT, small, sizeof(small)) == ERANGE);
+  CHECK(same(small, "No such"));
+
+  CHECK(strerror_r(1000, buf, sizeof(buf)) == EINVAL);
+  CHECK(same(buf, "Unknown error 1000"));
+}
+
+// runs report with stderr pointed at a file and returns what it wrote
+static const char *captured(void (*report)(void)) {
+  static char buf[256];
+  memset(buf, 0, sizeof(buf));
+
+  int fd = open(PATH, O_CREAT | O_TRUNC | O_RDWR, 0644);
+  int saved = dup(STDERR_FILENO);
+  CHECK(fd >= 0 && saved >= 0);
+
+  fflush(stderr);
+  dup2(fd, STDERR_FILENO);
+  report();
+  fflush(stderr);
+  dup2(saved, STDERR_FILENO);
+  close(saved);
+
+  CHECK(pread(fd, buf, sizeof(buf) - 1, 0) >= 0);
+  close(fd);
+
+  return buf;
+}
+
+static void perror_with_prefix(void) {
+  errno = ENOENT;
+  perror("prefix");
+}
+
+static void perror_without_prefix(void) {
+  errno = EISDIR;
+  perror(NULL);
+}
+
+static void warn_with_errno(void) {
+  errno = EACCES;
+  warn("opening %s", "thing");
+}
+
+static void warnx_without_errno(void) {
+  errno = EACCES;
+  warnx("%d things", 3);
+}
+
+static void check_reports(void) {
+  char expected[256];
+
+  CHECK(same(captured(perror_with_prefix),
+             "prefix: No such file or directory\n"));
+  CHECK(same(captured(perror_without_prefix), "Is a directory\n"));
+
+  snprintf(expected, sizeof(expected), "%s: opening thing: Permission denied\n",
+           program_invocation_short_name);
+  CHECK(same(captured(warn_with_errno), expected));
+
+  snprintf(expected, sizeof(expected), "%s: 3 things\n",
+           program_invocation_short_name);
+  CHECK(same(captured(warnx_without_errno), expected));
+}
+
+static void check_err_exits(void) {
+  pid_t pid = fork();
+
+  if (pid == 0) {
+    int fd = open("/dev/null", O_WRONLY);
+    dup2(fd, STDERR_FILENO);
+    errx(42, "bye");
+  }
+
+  int status;
+  CHECK(waitpid(pid, &status, 0) == pid);
+  CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 42);
+}
+
+static void check_open_failure(void) {
+  // used to take the whole process down
+  errno = 0;
+  CHECK(fopen("/tmp/kns-errno-test-does/not/exist", "r") == NULL);
+  CHECK(errno == ENOENT);
+}
+
+int main(void) {
+  check_messages();
+  check_strerror_r();
+  check_reports();
+  check_err_exits();
+  check_open_failure();
+  unlink(PATH);
+
+  return check_status();
+}
@@ -0,0 +1,167 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/file.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "check.h"
+
+#define DIR_PATH "/tmp/kns-fcntl-test"
+#define FILE_PATH DIR_PATH "/file"
+
+static int is_cloexec(int fd) { return fcntl(fd, F_GETFD) & FD_CLOEXEC; }
+
+// runs check in a child, since locks held by this process never block it
+static int in_child(int (*check)(void)) {
+  pid_t pid = fork();
+
+  if (pid == 0) {
+    _exit(check());
+  }
+
+  int status;
+
+  return waitpid(pid, &status, 0) == pid && WIFEXITED(status) &&
+         WEXITSTATUS(status) == 0;
+}
+
+static int flock_is_held(void) {
+  int fd = open(FILE_PATH, O_RDONLY);
+
+  return !(flock(fd, LOCK_EX | LOCK_NB) == -1 && errno == EAGAIN);
+}
+
+static int lockf_is_held(void) {
+  int fd = open(FILE_PATH, O_RDWR);
+
+  return !(lockf(fd, F_TEST, 0) == -1 && errno == EACCES &&
+           lockf(fd, F_TLOCK, 0) == -1);
+}
+
+int main(void) {
+  unlink(FILE_PATH);
+  rmdir(DIR_PATH);
+  CHECK(mkdir(DIR_PATH, 0755) == 0);
+
+  // open only looks at mode when it might create the file
+  int fd = open(FILE_PATH, O_RDWR | O_CREAT | O_EXCL, 0640);
+  CHECK(fd >= 0);
+
+  struct stat st;
+  CHECK(fstat(fd, &st) == 0 && (st.st_mode & 0777) == 0640);
+  CHECK(open(FILE_PATH, O_RDWR | O_CREAT | O_EXCL, 0640) == -1);
+  CHECK(errno == EEXIST);
+
+  int dirfd = open(DIR_PATH, O_RDONLY | O_DIRECTORY | O_CLOEXEC);
+  CHECK(dirfd >= 0);
+  CHECK(is_cloexec(dirfd));
+
+  int fd2 = openat(dirfd, "file", O_RDONLY);
+  CHECK(fd2 >= 0);
+  CHECK(!is_cloexec(fd2));
+  CHECK(openat(dirfd, "nope", O_RDONLY) == -1 && errno == ENOENT);
+  close(fd2);
+
+  int tmp = openat(dirfd, ".", O_TMPFILE | O_RDWR, 0600);
+
+  // not every filesystem can do O_TMPFILE
+  if (tmp >= 0) {
+    CHECK(fstat(tmp, &st) == 0 && st.st_nlink == 0);
+    close(tmp);
+  }
+
+  int created = creat(DIR_PATH "/created", 0600);
+  CHECK(created >= 0);
+  CHECK((fcntl(created, F_GETFL) & O_ACCMODE) == O_WRONLY);
+  close(created);
+  unlink(DIR_PATH "/created");
+
+  // flags
+  CHECK((fcntl(fd, F_GETFL) & O_ACCMODE) == O_RDWR);
+  CHECK(fcntl(fd, F_SETFL, O_NONBLOCK | O_APPEND) == 0);
+  CHECK(fcntl(fd, F_GETFL) & O_APPEND);
+  CHECK(fcntl(fd, F_GETFL) & O_NONBLOCK);
+  CHECK(fcntl(fd, F_SETFL, 0) == 0);
+
+  CHECK(!is_cloexec(fd));
+  CHECK(fcntl(fd, F_SETFD, FD_CLOEXEC) == 0);
+  CHECK(is_cloexec(fd));
+  CHECK(fcntl(fd, F_SETFD, 0) == 0);
+
+  CHECK(fcntl(fd, F_GETOWN) == 0);
+
+  // duplicating
+  int high = fcntl(fd, F_DUPFD_CLOEXEC, 100);
+  CHECK(high >= 100 && is_cloexec(high));
+  close(high);
+
+  int copy = dup(fd);
+  CHECK(copy >= 0 && copy != fd && !is_cloexec(copy));
+  CHECK(dup2(fd, copy) == copy);
+  CHECK(dup2(copy, copy) == copy);
+  CHECK(dup3(copy, copy, 0) == -1 && errno == EINVAL);
+  CHECK(dup3(fd, copy, O_CLOEXEC) == copy && is_cloexec(copy));
+  close(copy);
+  CHECK(dup(-1) == -1 && errno == EBADF);
+
+  // pipes
+  int fds[2];
+  CHECK(pipe(fds) == 0);
+  CHECK(!is_cloexec(fds[0]) && !is_cloexec(fds[1]));
+  CHECK(write(fds[1], "hi", 2) == 2);
+
+  char buf[2];
+  CHECK(read(fds[0], buf, 2) == 2 && memcmp(buf, "hi", 2) == 0);
+  close(fds[0]);
+  close(fds[1]);
+
+  CHECK(pipe2(fds, O_CLOEXEC | O_NONBLOCK) == 0);
+  CHECK(is_cloexec(fds[0]) && is_cloexec(fds[1]));
+  CHECK(read(fds[0], buf, 1) == -1 && errno == EAGAIN);
+  close(fds[0]);
+  close(fds[1]);
+
+  // locking
+  CHECK(flock(fd, LOCK_EX) == 0);
+  CHECK(in_child(flock_is_held));
+  CHECK(flock(fd, LOCK_UN) == 0);
+  CHECK(!in_child(flock_is_held));
+
+  struct flock lock = {.l_type = F_WRLCK, .l_whence = SEEK_SET};
+  CHECK(fcntl(fd, F_OFD_SETLK, &lock) == 0);
+
+  // open file description locks conflict even within one process
+  fd2 = open(FILE_PATH, O_RDWR);
+  CHECK(fcntl(fd2, F_OFD_SETLK, &lock) == -1 && errno == EAGAIN);
+
+  struct flock query = {.l_type = F_WRLCK, .l_whence = SEEK_SET};
+  CHECK(fcntl(fd2, F_OFD_GETLK, &query) == 0 && query.l_type == F_WRLCK);
+  close(fd2);
+
+  lock.l_type = F_UNLCK;
+  CHECK(fcntl(fd, F_OFD_SETLK, &lock) == 0);
+
+  CHECK(lockf(fd, F_LOCK, 0) == 0);
+  CHECK(lockf(fd, F_TEST, 0) == 0);
+  CHECK(in_child(lockf_is_held));
+  CHECK(lockf(fd, F_ULOCK, 0) == 0);
+  CHECK(!in_child(lockf_is_held));
+  CHECK(lockf(fd, 42, 0) == -1 && errno == EINVAL);
+
+  // space and advice
+  CHECK(posix_fallocate(fd, 0, 4096) == 0);
+  CHECK(fstat(fd, &st) == 0 && st.st_size == 4096);
+  CHECK(posix_fallocate(fd, 0, 0) == EINVAL);
+  CHECK(posix_fadvise(fd, 0, 0, POSIX_FADV_SEQUENTIAL) == 0);
+  CHECK(posix_fadvise(-1, 0, 0, POSIX_FADV_NORMAL) == EBADF);
+
+  close(fd);
+  close(dirfd);
+  unlink(FILE_PATH);
+  rmdir(DIR_PATH);
+
+  return check_status();
+}
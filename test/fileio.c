@@ -0,0 +1,73 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+#include "check.h"
+
+static const char PATH[] = "/tmp/kns-fileio-test";
+
+int main(void) {
+  const unsigned char binary[] = {0x00, 0xff, 0x7f, 0x80, '\n', 0x01};
+
+  FILE *out = fopen(PATH, "w");
+  CHECK(out != NULL);
+  CHECK(fileno(out) > 2);
+  CHECK(fwrite(binary, 1, sizeof(binary), out) == sizeof(binary));
+  CHECK(fputc('x', out) == 'x');
+  CHECK(putc('\n', out) == '\n');
+  CHECK(fputs("first line\nsecond;third", out) >= 0);
+  CHECK(fwrite(binary, sizeof(binary), 0, out) == 0);
+  CHECK(ferror(out) == 0);
+  fclose(out);
+
+  FILE *in = fopen(PATH, "r");
+  CHECK(in != NULL);
+
+  unsigned char read_back[sizeof(binary)];
+  CHECK(fread(read_back, 2, 3, in) == 3);
+  CHECK(memcmp(read_back, binary, sizeof(binary)) == 0);
+
+  CHECK(fgetc(in) == 'x');
+  CHECK(ungetc('y', in) == 'y');
+  CHECK(getc(in) == 'y');
+  CHECK(getc(in) == '\n');
+
+  char *line = NULL;
+  size_t n = 0;
+  CHECK(getline(&line, &n, in) == 11);
+  CHECK(line != NULL && n >= 12 && memcmp(line, "first line\n", 12) == 0);
+  CHECK(getdelim(&line, &n, ';', in) == 7);
+  CHECK(memcmp(line, "second;", 8) == 0);
+  CHECK(getline(&line, &n, in) == 5);
+  CHECK(memcmp(line, "third", 6) == 0);
+
+  CHECK(feof(in) != 0);
+  CHECK(getline(&line, &n, in) == -1);
+  CHECK(fgetc(in) == EOF);
+  CHECK(fread(read_back, 1, 1, in) == 0);
+  clearerr(in);
+  CHECK(feof(in) == 0);
+
+  // pushing back at end of file clears the flag again
+  CHECK(ungetc('z', in) == 'z');
+  CHECK(fgetc(in) == 'z');
+
+  free(line);
+  fclose(in);
+
+  // closing gives back what was read ahead, leaving a shared offset where
+  // the stream stopped
+  in = fopen(PATH, "r");
+  CHECK(in != NULL);
+  int shared = dup(fileno(in));
+  CHECK(fgetc(in) == 0x00 && fgetc(in) == 0xff && fgetc(in) == 0x7f);
+  CHECK(fclose(in) == 0);
+  CHECK(lseek(shared, 0, SEEK_CUR) == 3);
+  close(shared);
+
+  CHECK(puts("puts works") >= 0);
+  CHECK(putchar('\n') == '\n');
+
+  return check_status();
+}
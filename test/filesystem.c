@@ -0,0 +1,159 @@
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+
+#include "check.h"
+
+#define DIR_PATH "/tmp/kns-filesystem-test"
+
+static int same(const char *a, const char *b) {
+  size_t len = strlen(a);
+
+  return len == strlen(b) && memcmp(a, b, len) == 0;
+}
+
+static int write_file(const char *path, const char *contents) {
+  FILE *f = fopen(path, "w");
+
+  if (!f) {
+    return 0;
+  }
+
+  fputs(contents, f);
+
+  return fclose(f) == 0;
+}
+
+static void cleanup(void) {
+  unlink(DIR_PATH "/sub/f");
+  unlink(DIR_PATH "/sub/g");
+  unlink(DIR_PATH "/sub/h");
+  unlink(DIR_PATH "/l");
+  unlink(DIR_PATH "/loop");
+  rmdir(DIR_PATH "/sub");
+  rmdir(DIR_PATH);
+}
+
+int main(void) {
+  cleanup();
+
+  CHECK(mkdir(DIR_PATH, 0755) == 0);
+  CHECK(mkdir(DIR_PATH, 0755) == -1 && errno == EEXIST);
+
+  struct stat st;
+  CHECK(stat(DIR_PATH, &st) == 0);
+  CHECK(S_ISDIR(st.st_mode));
+  CHECK((st.st_mode & 0777) == 0755);
+
+  CHECK(access(DIR_PATH, R_OK | W_OK | X_OK) == 0);
+  CHECK(access(DIR_PATH "/nope", F_OK) == -1 && errno == ENOENT);
+  CHECK(faccessat(AT_FDCWD, DIR_PATH, F_OK, AT_EACCESS) == 0);
+
+  DIR *dir = opendir(DIR_PATH);
+  CHECK(dir != NULL);
+  int dfd = dirfd(dir);
+
+  CHECK(mkdirat(dfd, "sub", 0700) == 0);
+  CHECK(write_file(DIR_PATH "/sub/f", "hello"));
+
+  CHECK(fstatat(dfd, "sub/f", &st, 0) == 0);
+  CHECK(S_ISREG(st.st_mode));
+  CHECK(st.st_size == 5);
+
+  // symlinks
+  CHECK(symlink("sub/f", DIR_PATH "/l") == 0);
+  CHECK(lstat(DIR_PATH "/l", &st) == 0 && S_ISLNK(st.st_mode));
+  CHECK(stat(DIR_PATH "/l", &st) == 0 && S_ISREG(st.st_mode));
+  CHECK(fstatat(dfd, "l", &st, AT_SYMLINK_NOFOLLOW) == 0 &&
+        S_ISLNK(st.st_mode));
+
+  char buf[4096];
+  ssize_t len = readlink(DIR_PATH "/l", buf, sizeof(buf));
+  CHECK(len == 5 && memcmp(buf, "sub/f", 5) == 0);
+  len = readlinkat(dfd, "l", buf, sizeof(buf));
+  CHECK(len == 5);
+
+  // realpath
+  char *resolved = realpath(DIR_PATH "/sub/../l", NULL);
+  CHECK(resolved && same(resolved, DIR_PATH "/sub/f"));
+  free(resolved);
+
+  CHECK(realpath(DIR_PATH "//./sub/", buf) == buf);
+  CHECK(same(buf, DIR_PATH "/sub"));
+  CHECK(realpath("/", buf) && same(buf, "/"));
+  CHECK(realpath("/..", buf) && same(buf, "/"));
+  CHECK(realpath(DIR_PATH "/nope", buf) == NULL && errno == ENOENT);
+  CHECK(realpath(DIR_PATH "/sub/f/x", buf) == NULL && errno == ENOTDIR);
+
+  CHECK(symlink("loop", DIR_PATH "/loop") == 0);
+  CHECK(realpath(DIR_PATH "/loop", buf) == NULL && errno == ELOOP);
+
+  // links and renames
+  CHECK(link(DIR_PATH "/sub/f", DIR_PATH "/sub/g") == 0);
+  CHECK(stat(DIR_PATH "/sub/f", &st) == 0 && st.st_nlink == 2);
+
+  CHECK(write_file(DIR_PATH "/sub/h", "h"));
+  CHECK(renameat2(dfd, "sub/g", dfd, "sub/h", RENAME_NOREPLACE) == -1);
+  CHECK(errno == EEXIST || errno == EINVAL);
+  CHECK(rename(DIR_PATH "/sub/g", DIR_PATH "/sub/h") == 0);
+  CHECK(stat(DIR_PATH "/sub/h", &st) == 0 && st.st_size == 5);
+  CHECK(access(DIR_PATH "/sub/g", F_OK) == -1 && errno == ENOENT);
+
+  // permissions, sizes and times
+  CHECK(chmod(DIR_PATH "/sub/f", 0600) == 0);
+  CHECK(stat(DIR_PATH "/sub/f", &st) == 0 && (st.st_mode & 0777) == 0600);
+
+  CHECK(chown(DIR_PATH "/sub/f", getuid(), getgid()) == 0);
+
+  CHECK(truncate(DIR_PATH "/sub/f", 100) == 0);
+  CHECK(stat(DIR_PATH "/sub/f", &st) == 0 && st.st_size == 100);
+
+  FILE *f = fopen(DIR_PATH "/sub/f", "r+");
+  CHECK(f != NULL);
+  CHECK(ftruncate(fileno(f), 3) == 0);
+  CHECK(fchmod(fileno(f), 0644) == 0);
+  fclose(f);
+
+  struct timespec times[2] = {{1000, 0}, {2000, 500}};
+  CHECK(utimensat(dfd, "sub/f", times, 0) == 0);
+
+  struct statx stx;
+  CHECK(statx(AT_FDCWD, DIR_PATH "/sub/f", 0, STATX_BASIC_STATS, &stx) == 0);
+  CHECK(stx.stx_size == 3);
+  CHECK((stx.stx_mode & 0777) == 0644);
+  CHECK(stx.stx_mtime.tv_sec == 2000 && stx.stx_mtime.tv_nsec == 500);
+  CHECK(stx.stx_atime.tv_sec == 1000);
+
+  // the working directory
+  char *cwd = getcwd(NULL, 0);
+  CHECK(cwd != NULL);
+
+  CHECK(chdir(DIR_PATH "/sub") == 0);
+  CHECK(getcwd(buf, sizeof(buf)) == buf && same(buf, DIR_PATH "/sub"));
+  CHECK(getcwd(buf, 4) == NULL && errno == ERANGE);
+  CHECK(access("f", F_OK) == 0);
+
+  CHECK(fchdir(dfd) == 0);
+  CHECK(getcwd(buf, sizeof(buf)) && same(buf, DIR_PATH));
+
+  CHECK(chdir(cwd) == 0);
+  free(cwd);
+
+  // removal
+  CHECK(rmdir(DIR_PATH "/sub") == -1 && errno == ENOTEMPTY);
+  CHECK(unlinkat(dfd, "sub", 0) == -1 && errno == EISDIR);
+  CHECK(unlink(DIR_PATH "/sub/f") == 0);
+  CHECK(unlinkat(dfd, "sub/h", 0) == 0);
+  CHECK(unlinkat(dfd, "sub", AT_REMOVEDIR) == 0);
+
+  closedir(dir);
+  cleanup();
+  CHECK(access(DIR_PATH, F_OK) == -1);
+
+  return check_status();
+}
@@ -0,0 +1,80 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "check.h"
+
+static volatile sig_atomic_t alarms = 0;
+
+static void on_alarm(int sig) {
+  (void)sig;
+  ++alarms;
+}
+
+static void set_alarm_handler(int flags) {
+  struct sigaction act = {0};
+  act.sa_handler = on_alarm;
+  act.sa_flags = flags;
+  sigemptyset(&act.sa_mask);
+
+  CHECK(sigaction(SIGALRM, &act, NULL) == 0);
+}
+
+// sends SIGALRM to the parent every so often, count times or until it's
+// killed, then writes c to fd
+static pid_t start_alarms(int count, int fd, char c) {
+  pid_t parent = getpid();
+  pid_t pid = fork();
+
+  if (pid != 0) {
+    return pid;
+  }
+
+  for (int sent = 0; count < 0 || sent < count; ++sent) {
+    // give the parent time to block
+    for (int i = 0; i < 10000; ++i) {
+      getppid();
+    }
+
+    kill(parent, SIGALRM);
+  }
+
+  _exit(write(fd, &c, 1) == 1 ? 0 : 1);
+}
+
+int main(void) {
+  int line_pipe[2];
+  char path[32];
+
+  CHECK(pipe(line_pipe) == 0);
+  snprintf(path, sizeof(path), "/proc/self/fd/%d", line_pipe[0]);
+  FILE *in = fopen(path, "r");
+  CHECK(in != NULL);
+
+  // without SA_RESTART, a read that nothing arrives for fails with EINTR
+  set_alarm_handler(0);
+  pid_t pid = start_alarms(-1, line_pipe[1], 'x');
+  errno = 0;
+  CHECK(fgetc(in) == EOF);
+  CHECK(errno == EINTR);
+  CHECK(ferror(in));
+  CHECK(alarms > 0);
+  kill(pid, SIGKILL);
+  CHECK(waitpid(pid, NULL, 0) == pid);
+
+  // with it, the read carries on until there's something to read
+  clearerr(in);
+  alarms = 0;
+  set_alarm_handler(SA_RESTART);
+  pid = start_alarms(3, line_pipe[1], 'y');
+  CHECK(fgetc(in) == 'y');
+  CHECK(alarms > 0);
+  CHECK(waitpid(pid, NULL, 0) == pid);
+
+  fclose(in);
+
+  return check_status();
+}
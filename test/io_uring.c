@@ -0,0 +1,111 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <liburing.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+#include "check.h"
+
+#define PATH "/tmp/kns-io_uring-test"
+
+// submits whatever is queued and returns the result of the next completion
+static int complete_one(struct io_uring *ring, void *expected_data) {
+  struct io_uring_cqe *cqe;
+
+  CHECK(io_uring_submit(ring) >= 0);
+  CHECK(io_uring_wait_cqe(ring, &cqe) == 0);
+
+  if (cqe == NULL) {
+    return -EINVAL;
+  }
+
+  CHECK(io_uring_cqe_get_data(cqe) == expected_data);
+
+  int res = cqe->res;
+  io_uring_cqe_seen(ring, cqe);
+
+  return res;
+}
+
+int main(void) {
+  struct io_uring ring;
+  int ret = io_uring_queue_init(8, &ring, 0);
+
+  if (ret == -ENOSYS || ret == -EPERM) {
+    printf("io_uring unavailable, skipping\n0 failed\n");
+
+    return 0;
+  }
+
+  CHECK(ret == 0);
+
+  struct io_uring_sqe *sqe = io_uring_get_sqe(&ring);
+  io_uring_prep_openat(sqe, AT_FDCWD, PATH, O_CREAT | O_TRUNC | O_RDWR, 0644);
+  io_uring_sqe_set_data(sqe, &ring);
+  int fd = complete_one(&ring, &ring);
+  CHECK(fd >= 0);
+
+  static const char message[] = "hello, io_uring";
+  size_t len = sizeof(message) - 1;
+
+  sqe = io_uring_get_sqe(&ring);
+  io_uring_prep_write(sqe, fd, message, len, 0);
+  CHECK(complete_one(&ring, NULL) == (int)len);
+
+  char buf[64] = {0};
+  sqe = io_uring_get_sqe(&ring);
+  io_uring_prep_read(sqe, fd, buf, sizeof(buf), 0);
+  CHECK(complete_one(&ring, NULL) == (int)len);
+  CHECK(memcmp(buf, message, len) == 0);
+
+  char first[5] = {0};
+  char rest[64] = {0};
+  struct iovec iov[2] = {{first, 5}, {rest, sizeof(rest)}};
+  sqe = io_uring_get_sqe(&ring);
+  io_uring_prep_readv(sqe, fd, iov, 2, 2);
+  CHECK(complete_one(&ring, NULL) == (int)len - 2);
+  CHECK(memcmp(first, "llo, ", 5) == 0);
+  CHECK(memcmp(rest, "io_uring", 8) == 0);
+
+  sqe = io_uring_get_sqe(&ring);
+  io_uring_prep_close(sqe, fd);
+  CHECK(complete_one(&ring, NULL) == 0);
+
+  sqe = io_uring_get_sqe(&ring);
+  io_uring_prep_read(sqe, fd, buf, sizeof(buf), 0);
+  CHECK(complete_one(&ring, NULL) == -EBADF);
+
+  struct __kernel_timespec ts = {0, 1000000};
+  sqe = io_uring_get_sqe(&ring);
+  io_uring_prep_timeout(sqe, &ts, 0, 0);
+  CHECK(complete_one(&ring, NULL) == -ETIME);
+
+  // a batch is completed in whatever order, but all of it is completed
+  for (int i = 0; i < 8; ++i) {
+    sqe = io_uring_get_sqe(&ring);
+    CHECK(sqe != NULL);
+    io_uring_prep_nop(sqe);
+    io_uring_sqe_set_data(sqe, (void *)(long)(i + 1));
+  }
+
+  CHECK(io_uring_get_sqe(&ring) == NULL);
+  CHECK(io_uring_submit_and_wait(&ring, 8) == 8);
+
+  int seen = 0;
+  struct io_uring_cqe *cqe;
+
+  while (io_uring_peek_cqe(&ring, &cqe) == 0) {
+    seen |= 1 << ((long)io_uring_cqe_get_data(cqe) - 1);
+    io_uring_cqe_seen(&ring, cqe);
+  }
+
+  CHECK(seen == 0xff);
+  CHECK(io_uring_peek_cqe(&ring, &cqe) == -EAGAIN && cqe == NULL);
+
+  io_uring_queue_exit(&ring);
+  unlink(PATH);
+
+  return check_status();
+}
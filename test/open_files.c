@@ -0,0 +1,107 @@
+#define _GNU_SOURCE
+#include <pthread.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "check.h"
+
+static const char FIRST_PATH[] = "/tmp/kns-open-files-test-1";
+static const char SECOND_PATH[] = "/tmp/kns-open-files-test-2";
+
+// how much of the file has actually been written out
+static size_t file_len(const char *path) {
+  char buf[512];
+
+  FILE *f = fopen(path, "r");
+  const size_t len = fread(buf, 1, sizeof(buf), f);
+  fclose(f);
+
+  return len;
+}
+
+static int ready_pipe[2];
+
+static void *read_char(void *arg) {
+  write(ready_pipe[1], "", 1);
+
+  return fgetc(arg) == EOF ? NULL : arg;
+}
+
+// exits while another thread is blocked reading a stream, with output still
+// buffered in a second one
+static void exit_while_reading(void) {
+  int line_pipe[2];
+  char path[32];
+  pthread_t reader;
+  char ready;
+
+  if (pipe(line_pipe) != 0 || pipe(ready_pipe) != 0) {
+    _exit(1);
+  }
+
+  snprintf(path, sizeof(path), "/proc/self/fd/%d", line_pipe[0]);
+  FILE *in = fopen(path, "r");
+  FILE *out = fopen(FIRST_PATH, "w");
+
+  if (!in || !out || fputs("at exit\n", out) < 0 ||
+      pthread_create(&reader, NULL, read_char, in) != 0 ||
+      read(ready_pipe[0], &ready, 1) != 1) {
+    _exit(1);
+  }
+
+  // give the reader time to block
+  for (int i = 0; i < 10000; ++i) {
+    getppid();
+  }
+
+  exit(0);
+}
+
+int main(void) {
+  FILE *first = fopen(FIRST_PATH, "w");
+  CHECK(first != NULL);
+  FILE *second = fopen(SECOND_PATH, "w");
+  CHECK(second != NULL);
+
+  CHECK(fputs("first\n", first) >= 0);
+  CHECK(fputs("second\n", second) >= 0);
+  CHECK(file_len(FIRST_PATH) == 0);
+  CHECK(file_len(SECOND_PATH) == 0);
+
+  // fflush(NULL) reaches streams that were opened with fopen
+  CHECK(fflush(NULL) == 0);
+  CHECK(file_len(FIRST_PATH) == 6);
+  CHECK(file_len(SECOND_PATH) == 7);
+
+  // closing one stream leaves the others tracked
+  CHECK(fputs("more\n", first) >= 0);
+  CHECK(fputs("more\n", second) >= 0);
+  CHECK(fclose(first) == 0);
+  CHECK(file_len(FIRST_PATH) == 11);
+  CHECK(file_len(SECOND_PATH) == 7);
+  CHECK(fflush(NULL) == 0);
+  CHECK(file_len(SECOND_PATH) == 12);
+
+  CHECK(fputs("last\n", second) >= 0);
+  CHECK(fcloseall() == 0);
+  CHECK(file_len(SECOND_PATH) == 17);
+
+  // exit flushes what it can instead of waiting on a stream that's stuck in
+  // a read
+  pid_t pid = fork();
+
+  if (pid == 0) {
+    exit_while_reading();
+  }
+
+  int status;
+  CHECK(waitpid(pid, &status, 0) == pid);
+  CHECK(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+  CHECK(file_len(FIRST_PATH) == 8);
+
+  // the standard streams stay usable after fcloseall
+  return check_status();
+}
@@ -0,0 +1,96 @@
+#include <stddef.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "check.h"
+
+static void check(const char *expected, const char *format, ...) {
+  char buf[256];
+
+  va_list ap;
+  va_start(ap, format);
+  const int len = vsnprintf(buf, sizeof(buf), format, ap);
+  va_end(ap);
+
+  if (len != (int)strlen(expected) || memcmp(buf, expected, len + 1) != 0) {
+    fprintf(stderr, "\"%s\": expected \"%s\" (%d), got \"%s\" (%d)\n", format,
+            expected, (int)strlen(expected), buf, len);
+    ++num_failed;
+  }
+}
+
+int main(void) {
+  check("hello, world", "hello, %s", "world");
+  check("100%", "%d%%", 100);
+  check("-42|42   |  +42|00042", "%d|%-5d|%+5d|%05d", -42, 42, 42, 42);
+  check("-2147483648", "%d", -2147483647 - 1);
+  check("-9223372036854775808", "%lld", -9223372036854775807LL - 1);
+  check("18446744073709551615", "%lu", 18446744073709551615UL);
+  // glibc takes L on an integer conversion to mean ll
+  check("-9223372036854775808|ffffffffffffffff", "%Ld|%Lx",
+        -9223372036854775807LL - 1, 18446744073709551615ULL);
+  check("ff|FF|0xff|0XFF|377|0377|0", "%x|%X|%#x|%#X|%o|%#o|%#x", 255, 255,
+        255, 255, 255, 255, 0);
+  check("-1|255|65535", "%hhd|%hhu|%hu", 255, 255, -1);
+  check("00042|   00042|", "%.5d|%8.5d|%.0d", 42, 42, 0);
+  check("0", "%#.0o", 0);
+  check(" 42", "% d", 42);
+  check("  abc|abc  |ab", "%5s|%-5s|%.2s", "abc", "abc", "abc");
+  check("    x|x    ", "%*c|%-*c", 5, 'x', 5, 'x');
+  check("   42|42   ", "%*d|%*d", 5, 42, -5, 42);
+  check("00042", "%.*d", 5, 42);
+  check("(null)|", "%s|%.3s", (char *)NULL, (char *)NULL);
+  check("0x1234|(nil)", "%p|%p", (void *)0x1234, (void *)0);
+  check("12345|-1|7", "%zu|%zd|%td", (size_t)12345, (ssize_t)-1, (ptrdiff_t)7);
+
+  int n = 0;
+  check("abc", "abc%n", &n);
+  if (n != 3) {
+    fputs("%n stored the wrong count\n", stderr);
+    ++num_failed;
+  }
+
+  char small[4];
+  if (snprintf(small, sizeof(small), "%d", 123456) != 6 ||
+      memcmp(small, "123", 4) != 0) {
+    fputs("snprintf didn't truncate\n", stderr);
+    ++num_failed;
+  }
+
+  if (snprintf(NULL, 0, "%s", "length only") != 11) {
+    fputs("snprintf(NULL, 0) returned the wrong length\n", stderr);
+    ++num_failed;
+  }
+
+  char *allocated = NULL;
+  if (asprintf(&allocated, "%s-%d", "kns", 2020) != 8 ||
+      memcmp(allocated, "kns-2020", 9) != 0) {
+    fputs("asprintf produced the wrong string\n", stderr);
+    ++num_failed;
+  }
+  free(allocated);
+
+  // longer than asprintf's first buffer, so it has to grow it, and the string
+  // must survive the blocks malloc hands out next
+  char *grown = NULL;
+  int grown_ok = asprintf(&grown, "%4096d", 42) == 4096;
+  char *neighbours[16];
+  for (int i = 0; i < 16; ++i) {
+    neighbours[i] = malloc(64);
+    memset(neighbours[i], 'x', 64);
+  }
+  for (int i = 0; grown_ok && i < 4094; ++i) {
+    grown_ok = grown[i] == ' ';
+  }
+  if (!grown_ok || memcmp(grown + 4094, "42", 3) != 0) {
+    fputs("asprintf lost part of a grown string\n", stderr);
+    ++num_failed;
+  }
+  for (int i = 0; i < 16; ++i) {
+    free(neighbours[i]);
+  }
+  free(grown);
+
+  return check_status();
+}
@@ -0,0 +1,199 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "check.h"
+
+#define OUTPUT_PATH "/tmp/kns-process-test"
+
+static int exit_status(pid_t pid) {
+  int status;
+
+  if (waitpid(pid, &status, 0) != pid || !WIFEXITED(status)) {
+    return -1;
+  }
+
+  return WEXITSTATUS(status);
+}
+
+static int output_is(const char *expected) {
+  char buf[64];
+  FILE *f = fopen(OUTPUT_PATH, "r");
+
+  if (!f) {
+    return 0;
+  }
+
+  size_t len = fread(buf, 1, sizeof(buf), f);
+  fclose(f);
+
+  return len == strlen(expected) && memcmp(buf, expected, len) == 0;
+}
+
+static int ready_pipe[2];
+
+static void *read_line(void *arg) {
+  char buf[8];
+
+  write(ready_pipe[1], "", 1);
+
+  return fgets(buf, sizeof(buf), arg);
+}
+
+int main(void) {
+  pid_t parent = getpid();
+  pid_t pid = fork();
+
+  if (pid == 0) {
+    _exit(getppid() == parent ? 42 : 1);
+  }
+
+  CHECK(pid > 0);
+  CHECK(exit_status(pid) == 42);
+
+  // the allocator and stdio still work in a child
+  pid = fork();
+
+  if (pid == 0) {
+    char *p = malloc(1 << 20);
+    FILE *f = fopen(OUTPUT_PATH, "w");
+
+    if (!p || !f || fputs("forked", f) == EOF) {
+      _exit(1);
+    }
+
+    free(p);
+    exit(fclose(f) == 0 ? 0 : 1);
+  }
+
+  CHECK(exit_status(pid) == 0);
+  CHECK(output_is("forked"));
+
+  // a thread blocked reading a stream doesn't hold up a fork, and the child
+  // can still lock that stream
+  int line_pipe[2];
+  pthread_t reader;
+  char ready;
+  void *line;
+  CHECK(pipe(ready_pipe) == 0);
+  CHECK(pipe(line_pipe) == 0);
+  char line_path[32];
+  snprintf(line_path, sizeof(line_path), "/proc/self/fd/%d", line_pipe[0]);
+  FILE *line_stream = fopen(line_path, "r");
+  CHECK(line_stream);
+  CHECK(pthread_create(&reader, NULL, read_line, line_stream) == 0);
+  CHECK(read(ready_pipe[0], &ready, 1) == 1);
+
+  // give the reader time to block
+  for (int i = 0; i < 10000; ++i) {
+    getppid();
+  }
+
+  pid = fork();
+
+  if (pid == 0) {
+    _exit(fflush(NULL) == 0 ? 0 : 1);
+  }
+
+  CHECK(exit_status(pid) == 0);
+  CHECK(write(line_pipe[1], "line\n", 5) == 5);
+  CHECK(pthread_join(reader, &line) == 0);
+  CHECK(line);
+  CHECK(fclose(line_stream) == 0);
+  close(line_pipe[0]);
+  close(line_pipe[1]);
+  close(ready_pipe[0]);
+  close(ready_pipe[1]);
+
+  pid = vfork();
+
+  if (pid == 0) {
+    _exit(7);
+  }
+
+  CHECK(exit_status(pid) == 7);
+
+  pid = fork();
+
+  if (pid == 0) {
+    char *const argv[] = {"sh", "-c", "exit 3", NULL};
+    execvp("sh", argv);
+    _exit(100);
+  }
+
+  CHECK(exit_status(pid) == 3);
+
+  char *const missing_argv[] = {"kns-no-such-program", NULL};
+  errno = 0;
+  CHECK(execvp("kns-no-such-program", missing_argv) == -1);
+  CHECK(errno == ENOENT);
+
+  errno = 0;
+  CHECK(execv("/kns/no/such/program", missing_argv) == -1);
+  CHECK(errno == ENOENT);
+
+  errno = 0;
+  CHECK(waitpid(-1, NULL, WNOHANG) == -1);
+  CHECK(errno == ECHILD);
+
+  // posix_spawnp searches PATH and runs file actions in order
+  posix_spawn_file_actions_t actions;
+  CHECK(posix_spawn_file_actions_init(&actions) == 0);
+  CHECK(posix_spawn_file_actions_addopen(&actions, 3, OUTPUT_PATH,
+                                         O_WRONLY | O_CREAT | O_TRUNC,
+                                         0644) == 0);
+  CHECK(posix_spawn_file_actions_adddup2(&actions, 3, STDOUT_FILENO) == 0);
+  CHECK(posix_spawn_file_actions_addclose(&actions, 3) == 0);
+  CHECK(posix_spawn_file_actions_addclose(&actions, -1) == EBADF);
+
+  char *const echo_argv[] = {"echo", "spawned", NULL};
+  CHECK(posix_spawnp(&pid, "echo", &actions, NULL, echo_argv, environ) == 0);
+  CHECK(exit_status(pid) == 0);
+  CHECK(output_is("spawned\n"));
+  CHECK(posix_spawn_file_actions_destroy(&actions) == 0);
+
+  posix_spawnattr_t attr;
+  short flags;
+  pid_t pgroup;
+  CHECK(posix_spawnattr_init(&attr) == 0);
+  CHECK(posix_spawnattr_setflags(&attr, POSIX_SPAWN_SETPGROUP) == 0);
+  CHECK(posix_spawnattr_setpgroup(&attr, 0) == 0);
+  CHECK(posix_spawnattr_getflags(&attr, &flags) == 0);
+  CHECK(flags == POSIX_SPAWN_SETPGROUP);
+  CHECK(posix_spawnattr_getpgroup(&attr, &pgroup) == 0);
+  CHECK(pgroup == 0);
+
+  char *const true_argv[] = {"true", NULL};
+  CHECK(posix_spawnp(&pid, "true", NULL, &attr, true_argv, environ) == 0);
+  CHECK(exit_status(pid) == 0);
+  CHECK(posix_spawnattr_destroy(&attr) == 0);
+
+  // a failed exec is reported by posix_spawn, not as an exit status
+  pid = -1;
+  CHECK(posix_spawn(&pid, "/kns/no/such/program", NULL, NULL, missing_argv,
+                    environ) == ENOENT);
+  CHECK(posix_spawnp(&pid, "kns-no-such-program", NULL, NULL, missing_argv,
+                     environ) == ENOENT);
+  CHECK(pid == -1);
+
+  siginfo_t info;
+  pid = fork();
+
+  if (pid == 0) {
+    _exit(5);
+  }
+
+  CHECK(waitid(P_PID, (id_t)pid, &info, WEXITED) == 0);
+  CHECK(info.si_signo == SIGCHLD);
+  CHECK(info.si_code == CLD_EXITED);
+  CHECK(info.si_pid == pid);
+  CHECK(info.si_status == 5);
+
+  return check_status();
+}
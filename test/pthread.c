@@ -0,0 +1,64 @@
+#include <pthread.h>
+#include <stdio.h>
+
+static _Thread_local int counter = 0;
+
+static void *count_to(void *arg) {
+  const long n = (long)arg;
+
+  for (long i = 0; i < n; ++i) {
+    ++counter;
+  }
+
+  return (void *)(long)counter;
+}
+
+static void *detached(void *arg) {
+  (void)arg;
+
+  pthread_exit(NULL);
+}
+
+int main(void) {
+  pthread_t threads[4];
+
+  for (long i = 0; i < 4; ++i) {
+    if (pthread_create(&threads[i], NULL, count_to, (void *)(1000 * (i + 1)))) {
+      fputs("pthread_create failed\n", stderr);
+
+      return 1;
+    }
+  }
+
+  for (long i = 0; i < 4; ++i) {
+    void *result;
+
+    if (pthread_join(threads[i], &result) ||
+        (long)result != 1000 * (i + 1)) {
+      fputs("pthread_join returned the wrong value\n", stderr);
+
+      return 1;
+    }
+  }
+
+  pthread_attr_t attr;
+  pthread_attr_init(&attr);
+  pthread_attr_setdetachstate(&attr, PTHREAD_CREATE_DETACHED);
+
+  pthread_t detached_thread;
+  if (pthread_create(&detached_thread, &attr, detached, NULL)) {
+    fputs("pthread_create failed\n", stderr);
+
+    return 1;
+  }
+
+  pthread_attr_destroy(&attr);
+
+  if (counter != 0) {
+    fputs("thread-local counter was shared\n", stderr);
+
+    return 1;
+  }
+
+  fputs("ok\n", stdout);
+}
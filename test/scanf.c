@@ -0,0 +1,63 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "check.h"
+
+int main(void) {
+  int i = 0;
+  int j = 0;
+  unsigned u = 0;
+  long l = 0;
+  double d = 0;
+  float f = 0;
+  char s[32] = {0};
+  int n = 0;
+
+  CHECK(sscanf("42 -17", "%d %d", &i, &j) == 2 && i == 42 && j == -17);
+  CHECK(sscanf("0x1f 017 10", "%i %i %i", &i, &j, &u) == 3 && i == 31 &&
+        j == 15 && u == 10);
+  CHECK(sscanf("ff 777", "%x %o", &u, &i) == 2 && u == 255 && i == 511);
+  CHECK(sscanf("9223372036854775807", "%ld", &l) == 1 &&
+        l == 9223372036854775807L);
+  CHECK(sscanf("12345", "%3d%d", &i, &j) == 2 && i == 123 && j == 45);
+  CHECK(sscanf("1 2", "%*d %d", &i) == 1 && i == 2);
+  CHECK(sscanf("abc", "%d", &i) == 0);
+  CHECK(sscanf("", "%d", &i) == EOF);
+  CHECK(sscanf("   ", "%d", &i) == EOF);
+
+  CHECK(sscanf("3.25 -1e-3", "%lf %f", &d, &f) == 2 && d == 3.25 &&
+        f == -1e-3f);
+  CHECK(sscanf("0x1.8p1", "%lf", &d) == 1 && d == 3.0);
+  CHECK(sscanf("2.2250738585072011e-308", "%lf", &d) == 1 &&
+        d == 2.2250738585072009e-308);
+  CHECK(sscanf("inf", "%lf", &d) == 1 && d > 1e308);
+  CHECK(sscanf("nan", "%lf", &d) == 1 && d != d);
+  CHECK(sscanf("1e", "%lf%n", &d, &n) == 1 && d == 1 && n == 2);
+
+  CHECK(sscanf("hello world", "%s%n", s, &n) == 1 &&
+        memcmp(s, "hello", 6) == 0 && n == 5);
+  CHECK(sscanf("abcdef", "%3c", s) == 1 && memcmp(s, "abc", 3) == 0);
+  CHECK(sscanf("key=value;", "%[^=]=%[^;]", s, s + 16) == 2 &&
+        memcmp(s, "key", 4) == 0 && memcmp(s + 16, "value", 6) == 0);
+  CHECK(sscanf("]]x", "%[]]", s) == 1 && memcmp(s, "]]", 3) == 0);
+  CHECK(sscanf("a-b", "%[a-]", s) == 1 && memcmp(s, "a-", 3) == 0);
+  CHECK(sscanf("100%", "%d%%", &i) == 1 && i == 100);
+
+  FILE *out = fopen("/tmp/kns-scanf-test", "w");
+  CHECK(out != NULL);
+  fputs("12abc 3.5\n", out);
+  fclose(out);
+
+  FILE *in = fopen("/tmp/kns-scanf-test", "r");
+  CHECK(in != NULL);
+
+  // a failed conversion leaves the offending character unread
+  CHECK(fscanf(in, "%d", &i) == 1 && i == 12);
+  CHECK(fscanf(in, "%d", &i) == 0);
+  CHECK(fscanf(in, "%s", s) == 1 && memcmp(s, "abc", 4) == 0);
+  CHECK(fscanf(in, "%lf", &d) == 1 && d == 3.5);
+  CHECK(fscanf(in, "%d", &i) == EOF);
+  fclose(in);
+
+  return check_status();
+}
@@ -0,0 +1,78 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "check.h"
+
+static const char PATH[] = "/tmp/kns-seek-test";
+
+static void check_contents(const char *expected) {
+  char buf[64] = {0};
+
+  FILE *f = fopen(PATH, "r");
+  CHECK(f != NULL);
+  const size_t len = fread(buf, 1, sizeof(buf) - 1, f);
+  CHECK(len == strlen(expected) && memcmp(buf, expected, len) == 0);
+  fclose(f);
+}
+
+int main(void) {
+  char buf[16] = {0};
+
+  FILE *f = fopen(PATH, "w+");
+  CHECK(f != NULL);
+  CHECK(fputs("hello, world", f) >= 0);
+  CHECK(ftell(f) == 12);
+
+  // reading right after writing sees the end of the file
+  CHECK(fgetc(f) == EOF);
+  CHECK(feof(f));
+
+  CHECK(fseek(f, 7, SEEK_SET) == 0);
+  CHECK(!feof(f));
+  CHECK(ftell(f) == 7);
+  CHECK(fgetc(f) == 'w');
+  CHECK(ftell(f) == 8);
+
+  // a write after a read lands right after the last byte read
+  CHECK(fputs("O", f) >= 0);
+  CHECK(ftell(f) == 9);
+  CHECK(fgetc(f) == 'r');
+
+  CHECK(fseek(f, -3, SEEK_END) == 0);
+  CHECK(fread(buf, 1, 3, f) == 3 && memcmp(buf, "rld", 3) == 0);
+
+  CHECK(fseek(f, -5, SEEK_CUR) == 0);
+  CHECK(fgetc(f) == 'w');
+
+  fpos_t pos;
+  CHECK(fgetpos(f, &pos) == 0);
+  CHECK(fgetc(f) == 'O');
+  CHECK(fsetpos(f, &pos) == 0);
+  CHECK(fgetc(f) == 'O');
+
+  rewind(f);
+  CHECK(ftello(f) == 0);
+  CHECK(fputc('H', f) == 'H');
+  CHECK(fseeko(f, 0, SEEK_END) == 0);
+  CHECK(ftello(f) == 12);
+  CHECK(fseek(f, -1, SEEK_SET) == -1);
+  fclose(f);
+
+  check_contents("Hello, wOrld");
+
+  f = fopen(PATH, "a+");
+  CHECK(f != NULL);
+
+  // reads start at the beginning, but writes always go to the end
+  CHECK(fgetc(f) == 'H');
+  CHECK(fputs("!", f) >= 0);
+  CHECK(ftell(f) == 13);
+  rewind(f);
+  CHECK(fgets(buf, sizeof(buf), f) != NULL);
+  CHECK(memcmp(buf, "Hello, wOrld!", 14) == 0);
+  fclose(f);
+
+  check_contents("Hello, wOrld!");
+
+  return check_status();
+}
@@ -0,0 +1,66 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "check.h"
+
+static const char PATH[] = "/tmp/kns-setvbuf-test";
+
+// how much of the file has actually been written out
+static size_t file_len(void) {
+  char buf[512];
+
+  FILE *f = fopen(PATH, "r");
+  const size_t len = fread(buf, 1, sizeof(buf), f);
+  fclose(f);
+
+  return len;
+}
+
+int main(void) {
+  static char user_buf[256];
+
+  FILE *f = fopen(PATH, "w");
+  CHECK(f != NULL);
+  CHECK(setvbuf(f, user_buf, _IOFBF, sizeof(user_buf)) == 0);
+  CHECK(fputs("buffered\n", f) >= 0);
+  CHECK(file_len() == 0);
+  CHECK(memcmp(user_buf, "buffered\n", 9) == 0);
+  CHECK(fflush(f) == 0);
+  CHECK(file_len() == 9);
+
+  // filling the user's buffer forces a flush
+  for (int i = 0; i < 30; ++i) {
+    CHECK(fputs("0123456789", f) >= 0);
+  }
+  CHECK(file_len() >= 256);
+  CHECK(fflush(NULL) == 0 && fflush(f) == 0);
+  CHECK(file_len() == 309);
+  fclose(f);
+
+  f = fopen(PATH, "w");
+  CHECK(f != NULL);
+  setlinebuf(f);
+  CHECK(fputs("no newline", f) >= 0);
+  CHECK(file_len() == 0);
+  CHECK(fputc('\n', f) == '\n');
+  CHECK(file_len() == 11);
+  fclose(f);
+
+  f = fopen(PATH, "w");
+  CHECK(f != NULL);
+  setbuf(f, NULL);
+  CHECK(fputc('x', f) == 'x');
+  CHECK(file_len() == 1);
+  fclose(f);
+
+  f = fopen(PATH, "w");
+  CHECK(f != NULL);
+  CHECK(setvbuf(f, NULL, _IOFBF, 16) == 0);
+  CHECK(fputs("0123456789", f) >= 0);
+  CHECK(file_len() == 0);
+  CHECK(setvbuf(f, NULL, 42, 0) != 0);
+  fclose(f);
+  CHECK(file_len() == 10);
+
+  return check_status();
+}
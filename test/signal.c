@@ -0,0 +1,164 @@
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/signalfd.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "check.h"
+
+static volatile sig_atomic_t last_signal = 0;
+static volatile sig_atomic_t last_code = 0;
+static volatile sig_atomic_t on_alt_stack = 0;
+static char alt_stack[64 * 1024];
+
+static void handler(int sig) {
+  last_signal = sig;
+
+  // clobbered, but the interrupted code shouldn't see it
+  errno = EBADF;
+}
+
+static void info_handler(int sig, siginfo_t *info, void *context) {
+  (void)context;
+
+  last_signal = info->si_signo == sig ? sig : -1;
+  last_code = info->si_code;
+}
+
+static void stack_handler(int sig) {
+  char local;
+
+  last_signal = sig;
+  on_alt_stack = &local >= alt_stack && &local < alt_stack + sizeof(alt_stack);
+}
+
+int main(void) {
+  sigset_t set;
+  CHECK(sigemptyset(&set) == 0);
+  CHECK(sigismember(&set, SIGINT) == 0);
+  CHECK(sigaddset(&set, SIGINT) == 0);
+  CHECK(sigaddset(&set, SIGRTMAX) == 0);
+  CHECK(sigismember(&set, SIGINT) == 1);
+  CHECK(sigismember(&set, SIGRTMAX) == 1);
+  CHECK(sigismember(&set, SIGTERM) == 0);
+  CHECK(sigdelset(&set, SIGINT) == 0);
+  CHECK(sigismember(&set, SIGINT) == 0);
+  CHECK(sigfillset(&set) == 0);
+  CHECK(sigismember(&set, SIGTERM) == 1);
+  errno = 0;
+  CHECK(sigaddset(&set, 0) == -1);
+  CHECK(errno == EINVAL);
+  CHECK(sigismember(&set, NSIG) == -1);
+
+  struct sigaction act = {0};
+  struct sigaction old;
+  act.sa_handler = handler;
+  sigemptyset(&act.sa_mask);
+  CHECK(sigaction(SIGUSR1, &act, NULL) == 0);
+
+  errno = EINVAL;
+  CHECK(raise(SIGUSR1) == 0);
+  CHECK(last_signal == SIGUSR1);
+  CHECK(errno == EINVAL);
+
+  // the old action is what the program installed
+  act.sa_sigaction = info_handler;
+  act.sa_flags = SA_SIGINFO;
+  CHECK(sigaction(SIGUSR1, &act, &old) == 0);
+  CHECK(old.sa_handler == handler);
+  CHECK((old.sa_flags & SA_SIGINFO) == 0);
+
+  last_signal = 0;
+  CHECK(kill(getpid(), SIGUSR1) == 0);
+  CHECK(last_signal == SIGUSR1);
+  CHECK(last_code == 0); // SI_USER
+
+  CHECK(sigaction(SIGUSR1, NULL, &old) == 0);
+  CHECK(old.sa_sigaction == info_handler);
+  CHECK((old.sa_flags & SA_SIGINFO) != 0);
+
+  CHECK((void *)signal(SIGUSR1, SIG_IGN) == (void *)info_handler);
+  CHECK(raise(SIGUSR1) == 0);
+  CHECK(signal(SIGUSR1, handler) == SIG_IGN);
+
+  errno = 0;
+  CHECK(signal(SIGKILL, handler) == SIG_ERR);
+  CHECK(errno == EINVAL);
+  CHECK(signal(0, handler) == SIG_ERR);
+
+  // blocked signals stay pending until someone waits for them
+  sigset_t usr2;
+  sigset_t pending;
+  sigset_t old_mask;
+  int sig = 0;
+  sigemptyset(&usr2);
+  sigaddset(&usr2, SIGUSR2);
+  CHECK(sigprocmask(SIG_BLOCK, &usr2, &old_mask) == 0);
+  CHECK(sigismember(&old_mask, SIGUSR2) == 0);
+  CHECK(raise(SIGUSR2) == 0);
+  CHECK(sigpending(&pending) == 0);
+  CHECK(sigismember(&pending, SIGUSR2) == 1);
+  CHECK(sigwait(&usr2, &sig) == 0);
+  CHECK(sig == SIGUSR2);
+
+  struct timespec no_time = {0, 0};
+  errno = 0;
+  CHECK(sigtimedwait(&usr2, NULL, &no_time) == -1);
+  CHECK(errno == EAGAIN);
+
+  siginfo_t info;
+  CHECK(raise(SIGUSR2) == 0);
+  CHECK(sigwaitinfo(&usr2, &info) == SIGUSR2);
+  CHECK(info.si_signo == SIGUSR2);
+  CHECK(info.si_pid == getpid());
+
+  int fd = signalfd(-1, &usr2, SFD_CLOEXEC);
+  CHECK(fd >= 0);
+  CHECK(raise(SIGUSR2) == 0);
+
+  struct signalfd_siginfo fdsi;
+  CHECK(read(fd, &fdsi, sizeof(fdsi)) == sizeof(fdsi));
+  CHECK(fdsi.ssi_signo == SIGUSR2);
+  close(fd);
+
+  CHECK(pthread_sigmask(SIG_SETMASK, &old_mask, NULL) == 0);
+  CHECK(pthread_sigmask(-1, &old_mask, NULL) == EINVAL);
+
+  // sigsuspend lets a blocked signal in and always "fails"
+  sigset_t usr1;
+  sigemptyset(&usr1);
+  sigaddset(&usr1, SIGUSR1);
+  CHECK(sigprocmask(SIG_BLOCK, &usr1, NULL) == 0);
+  last_signal = 0;
+  CHECK(raise(SIGUSR1) == 0);
+  CHECK(last_signal == 0);
+  CHECK(sigsuspend(&old_mask) == -1);
+  CHECK(errno == EINTR);
+  CHECK(last_signal == SIGUSR1);
+  CHECK(sigprocmask(SIG_SETMASK, &old_mask, NULL) == 0);
+
+  stack_t ss = {alt_stack, 0, sizeof(alt_stack)};
+  stack_t old_ss;
+  CHECK(sigaltstack(&ss, &old_ss) == 0);
+  CHECK(old_ss.ss_flags == SS_DISABLE);
+  act.sa_handler = stack_handler;
+  act.sa_flags = SA_ONSTACK;
+  CHECK(sigaction(SIGUSR2, &act, NULL) == 0);
+  CHECK(raise(SIGUSR2) == 0);
+  CHECK(on_alt_stack);
+
+  pid_t pid = fork();
+
+  if (pid == 0) {
+    abort();
+  }
+
+  int status;
+  CHECK(waitpid(pid, &status, 0) == pid);
+  CHECK(WIFSIGNALED(status));
+  CHECK(WTERMSIG(status) == SIGABRT);
+
+  return check_status();
+}
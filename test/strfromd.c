@@ -0,0 +1,85 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "check.h"
+
+static void check(const char *expected, const char *format, double value) {
+  char buf[512];
+  const int len = strfromd(buf, sizeof(buf), format, value);
+
+  if (len != (int)strlen(expected) || memcmp(buf, expected, len + 1) != 0) {
+    fprintf(stderr, "\"%s\": expected \"%s\" (%d), got \"%s\" (%d)\n", format,
+            expected, (int)strlen(expected), buf, len);
+    ++num_failed;
+  }
+}
+
+static void check_printf(const char *expected, const char *format, ...) {
+  char buf[512];
+
+  va_list ap;
+  va_start(ap, format);
+  const int len = vsnprintf(buf, sizeof(buf), format, ap);
+  va_end(ap);
+
+  if (len != (int)strlen(expected) || memcmp(buf, expected, len + 1) != 0) {
+    fprintf(stderr, "\"%s\": expected \"%s\" (%d), got \"%s\" (%d)\n", format,
+            expected, (int)strlen(expected), buf, len);
+    ++num_failed;
+  }
+}
+
+int main(void) {
+  check("3.141593", "%f", 3.14159265358979);
+  check("3.14159e+00", "%.5e", 3.14159265358979);
+  check("3.14159", "%g", 3.14159265358979);
+  check("0.1000000000000000055511151231257827", "%.34f", 0.1);
+  check("2", "%.0f", 2.5);
+  check("4", "%.0f", 3.5);
+  check("1e+100", "%g", 1e100);
+  check("1.00000000000000001590289110975991804683608085639452813897813e+100",
+        "%.59e", 1e100);
+  check("4.94066e-324", "%g", 4.9406564584124654e-324);
+  check("1.7976931348623157e+308", "%.17g", 1.7976931348623157e308);
+  check("0.0001", "%g", 0.0001);
+  check("1e-05", "%g", 0.00001);
+  check("100000", "%g", 100000.0);
+  check("1e+06", "%g", 1000000.0);
+  check("-0", "%g", -0.0);
+  check("-0.000000", "%f", -0.0);
+  check("inf", "%f", __builtin_inf());
+  check("-INF", "%E", -__builtin_inf());
+  check("nan", "%g", __builtin_nan(""));
+  check("NAN", "%F", __builtin_nan(""));
+  check("0x1p+0", "%a", 1.0);
+  check("0X1.8P+1", "%A", 3.0);
+  check("0x1.99999999999ap-4", "%a", 0.1);
+  check("0x2p+0", "%.0a", 1.5);
+  check("0x0p+0", "%a", 0.0);
+
+  char small[4];
+  if (strfromd(small, sizeof(small), "%f", 1.5) != 8 ||
+      memcmp(small, "1.5", 4) != 0) {
+    fputs("strfromd didn't truncate\n", stderr);
+    ++num_failed;
+  }
+
+  char buf[32];
+  if (strfromd(buf, sizeof(buf), "%5f", 1.5) != -1) {
+    fputs("strfromd accepted a width\n", stderr);
+    ++num_failed;
+  }
+
+  if (strfromf(buf, sizeof(buf), "%.3f", 0.1f) != 5 ||
+      memcmp(buf, "0.100", 6) != 0) {
+    fputs("strfromf produced the wrong string\n", stderr);
+    ++num_failed;
+  }
+
+  check_printf("  +1.50|1.50   |0001.5|1.e+00|1.000",
+               "%+7.2f|%-7.2f|%06.1f|%#.0e|%#.4g", 1.5, 1.5, 1.5, 1.0, 1.0);
+  check_printf("   inf|-nan", "%06f|%f", __builtin_inf(), -__builtin_nan(""));
+
+  return check_status();
+}
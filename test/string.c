@@ -0,0 +1,167 @@
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <strings.h>
+
+#include "check.h"
+
+static int sign(int x) { return (x > 0) - (x < 0); }
+
+static void check_compare(void) {
+  CHECK(strcmp("abc", "abc") == 0);
+  CHECK(sign(strcmp("abc", "abd")) == -1);
+  CHECK(sign(strcmp("abd", "abc")) == 1);
+  CHECK(sign(strcmp("ab", "abc")) == -1);
+  CHECK(sign(strcmp("", "a")) == -1);
+  // bytes compare as unsigned char
+  CHECK(sign(strcmp("\xff", "a")) == 1);
+
+  CHECK(strncmp("abcx", "abcy", 3) == 0);
+  CHECK(sign(strncmp("abcx", "abcy", 4)) == -1);
+  CHECK(strncmp("a", "b", 0) == 0);
+  CHECK(strncmp("ab", "ab\0x", 10) == 0);
+
+  CHECK(strcasecmp("Hello", "hELLO") == 0);
+  CHECK(sign(strcasecmp("apple", "Banana")) == -1);
+  CHECK(strncasecmp("ABCdef", "abcXYZ", 3) == 0);
+  CHECK(sign(strncasecmp("ABCdef", "abcXYZ", 4)) == -1);
+
+  CHECK(memcmp("abc", "abd", 2) == 0);
+  CHECK(sign(memcmp("abc", "abd", 3)) == -1);
+  CHECK(bcmp("abc", "abc", 3) == 0 && bcmp("abc", "abd", 3) != 0);
+}
+
+static void check_copy(void) {
+  char buf[16];
+
+  CHECK(strcpy(buf, "hello") == buf && strcmp(buf, "hello") == 0);
+  CHECK(stpcpy(buf, "hey") == buf + 3 && strcmp(buf, "hey") == 0);
+
+  memset(buf, 'x', sizeof(buf));
+  CHECK(strncpy(buf, "ab", 5) == buf);
+  CHECK(memcmp(buf, "ab\0\0\0x", 6) == 0);
+
+  memset(buf, 'x', sizeof(buf));
+  CHECK(strncpy(buf, "abcdef", 3) == buf && memcmp(buf, "abcx", 4) == 0);
+
+  memset(buf, 'x', sizeof(buf));
+  CHECK(stpncpy(buf, "ab", 5) == buf + 2);
+  CHECK(stpncpy(buf, "abcdef", 3) == buf + 3);
+
+  strcpy(buf, "foo");
+  CHECK(strcat(buf, "bar") == buf && strcmp(buf, "foobar") == 0);
+  CHECK(strncat(buf, "bazqux", 3) == buf && strcmp(buf, "foobarbaz") == 0);
+
+  CHECK(mempcpy(buf, "12345", 5) == buf + 5 && memcmp(buf, "12345", 5) == 0);
+
+  CHECK(strlcpy(buf, "short", sizeof(buf)) == 5 && strcmp(buf, "short") == 0);
+  CHECK(strlcpy(buf, "this is far too long", 8) == 20);
+  CHECK(strcmp(buf, "this is") == 0);
+  CHECK(strlcpy(buf, "untouched", 0) == 9 && strcmp(buf, "this is") == 0);
+
+  strcpy(buf, "abc");
+  CHECK(strlcat(buf, "defgh", 6) == 8 && strcmp(buf, "abcde") == 0);
+  CHECK(strlcat(buf, "xyz", 3) == 6 && strcmp(buf, "abcde") == 0);
+
+  char *dup = strdup("duplicate");
+  CHECK(dup != NULL && strcmp(dup, "duplicate") == 0);
+  free(dup);
+
+  dup = strndup("duplicate", 3);
+  CHECK(dup != NULL && strcmp(dup, "dup") == 0);
+  free(dup);
+
+  dup = strndup("ab", 10);
+  CHECK(dup != NULL && strcmp(dup, "ab") == 0);
+  free(dup);
+}
+
+static void check_search(void) {
+  const char *s = "hello, world";
+
+  CHECK(strlen(s) == 12 && strlen("") == 0);
+  CHECK(strnlen(s, 5) == 5 && strnlen(s, 100) == 12);
+
+  CHECK(strchr(s, 'o') == s + 4);
+  CHECK(strchr(s, 'z') == NULL);
+  CHECK(strchr(s, '\0') == s + 12);
+  CHECK(strrchr(s, 'o') == s + 8);
+  CHECK(strrchr(s, 'z') == NULL);
+  CHECK(strrchr(s, '\0') == s + 12);
+  CHECK(strchrnul(s, 'w') == s + 7);
+  CHECK(strchrnul(s, 'z') == s + 12);
+
+  CHECK(memchr(s, 'l', 12) == s + 2);
+  CHECK(memchr(s, 'l', 2) == NULL);
+  CHECK(memchr("a\0b", 'b', 3) != NULL);
+  CHECK(memchr(s, 'l', (size_t)-1) == s + 2);
+  CHECK(memrchr(s, 'l', 12) == s + 10);
+  CHECK(memrchr(s, 'h', 0) == NULL);
+
+  CHECK(strstr(s, "world") == s + 7);
+  CHECK(strstr(s, "") == s);
+  CHECK(strstr(s, "worlds") == NULL);
+  CHECK(strstr("aaab", "aab") != NULL);
+
+  CHECK(memmem(s, 12, "lo,", 3) == s + 3);
+  CHECK(memmem(s, 12, "", 0) == s);
+  CHECK(memmem(s, 3, "lo", 2) == NULL);
+  CHECK(memmem("a\0bc", 4, "\0b", 2) != NULL);
+
+  CHECK(strspn("aabbcd", "ab") == 4);
+  CHECK(strspn("xyz", "ab") == 0);
+  CHECK(strcspn("hello", "lo") == 2);
+  CHECK(strcspn("hello", "") == 5);
+  CHECK(strpbrk(s, "rw") == s + 7);
+  CHECK(strpbrk(s, "xyz") == NULL);
+}
+
+static void check_tokens(void) {
+  char buf[32];
+  strcpy(buf, ",,a,b,,c,");
+
+  char *save;
+  CHECK(strcmp(strtok_r(buf, ",", &save), "a") == 0);
+  CHECK(strcmp(strtok_r(NULL, ",", &save), "b") == 0);
+  CHECK(strcmp(strtok_r(NULL, ",", &save), "c") == 0);
+  CHECK(strtok_r(NULL, ",", &save) == NULL);
+  CHECK(strtok_r(NULL, ",", &save) == NULL);
+
+  strcpy(buf, "one two  three");
+  CHECK(strcmp(strtok(buf, " "), "one") == 0);
+  CHECK(strcmp(strtok(NULL, " "), "two") == 0);
+  CHECK(strcmp(strtok(NULL, " "), "three") == 0);
+  CHECK(strtok(NULL, " ") == NULL);
+
+  strcpy(buf, "a,,b");
+  char *rest = buf;
+  CHECK(strcmp(strsep(&rest, ","), "a") == 0);
+  CHECK(strcmp(strsep(&rest, ","), "") == 0);
+  CHECK(strcmp(strsep(&rest, ","), "b") == 0);
+  CHECK(rest == NULL);
+  CHECK(strsep(&rest, ",") == NULL);
+}
+
+static void check_misc(void) {
+  CHECK(ffs(0) == 0 && ffs(1) == 1 && ffs(0x80) == 8);
+  CHECK(ffs((int)0x80000000u) == 32);
+  CHECK(ffsl(1L << 40) == 41);
+  CHECK(ffsll(1LL << 63) == 64);
+
+  CHECK(strcmp(strsignal(SIGINT), "Interrupt") == 0);
+  CHECK(strcmp(strsignal(SIGSEGV), "Segmentation fault") == 0);
+  CHECK(strcmp(strsignal(SIGRTMIN + 2), "Real-time signal 2") == 0);
+  CHECK(strcmp(strsignal(0), "Unknown signal 0") == 0);
+  CHECK(strcmp(strsignal(1000), "Unknown signal 1000") == 0);
+}
+
+int main(void) {
+  check_compare();
+  check_copy();
+  check_search();
+  check_tokens();
+  check_misc();
+
+  return check_status();
+}
@@ -0,0 +1,76 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <sched.h>
+#include <stdio.h>
+#include <sys/auxv.h>
+#include <sys/resource.h>
+#include <sys/statfs.h>
+#include <sys/sysinfo.h>
+#include <unistd.h>
+
+#include "check.h"
+
+int main(void) {
+  const long page_size = sysconf(_SC_PAGESIZE);
+  CHECK(page_size == (long)getauxval(AT_PAGESZ));
+  CHECK(page_size == getpagesize());
+  CHECK(sysconf(_SC_PAGE_SIZE) == page_size);
+
+  const long online = sysconf(_SC_NPROCESSORS_ONLN);
+  const long configured = sysconf(_SC_NPROCESSORS_CONF);
+  CHECK(online >= 1);
+  CHECK(configured >= online);
+  CHECK(get_nprocs() == online);
+  CHECK(get_nprocs_conf() == configured);
+
+  // can't run on more CPUs than are online
+  cpu_set_t affinity;
+  CHECK(sched_getaffinity(0, sizeof(affinity), &affinity) == 0);
+  CHECK(CPU_COUNT(&affinity) >= 1 && CPU_COUNT(&affinity) <= online);
+
+  struct rlimit files;
+  CHECK(getrlimit(RLIMIT_NOFILE, &files) == 0);
+  CHECK(sysconf(_SC_OPEN_MAX) == (files.rlim_cur == RLIM_INFINITY
+                                      ? -1
+                                      : (long)files.rlim_cur));
+
+  // lowering the soft limit shows up in sysconf
+  struct rlimit lowered = files;
+  lowered.rlim_cur = 64;
+  CHECK(setrlimit(RLIMIT_NOFILE, &lowered) == 0);
+  CHECK(sysconf(_SC_OPEN_MAX) == 64);
+  CHECK(setrlimit(RLIMIT_NOFILE, &files) == 0);
+
+  CHECK(sysconf(_SC_ARG_MAX) >= 131072);
+  CHECK(sysconf(_SC_CLK_TCK) == (long)getauxval(AT_CLKTCK));
+  CHECK(sysconf(_SC_LINE_MAX) >= 2048);
+  CHECK(sysconf(_SC_PHYS_PAGES) > 0);
+  CHECK(sysconf(_SC_AVPHYS_PAGES) > 0);
+  CHECK(sysconf(_SC_AVPHYS_PAGES) <= sysconf(_SC_PHYS_PAGES));
+
+  struct sysinfo info;
+  CHECK(sysinfo(&info) == 0);
+  CHECK(info.mem_unit >= 1);
+
+  errno = 0;
+  CHECK(sysconf(-1) == -1);
+  CHECK(errno == EINVAL);
+
+  struct statfs tmp_fs;
+  CHECK(statfs("/tmp", &tmp_fs) == 0);
+  CHECK(pathconf("/tmp", _PC_NAME_MAX) == tmp_fs.f_namelen);
+  CHECK(pathconf("/tmp", _PC_PATH_MAX) == 4096);
+  CHECK(pathconf("/tmp", _PC_LINK_MAX) >= 8);
+
+  errno = 0;
+  CHECK(pathconf("/nonexistent/kns-sysconf-test", _PC_NAME_MAX) == -1);
+  CHECK(errno == ENOENT);
+
+  CHECK(fpathconf(STDOUT_FILENO, _PC_PIPE_BUF) == 4096);
+
+  errno = 0;
+  CHECK(fpathconf(STDOUT_FILENO, -1) == -1);
+  CHECK(errno == EINVAL);
+
+  return check_status();
+}
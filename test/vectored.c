@@ -0,0 +1,174 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/sendfile.h>
+#include <sys/stat.h>
+#include <sys/uio.h>
+#include <unistd.h>
+
+#include "check.h"
+
+#define SRC_PATH "/tmp/kns-vectored-test-src"
+#define DST_PATH "/tmp/kns-vectored-test-dst"
+
+static int has_contents(int fd, const char *expected) {
+  char buf[64] = {0};
+  size_t len = strlen(expected);
+
+  return pread(fd, buf, sizeof(buf), 0) == (ssize_t)len &&
+         memcmp(buf, expected, len) == 0;
+}
+
+static void check_stdio_flushing(void) {
+  // bigger than any stream buffer, so it skips the buffer but still has to
+  // come out after what's already in it
+  size_t big_len = 1 << 20;
+  char *big = malloc(big_len);
+  CHECK(big != NULL);
+  memset(big, 'b', big_len);
+
+  FILE *f = fopen(DST_PATH, "w");
+  CHECK(f != NULL);
+  CHECK(fputs("head", f) >= 0);
+  CHECK(fwrite(big, 1, big_len, f) == big_len);
+  CHECK(fputs("tail", f) >= 0);
+  CHECK(fclose(f) == 0);
+
+  int fd = open(DST_PATH, O_RDONLY);
+  struct stat st;
+  CHECK(fstat(fd, &st) == 0 && st.st_size == (off_t)(big_len + 8));
+
+  char edge[4];
+  CHECK(pread(fd, edge, 4, 0) == 4 && memcmp(edge, "head", 4) == 0);
+  CHECK(pread(fd, edge, 4, 4) == 4 && memcmp(edge, "bbbb", 4) == 0);
+  CHECK(pread(fd, edge, 4, big_len) == 4 && memcmp(edge, "bbbb", 4) == 0);
+  CHECK(pread(fd, edge, 4, big_len + 4) == 4 && memcmp(edge, "tail", 4) == 0);
+  close(fd);
+
+  free(big);
+}
+
+static void check_stdio_write_error(void) {
+  // a write that fails outright keeps what was buffered before it
+  size_t big_len = 1 << 20;
+  char *big = malloc(big_len);
+  CHECK(big != NULL);
+  memset(big, 'b', big_len);
+
+  int p[2];
+  CHECK(pipe(p) == 0);
+  CHECK(fcntl(p[0], F_SETFL, O_NONBLOCK) == 0);
+  CHECK(fcntl(p[1], F_SETFL, O_NONBLOCK) == 0);
+
+  char path[32];
+  snprintf(path, sizeof(path), "/proc/self/fd/%d", p[1]);
+  FILE *f = fopen(path, "w");
+  CHECK(f != NULL);
+  CHECK(fcntl(fileno(f), F_SETFL, O_NONBLOCK) == 0);
+
+  // fill the pipe so that nothing more gets in
+  while (write(p[1], big, big_len) > 0) {
+  }
+
+  CHECK(fputs("head", f) >= 0);
+  CHECK(fwrite(big, 1, big_len, f) < big_len);
+
+  while (read(p[0], big, big_len) > 0) {
+  }
+
+  clearerr(f);
+  CHECK(fflush(f) == 0);
+  CHECK(read(p[0], big, big_len) == 4 && memcmp(big, "head", 4) == 0);
+
+  CHECK(fclose(f) == 0);
+  close(p[0]);
+  close(p[1]);
+  free(big);
+}
+
+int main(void) {
+  int src = open(SRC_PATH, O_RDWR | O_CREAT | O_TRUNC, 0644);
+  int dst = open(DST_PATH, O_RDWR | O_CREAT | O_TRUNC, 0644);
+  CHECK(src >= 0 && dst >= 0);
+
+  // readv and writev
+  char hello[] = "hello, ";
+  char world[] = "world";
+  struct iovec out[] = {{hello, 7}, {NULL, 0}, {world, 5}};
+  CHECK(writev(src, out, 3) == 12);
+  CHECK(has_contents(src, "hello, world"));
+
+  char a[5] = {0};
+  char b[7] = {0};
+  struct iovec in[] = {{a, 5}, {b, 7}};
+  CHECK(lseek(src, 0, SEEK_SET) == 0);
+  CHECK(readv(src, in, 2) == 12);
+  CHECK(memcmp(a, "hello", 5) == 0 && memcmp(b, ", world", 7) == 0);
+  CHECK(readv(-1, in, 2) == -1 && errno == EBADF);
+
+  // the positional calls leave the offset alone
+  CHECK(lseek(src, 3, SEEK_SET) == 3);
+  CHECK(pwrite(src, "J", 1, 0) == 1);
+
+  char c;
+  CHECK(pread(src, &c, 1, 7) == 1 && c == 'w');
+  CHECK(lseek(src, 0, SEEK_CUR) == 3);
+
+  struct iovec one[] = {{a, 5}};
+  CHECK(preadv(src, one, 1, 0) == 5 && memcmp(a, "Jello", 5) == 0);
+  CHECK(pwritev(src, out, 1, 12) == 7);
+  CHECK(has_contents(src, "Jello, worldhello, "));
+  CHECK(lseek(src, 0, SEEK_CUR) == 3);
+
+  // an offset of -1 means the current one, which moves
+  CHECK(preadv2(src, one, 1, -1, 0) == 5 && memcmp(a, "lo, w", 5) == 0);
+  CHECK(lseek(src, 0, SEEK_CUR) == 8);
+  CHECK(pwritev2(src, one, 1, 0, 0) == 5);
+  CHECK(has_contents(src, "lo, w, worldhello, "));
+
+  // copying between files
+  off_t in_offset = 0;
+  CHECK(copy_file_range(src, &in_offset, dst, NULL, 5, 0) == 5);
+  CHECK(in_offset == 5);
+  CHECK(has_contents(dst, "lo, w"));
+
+  in_offset = 7;
+  CHECK(sendfile(dst, src, &in_offset, 5) == 5);
+  CHECK(in_offset == 12);
+  CHECK(has_contents(dst, "lo, wworld"));
+
+  // through pipes
+  int p[2];
+  int q[2];
+  CHECK(pipe(p) == 0 && pipe(q) == 0);
+
+  in_offset = 12;
+  CHECK(splice(src, &in_offset, p[1], NULL, 5, 0) == 5);
+  CHECK(tee(p[0], q[1], 5, 0) == 5);
+  CHECK(splice(p[0], NULL, dst, NULL, 5, SPLICE_F_MOVE) == 5);
+  CHECK(has_contents(dst, "lo, wworldhello"));
+
+  char teed[5];
+  CHECK(read(q[0], teed, 5) == 5 && memcmp(teed, "hello", 5) == 0);
+
+  struct iovec gift[] = {{world, 5}};
+  CHECK(vmsplice(p[1], gift, 1, 0) == 5);
+  CHECK(read(p[0], teed, 5) == 5 && memcmp(teed, "world", 5) == 0);
+
+  close(p[0]);
+  close(p[1]);
+  close(q[0]);
+  close(q[1]);
+  close(src);
+  close(dst);
+
+  check_stdio_flushing();
+  check_stdio_write_error();
+
+  unlink(SRC_PATH);
+  unlink(DST_PATH);
+
+  return check_status();
+}